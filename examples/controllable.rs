@@ -1,4 +1,3 @@
-#[macro_use]
 extern crate korome;
 
 use korome::*;
@@ -7,55 +6,48 @@ fn main() {
     // Create the `Graphics` object, which creates a window with the given title and dimensions
     let graphics = Graphics::new("korome works!", 800, 600).unwrap();
 
-    // Load a texture, whose bytes have been loaded at compile-time
-    let planet = include_texture!(graphics, "assets/planet.png").unwrap();
+    // Load a texture through the asset manager, which reloads it when the file changes
+    let planet = graphics.texture("examples/assets/planet.png").unwrap();
+
+    // Bind the controls, which could also be loaded from a file with `InputMap::load`
+    let controls = InputMap::new()
+        .with_axis("move_x", VirtualKeyCode::A, VirtualKeyCode::D)
+        .with_axis("move_x", VirtualKeyCode::Left, VirtualKeyCode::Right)
+        .with_axis("move_y", VirtualKeyCode::S, VirtualKeyCode::W)
+        .with_axis("move_y", VirtualKeyCode::Down, VirtualKeyCode::Up)
+        .with_axis("rotate", VirtualKeyCode::E, VirtualKeyCode::Q);
 
     run_until_closed(graphics, Controllable{
-        tex: &planet,
+        tex: planet,
+        controls,
         x: -400.,
         y: 300.,
         theta: 0.,
     })
 }
 
-struct Controllable<'a>{
+struct Controllable{
     x: f32,
     y: f32,
     theta: f32,
-    tex: &'a Texture
+    tex: Handle<Texture>,
+    controls: InputMap
 }
 
-impl<'a> Game for Controllable<'a>{
+impl Game for Controllable{
     type ReturnType = ();
     fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) {
         let delta = info.delta;
         let vel = 200.0 * delta;
 
         // Make the planet move with WASD and the arrow keys and rotate with Q and E
-        is_down!{info;
-            A, Left => {
-                self.x -= vel
-            },
-            D, Right => {
-                self.x += vel
-            },
-            S, Down => {
-                self.y -= vel
-            },
-            W, Up => {
-                self.y += vel
-            },
-            Q => {
-                self.theta += delta
-            },
-            E => {
-                self.theta -= delta
-            }
-        }
+        self.x += vel * info.axis(&self.controls, "move_x");
+        self.y += vel * info.axis(&self.controls, "move_y");
+        self.theta += delta * info.axis(&self.controls, "rotate");
 
         drawer.clear(0., 0., 0.);
 
-        self.tex.drawer()
+        self.tex.get().drawer()
             .pos((self.x, self.y))
             .rotation(self.theta)
             .draw(drawer);
@@ -1,62 +1,121 @@
-#[macro_use]
 extern crate korome;
 
 use korome::*;
 
-use State::*;
-enum State {
-    Menu, InGame
+struct Textures {
+    planet: Texture,
+    start_game: Texture,
+    quit_game: Texture,
+}
+
+// Checks whether a button of 200x55 at `pos` was clicked this frame
+fn clicked(info: &FrameInfo, (x, y): (f32, f32)) -> bool {
+    let (mx, my) = info.mousepos;
+
+    info.get_mouse_events().contains(&(true, MouseButton::Left)) &&
+        (mx - x).abs() <= 100. && (my - y).abs() <= 27.5
 }
 
 fn main() {
     let graphics = Graphics::new("Menu example!", 800, 600).unwrap();
 
-    let planet = Texture::from_file(&graphics, "examples/assets/planet.png").unwrap();
-    let start_game = Texture::from_file(&graphics, "examples/assets/start_game.png").unwrap();
-    let quit_game = Texture::from_file(&graphics, "examples/assets/quit_game.png").unwrap();
+    // Cloning textures is cheap, so every scene can have its own
+    let textures = Textures {
+        planet: Texture::from_file(&graphics, "examples/assets/planet.png").unwrap(),
+        start_game: Texture::from_file(&graphics, "examples/assets/start_game.png").unwrap(),
+        quit_game: Texture::from_file(&graphics, "examples/assets/quit_game.png").unwrap(),
+    };
 
-    let mut state = Menu;
-    let mut pos = (0., 0.);
+    run_until_closed(graphics, SceneStack::new(Box::new(Menu(textures))));
+}
+
+struct Menu(Textures);
 
-    run_until_closed(graphics, |info: &FrameInfo, drawer: &mut Drawer|{
+impl Scene for Menu {
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> SceneUpdate {
         drawer.clear(0., 0., 0.);
 
-        match state{
-            Menu => {
-                start_game.drawer().pos((0., 35.)).draw(drawer);
-                quit_game.drawer().pos((0., -35.)).draw(drawer);
-
-                for &e in info.get_mouse_events(){
-                    if let (true, MouseButton::Left) = e{
-                        match info.mousepos{
-                            (-100. ... 100., 10. ... 65.) => state = InGame,
-                            (-100. ... 100., -65. ... -10.) => return GameUpdate::Close,
-                            _ => ()
-                        }
-                    }
-                }
+        self.0.start_game.drawer().pos((0., 35.)).draw(drawer);
+        self.0.quit_game.drawer().pos((0., -35.)).draw(drawer);
+
+        if clicked(info, (0., 35.)) {
+            SceneUpdate::Replace(Box::new(InGame {
+                pos: (0., 0.),
+                textures: Textures {
+                    planet: self.0.planet.clone(),
+                    start_game: self.0.start_game.clone(),
+                    quit_game: self.0.quit_game.clone(),
+                },
+            }))
+        } else if clicked(info, (0., -35.)) {
+            SceneUpdate::Pop
+        } else {
+            SceneUpdate::Nothing
+        }
+    }
+}
+
+struct InGame {
+    pos: (f32, f32),
+    textures: Textures,
+}
+
+impl Scene for InGame {
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> SceneUpdate {
+        let vel = 100. * info.delta;
+
+        is_down!{info;
+            A, Left => {
+                self.pos.0 -= vel
             },
-            InGame => {
-                planet.drawer().pos(pos.into()).draw(drawer);
-
-                let vel = 100. * info.delta;
-
-                is_down!{info;
-                    A, Left => {
-                        pos.0 -= vel
-                    },
-                    D, Right => {
-                        pos.0 += vel
-                    },
-                    S, Down => {
-                        pos.1 -= vel
-                    },
-                    W, Up => {
-                        pos.1 += vel
-                    }
-                }
+            D, Right => {
+                self.pos.0 += vel
+            },
+            S, Down => {
+                self.pos.1 -= vel
+            },
+            W, Up => {
+                self.pos.1 += vel
             }
         }
-        GameUpdate::Nothing
-    });
+
+        self.draw(drawer);
+
+        if info.get_key_events().contains(&(true, VirtualKeyCode::Escape)) {
+            SceneUpdate::Push(Box::new(Pause {
+                resume: self.textures.start_game.clone(),
+                quit: self.textures.quit_game.clone(),
+            }))
+        } else {
+            SceneUpdate::Nothing
+        }
+    }
+    // Keeps the game visible behind the pause menu
+    fn draw(&mut self, drawer: &mut Drawer) {
+        drawer.clear(0., 0., 0.);
+        self.textures.planet.drawer().pos(self.pos).draw(drawer);
+    }
+}
+
+struct Pause {
+    resume: Texture,
+    quit: Texture,
+}
+
+impl Scene for Pause {
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> SceneUpdate {
+        self.resume.drawer().pos((0., 35.)).colour([1., 1., 1., 0.8]).draw(drawer);
+        self.quit.drawer().pos((0., -35.)).colour([1., 1., 1., 0.8]).draw(drawer);
+
+        if clicked(info, (0., 35.)) || info.get_key_events().contains(&(true, VirtualKeyCode::Escape)) {
+            SceneUpdate::Pop
+        } else if clicked(info, (0., -35.)) {
+            GameUpdate::Close.into()
+        } else {
+            SceneUpdate::Nothing
+        }
+    }
+    fn is_overlay(&self) -> bool {
+        true
+    }
 }
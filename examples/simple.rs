@@ -1,4 +1,3 @@
-#[macro_use]
 extern crate korome;
 
 use korome::*;
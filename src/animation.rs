@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use super::{Texture, TextureDrawer};
+
+/// A texture sliced into frames, e.g. for animations
+#[derive(Debug, Clone)]
+pub struct SpriteSheet{
+    frames: Vec<Texture>,
+    names: HashMap<String, usize>
+}
+
+impl SpriteSheet{
+    /// Slices the texture into a grid of frames of the given size.
+    ///
+    /// The frames are numbered from left to right, top to bottom, see `grid_frames`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame width or height is zero.
+    pub fn grid(texture: &Texture, frame_width: u32, frame_height: u32) -> Self{
+        let (width, height) = texture.get_size();
+        let frames = SpriteSheet::grid_frames((width as u32, height as u32), frame_width, frame_height)
+            .into_iter()
+            .map(|(x, y, width, height)| texture.sub_texture(x, y, width, height))
+            .collect();
+
+        SpriteSheet{
+            frames,
+            names: HashMap::new()
+        }
+    }
+    /// Returns the rectangles `grid` slices a texture of the given size into,
+    /// as `(x, y, width, height)` in pixels from the top left corner.
+    ///
+    /// The frames are numbered from left to right, top to bottom.
+    /// Space left over at the right and bottom edges is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame width or height is zero.
+    pub fn grid_frames((width, height): (u32, u32), frame_width: u32, frame_height: u32) -> Vec<(u32, u32, u32, u32)>{
+        assert!(frame_width > 0 && frame_height > 0, "sprite sheet frames can't be {}x{}", frame_width, frame_height);
+        let (columns, rows) = (width / frame_width, height / frame_height);
+
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column * frame_width, row * frame_height, frame_width, frame_height)))
+            .collect()
+    }
+    /// Slices the texture into named frames.
+    ///
+    /// Each rectangle is given as `(x, y, width, height)` in pixels from the top left corner.
+    /// The frames are numbered in the order they're given.
+    pub fn from_frames<S, I>(texture: &Texture, frames: I) -> Self
+    where S: Into<String>, I: IntoIterator<Item=(S, (u32, u32, u32, u32))> {
+        let mut sheet = SpriteSheet{
+            frames: Vec::new(),
+            names: HashMap::new()
+        };
+
+        for (name, (x, y, width, height)) in frames {
+            sheet.names.insert(name.into(), sheet.frames.len());
+            sheet.frames.push(texture.sub_texture(x, y, width, height));
+        }
+
+        sheet
+    }
+
+    #[inline]
+    /// Returns the amount of frames in the sheet
+    pub fn len(&self) -> usize{
+        self.frames.len()
+    }
+    #[inline]
+    /// Checks whether the sheet has no frames
+    pub fn is_empty(&self) -> bool{
+        self.frames.is_empty()
+    }
+    #[inline]
+    /// Returns the frame with the given number
+    pub fn get(&self, frame: usize) -> Option<&Texture>{
+        self.frames.get(frame)
+    }
+    #[inline]
+    /// Returns the number of the frame with the given name
+    pub fn index_of(&self, name: &str) -> Option<usize>{
+        self.names.get(name).cloned()
+    }
+    #[inline]
+    /// Returns the frame with the given name
+    pub fn get_named(&self, name: &str) -> Option<&Texture>{
+        self.index_of(name).and_then(|i| self.get(i))
+    }
+}
+
+/// How an `Animation` continues after its last frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlayMode{
+    /// Starts over from the first frame
+    Loop,
+    /// Plays the frames backwards, then forwards again, and so on
+    PingPong,
+    /// Stops at the last frame
+    Once
+}
+
+/// A sequence of sprite sheet frames, each shown for its own duration
+#[derive(Debug, Clone)]
+pub struct Animation{
+    frames: Vec<(usize, f32)>,
+    mode: PlayMode
+}
+
+impl Animation{
+    #[inline]
+    /// Creates an empty animation with the given play mode
+    pub fn new(mode: PlayMode) -> Self{
+        Animation{
+            frames: Vec::new(),
+            mode
+        }
+    }
+    /// Creates an animation where every frame lasts the same amount of seconds
+    pub fn uniform<I: IntoIterator<Item=usize>>(frames: I, frame_duration: f32, mode: PlayMode) -> Self{
+        Animation{
+            frames: frames.into_iter().map(|frame| (frame, frame_duration)).collect(),
+            mode
+        }
+    }
+    #[inline]
+    /// Adds a frame of the sprite sheet that will be shown for `duration` seconds
+    pub fn frame(mut self, frame: usize, duration: f32) -> Self{
+        self.frames.push((frame, duration));
+        self
+    }
+    #[inline]
+    /// Returns the play mode of the animation
+    pub fn mode(&self) -> PlayMode{
+        self.mode
+    }
+}
+
+/// Keeps track of which frame of an `Animation` to show
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer{
+    animation: Animation,
+    current: usize,
+    time: f32,
+    forwards: bool,
+    finished: bool
+}
+
+impl AnimationPlayer{
+    #[inline]
+    /// Creates a player starting at the first frame of the animation
+    pub fn new(animation: Animation) -> Self{
+        AnimationPlayer{
+            animation,
+            current: 0,
+            time: 0.,
+            forwards: true,
+            finished: false
+        }
+    }
+    #[inline]
+    /// Switches to another animation and starts it from the beginning
+    pub fn play(&mut self, animation: Animation){
+        *self = AnimationPlayer::new(animation);
+    }
+    #[inline]
+    /// Starts the animation over from the first frame
+    pub fn restart(&mut self){
+        self.current = 0;
+        self.time = 0.;
+        self.forwards = true;
+        self.finished = false;
+    }
+    #[inline]
+    /// Returns the animation being played
+    pub fn animation(&self) -> &Animation{
+        &self.animation
+    }
+    #[inline]
+    /// Checks whether a `PlayMode::Once` animation has reached its end
+    pub fn is_finished(&self) -> bool{
+        self.finished
+    }
+    #[inline]
+    /// Returns the number of the sprite sheet frame that should be shown
+    pub fn current_frame(&self) -> usize{
+        self.animation.frames.get(self.current).map(|&(frame, _)| frame).unwrap_or(0)
+    }
+
+    /// Advances the animation by `delta` seconds (e.g. `FrameInfo::delta`)
+    pub fn update(&mut self, delta: f32){
+        // An animation without any duration would never get past this loop
+        if self.finished || self.animation.frames.iter().all(|&(_, duration)| duration <= 0.) {
+            return
+        }
+        self.time += delta;
+
+        while self.time >= self.animation.frames[self.current].1 {
+            self.time -= self.animation.frames[self.current].1;
+            self.step();
+            if self.finished {
+                self.time = 0.;
+                break
+            }
+        }
+    }
+
+    fn step(&mut self){
+        let last = self.animation.frames.len() - 1;
+
+        match self.animation.mode {
+            PlayMode::Loop => self.current = if self.current < last {self.current + 1} else {0},
+            PlayMode::Once => if self.current < last {
+                self.current += 1
+            } else {
+                self.finished = true
+            },
+            PlayMode::PingPong => {
+                if last == 0 {
+                    return
+                }
+                if self.forwards && self.current == last || !self.forwards && self.current == 0 {
+                    self.forwards = !self.forwards;
+                }
+                if self.forwards {
+                    self.current += 1
+                } else {
+                    self.current -= 1
+                }
+            }
+        }
+    }
+
+    #[inline]
+    /// Returns an object for drawing the current frame from the sprite sheet
+    ///
+    /// # Panics
+    /// Panics if the current frame isn't in the sprite sheet
+    pub fn drawer<'a>(&self, sheet: &'a SpriteSheet) -> TextureDrawer<'a>{
+        sheet.get(self.current_frame())
+            .expect("animation frame is not in the sprite sheet")
+            .drawer()
+    }
+}
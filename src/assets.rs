@@ -0,0 +1,537 @@
+use glium::Display;
+use image::{self, RgbaImage, ImageError};
+
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::{Texture, TextureError};
+
+/// A cheap handle to an asset, which can be kept in game structs without borrowing anything
+///
+/// Clones refer to the same asset, and see it change when it's reloaded.
+#[derive(Debug)]
+pub struct Handle<T>{
+    asset: Rc<RefCell<T>>
+}
+
+impl<T> Clone for Handle<T>{
+    #[inline]
+    fn clone(&self) -> Self{
+        Handle{
+            asset: self.asset.clone()
+        }
+    }
+}
+
+impl<T> Handle<T>{
+    #[inline]
+    /// Makes a handle to an asset that isn't loaded from a file, e.g. a texture made in code
+    pub fn new(asset: T) -> Self{
+        Handle{
+            asset: Rc::new(RefCell::new(asset))
+        }
+    }
+    #[inline]
+    /// Borrows the asset, e.g. `handle.get().drawer().draw(drawer)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the asset is being replaced.
+    pub fn get(&self) -> Ref<'_, T>{
+        self.asset.borrow()
+    }
+    #[inline]
+    /// Swaps the asset for another one for all clones of this handle, returning the old asset
+    pub fn replace(&self, asset: T) -> T{
+        self.asset.replace(asset)
+    }
+    #[inline]
+    /// Checks whether two handles refer to the same asset
+    pub fn ptr_eq(&self, other: &Handle<T>) -> bool{
+        Rc::ptr_eq(&self.asset, &other.asset)
+    }
+}
+
+// How often files are checked for changes while hot reloading
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+// A texture loaded from a file and when the file was last changed
+#[derive(Debug)]
+struct Entry{
+    handle: Handle<Texture>,
+    modified: Option<SystemTime>,
+    // Whether the file is still being decoded on a worker thread
+    loading: bool
+}
+
+// An image decoded on a worker thread
+type Decoded = (PathBuf, Result<RgbaImage, ImageError>);
+
+// Decodes images on worker threads, which stop when it's dropped
+#[derive(Debug)]
+struct Loader{
+    jobs: Sender<PathBuf>,
+    decoded: Receiver<Decoded>
+}
+
+impl Loader{
+    fn new() -> Self{
+        Loader::with_decoder(|path| image::open(path).map(|img| img.to_rgba()))
+    }
+
+    // Every queued path is sent back exactly once, even if decoding it panics
+    fn with_decoder(decode: fn(&Path) -> Result<RgbaImage, ImageError>) -> Self{
+        let (jobs, queue) = channel::<PathBuf>();
+        let (done, decoded) = channel();
+        let queue = Arc::new(Mutex::new(queue));
+        let workers = thread::available_parallelism().map_or(2, |n| n.get()).clamp(1, 4);
+
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                // The queue is only locked while waiting, so the workers decode at the same time
+                let next = || queue.lock().ok().and_then(|queue| queue.recv().ok());
+                while let Some(path) = next() {
+                    let image = panic::catch_unwind(|| decode(&path))
+                        .unwrap_or_else(|_| Err(ImageError::FormatError("decoding panicked".to_owned())));
+                    if done.send((path, image)).is_err() {
+                        break
+                    }
+                }
+            });
+        }
+
+        Loader{
+            jobs,
+            decoded
+        }
+    }
+}
+
+// How many textures have been queued since nothing was loading, and how many of them are done
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Progress{
+    queued: usize,
+    finished: usize
+}
+
+impl Progress{
+    fn queue(&mut self){
+        self.queued += 1;
+    }
+    // For a texture forgotten while loading, which won't finish anymore
+    fn forget(&mut self){
+        self.queued -= 1;
+        self.reset_if_done();
+    }
+    fn finish(&mut self){
+        self.finished += 1;
+        self.reset_if_done();
+    }
+    // Starts counting again from nothing once everything queued is done
+    fn reset_if_done(&mut self){
+        if !self.is_loading() {
+            *self = Progress::default();
+        }
+    }
+    fn is_loading(&self) -> bool{
+        self.finished < self.queued
+    }
+    fn fraction(&self) -> f32{
+        match self.queued {
+            0 => 1.,
+            queued => self.finished as f32 / queued as f32
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime>{
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Whether a file modified at `now` has changed since it was loaded, not counting files that are gone
+fn changed(loaded: Option<SystemTime>, now: Option<SystemTime>) -> bool{
+    now.is_some() && now != loaded
+}
+
+/// Loads textures by path into `Handle`s, caching them so each file is only loaded once
+///
+/// Every `Graphics` has one, which `Graphics::texture` and `Graphics::texture_async` load through.
+///
+/// Textures loaded with `texture_async` are decoded on worker threads and only uploaded to the GPU
+/// on the main thread by `update`, which `run_until_closed` calls every frame,
+/// so a loading screen can keep drawing with `progress` while a level loads.
+/// When a game is recorded or replayed, `run` has `update` wait for all of them instead,
+/// as which frame a texture is ready on would otherwise depend on the worker threads' timing.
+///
+/// With hot reloading on, which it is by default in debug builds, `run_until_closed` checks
+/// the files twice a second and reloads any texture whose file has changed in place,
+/// so changes to the art show up without restarting the game.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// use korome::*;
+///
+/// struct Planet{
+///     texture: Handle<Texture>
+/// }
+///
+/// impl Game for Planet{
+///     type ReturnType = ();
+///     fn frame(&mut self, _: &FrameInfo, drawer: &mut Drawer){
+///         drawer.clear(0., 0., 0.);
+///         self.texture.get().drawer().draw(drawer);
+///     }
+/// }
+///
+/// # fn main(){
+/// let graphics = Graphics::new("Planet", 800, 600).unwrap();
+/// let texture = graphics.texture("planet.png").unwrap();
+///
+/// run_until_closed(graphics, Planet{texture});
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Assets{
+    textures: RefCell<HashMap<PathBuf, Entry>>,
+    hot_reload: Cell<bool>,
+    last_check: Cell<Option<Instant>>,
+    // Started by the first asynchronous load
+    loader: RefCell<Option<Loader>>,
+    // What handles of textures still loading hold, a transparent pixel
+    placeholder: RefCell<Option<Texture>>,
+    progress: Cell<Progress>,
+    errors: RefCell<Vec<(PathBuf, TextureError)>>
+}
+
+impl Default for Assets{
+    fn default() -> Self{
+        Assets{
+            textures: RefCell::new(HashMap::new()),
+            hot_reload: Cell::new(cfg!(debug_assertions)),
+            last_check: Cell::new(None),
+            loader: RefCell::new(None),
+            placeholder: RefCell::new(None),
+            progress: Cell::new(Progress::default()),
+            errors: RefCell::new(Vec::new())
+        }
+    }
+}
+
+impl Assets{
+    #[inline]
+    /// Creates an empty asset manager
+    pub fn new() -> Self{
+        Assets::default()
+    }
+
+    // The same file should be cached once however its path is written
+    fn key(path: &Path) -> PathBuf{
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+    }
+
+    /// Returns a handle to the texture in a file, loading it if it hasn't been loaded yet
+    ///
+    /// If the texture is still being loaded asynchronously, the handle holds the placeholder until it's done.
+    pub fn texture<P: AsRef<Path>>(&self, display: &Display, path: P) -> Result<Handle<Texture>, TextureError>{
+        let path = Assets::key(path.as_ref());
+        if let Some(entry) = self.textures.borrow().get(&path) {
+            return Ok(entry.handle.clone())
+        }
+
+        let modified = modified(&path);
+        let handle = Handle::new(Texture::from_file(display, &path)?);
+        self.textures.borrow_mut().insert(path, Entry{
+            handle: handle.clone(),
+            modified,
+            loading: false
+        });
+        Ok(handle)
+    }
+    /// Returns a handle to the texture in a file right away, decoding the file on a worker thread
+    /// if it hasn't been loaded yet.
+    ///
+    /// The handle holds a transparent 1x1 placeholder until `update` uploads the texture.
+    /// If loading fails the placeholder stays, and the error can be had from `take_errors`.
+    pub fn texture_async<P: AsRef<Path>>(&self, display: &Display, path: P) -> Result<Handle<Texture>, TextureError>{
+        let path = Assets::key(path.as_ref());
+        if let Some(entry) = self.textures.borrow().get(&path) {
+            return Ok(entry.handle.clone())
+        }
+
+        let placeholder = match *self.placeholder.borrow_mut() {
+            Some(ref texture) => texture.clone(),
+            ref mut placeholder => placeholder.insert(Texture::new(display, RgbaImage::new(1, 1))?).clone()
+        };
+        let handle = Handle::new(placeholder);
+
+        let mut loader = self.loader.borrow_mut();
+        // Sending only fails once every worker has exited, in which case fresh ones are started
+        if loader.as_ref().map_or(true, |loader| loader.jobs.send(path.clone()).is_err()) {
+            let fresh = loader.insert(Loader::new());
+            let _ = fresh.jobs.send(path.clone());
+        }
+
+        self.update_progress(Progress::queue);
+        self.textures.borrow_mut().insert(path, Entry{
+            handle: handle.clone(),
+            modified: None,
+            loading: true
+        });
+        Ok(handle)
+    }
+    /// Checks whether the texture in a file has been loaded, or is being loaded asynchronously
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool{
+        self.textures.borrow().contains_key(&Assets::key(path.as_ref()))
+    }
+    /// Checks whether the texture in a file has been loaded and isn't being loaded asynchronously anymore
+    pub fn is_loaded<P: AsRef<Path>>(&self, path: P) -> bool{
+        self.textures.borrow().get(&Assets::key(path.as_ref())).is_some_and(|entry| !entry.loading)
+    }
+    #[inline]
+    /// Checks whether any textures are being loaded asynchronously
+    pub fn is_loading(&self) -> bool{
+        self.progress.get().is_loading()
+    }
+    /// Returns how far the asynchronous loads are, from `0.` to `1.`, e.g. for a loading bar
+    ///
+    /// Counts all the textures queued since nothing was last loading, failed ones counting as done.
+    /// It's `1.` when nothing is loading.
+    pub fn progress(&self) -> f32{
+        self.progress.get().fraction()
+    }
+    fn update_progress<F: FnOnce(&mut Progress)>(&self, f: F){
+        let mut progress = self.progress.get();
+        f(&mut progress);
+        self.progress.set(progress);
+    }
+    /// Returns the errors of asynchronous loads and hot reloads by `update` that have failed since it was last called
+    pub fn take_errors(&self) -> Vec<(PathBuf, TextureError)>{
+        self.errors.replace(Vec::new())
+    }
+    #[inline]
+    /// Returns how many textures have been loaded
+    pub fn len(&self) -> usize{
+        self.textures.borrow().len()
+    }
+    #[inline]
+    /// Checks whether no textures have been loaded
+    pub fn is_empty(&self) -> bool{
+        self.textures.borrow().is_empty()
+    }
+    /// Forgets a texture, so it's loaded again the next time it's asked for,
+    /// returning whether it was loaded.
+    ///
+    /// Handles to it keep working, but aren't reloaded anymore,
+    /// nor given the texture if it was still loading asynchronously.
+    pub fn remove<P: AsRef<Path>>(&self, path: P) -> bool{
+        match self.textures.borrow_mut().remove(&Assets::key(path.as_ref())) {
+            Some(entry) => {
+                if entry.loading {
+                    self.update_progress(Progress::forget);
+                }
+                true
+            }
+            None => false
+        }
+    }
+    #[inline]
+    /// Forgets all the textures
+    pub fn clear(&self){
+        self.textures.borrow_mut().clear();
+        self.progress.set(Progress::default());
+    }
+
+    #[inline]
+    /// Sets whether the run loop reloads textures whose files change
+    pub fn set_hot_reload(&self, hot_reload: bool){
+        self.hot_reload.set(hot_reload);
+    }
+    #[inline]
+    /// Checks whether the run loop reloads textures whose files change
+    pub fn hot_reload(&self) -> bool{
+        self.hot_reload.get()
+    }
+
+    /// Reloads every texture whose file has changed since it was loaded,
+    /// returning the paths that were reloaded and whether reloading them worked.
+    ///
+    /// A texture that fails to reload, e.g. because the file is only half written, keeps its old image
+    /// and is tried again when the file next changes.
+    pub fn reload_changed(&self, display: &Display) -> Vec<(PathBuf, Result<(), TextureError>)>{
+        let mut textures = self.textures.borrow_mut();
+        textures.iter_mut()
+            .filter_map(|(path, entry)| {
+                let now = modified(path);
+                if entry.loading || !changed(entry.modified, now) {
+                    return None
+                }
+                entry.modified = now;
+
+                let result = Texture::from_file(display, path).map(|texture| {
+                    entry.handle.replace(texture);
+                });
+                Some((path.clone(), result))
+            })
+            .collect()
+    }
+    /// Uploads the textures that have finished decoding on worker threads into their handles,
+    /// and every so often reloads textures whose files have changed if hot reloading is on
+    ///
+    /// Textures that fail to load or reload are left out, with their errors kept for `take_errors`.
+    ///
+    /// `run_until_closed` calls this at the start of every frame.
+    pub fn update(&self, display: &Display){
+        self.finish_loads(display, false);
+
+        if !self.hot_reload.get() || self.last_check.get().is_some_and(|t| t.elapsed() < CHECK_INTERVAL) {
+            return
+        }
+        self.last_check.set(Some(Instant::now()));
+
+        let failed = self.reload_changed(display).into_iter()
+            .filter_map(|(path, result)| result.err().map(|e| (path, e)));
+        self.errors.borrow_mut().extend(failed);
+    }
+    /// Waits for all the asynchronous loads to finish and uploads them, like `update` does for those that are done
+    pub fn wait_for_loads(&self, display: &Display){
+        self.finish_loads(display, true);
+    }
+    fn finish_loads(&self, display: &Display, wait: bool){
+        loop {
+            let decoded = match *self.loader.borrow() {
+                // Only stops waiting early if the workers are gone
+                Some(ref loader) if wait && self.is_loading() => loader.decoded.recv().ok(),
+                Some(ref loader) => loader.decoded.try_recv().ok(),
+                None => return
+            };
+            match decoded {
+                Some((path, image)) => self.finish_load(display, path, image),
+                None => return
+            }
+        }
+    }
+    fn finish_load(&self, display: &Display, path: PathBuf, image: Result<RgbaImage, ImageError>){
+        let mut textures = self.textures.borrow_mut();
+        // Textures forgotten while loading don't count as done, as they aren't counted as queued anymore
+        let entry = match textures.get_mut(&path) {
+            Some(entry) if entry.loading => entry,
+            _ => return
+        };
+        entry.loading = false;
+        entry.modified = modified(&path);
+        self.update_progress(Progress::finish);
+
+        match image.map_err(TextureError::from).and_then(|image| Texture::new(display, image)) {
+            Ok(texture) => {
+                entry.handle.replace(texture);
+            }
+            Err(e) => self.errors.borrow_mut().push((path, e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use std::env;
+    use std::fs::File;
+
+    #[test]
+    fn paths_to_the_same_file_share_a_key(){
+        let dir = env::temp_dir().join("korome_asset_keys");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        File::create(dir.join("tile.png")).unwrap();
+
+        let key = Assets::key(&dir.join("tile.png"));
+        assert_eq!(Assets::key(&dir.join("sub").join("..").join("tile.png")), key);
+        assert_eq!(Assets::key(&dir.join(".").join("tile.png")), key);
+        assert!(key.is_absolute());
+        // Missing files are kept as they are written, to fail when loaded
+        assert_eq!(Assets::key(Path::new("missing/../tile.png")), Path::new("missing/../tile.png"));
+    }
+
+    #[test]
+    fn only_files_that_changed_are_reloaded(){
+        let then = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let later = then + Duration::from_secs(1);
+
+        assert!(!changed(Some(then), Some(then)));
+        assert!(changed(Some(then), Some(later)));
+        // Changing back to an older time counts too, e.g. after a file is restored from a backup
+        assert!(changed(Some(later), Some(then)));
+        // A file that couldn't be looked at when it was loaded changes once it can
+        assert!(changed(None, Some(then)));
+        // Deleted files keep their texture
+        assert!(!changed(Some(then), None));
+        assert!(!changed(None, None));
+    }
+
+    #[test]
+    fn progress_counts_loads_until_all_are_done(){
+        let mut progress = Progress::default();
+        assert!(!progress.is_loading());
+        assert_eq!(progress.fraction(), 1.);
+
+        for _ in 0..4 {
+            progress.queue();
+        }
+        progress.finish();
+        assert!(progress.is_loading());
+        assert_eq!(progress.fraction(), 0.25);
+
+        // A texture forgotten while loading isn't waited for anymore
+        progress.forget();
+        assert_eq!(progress.fraction(), 1. / 3.);
+        progress.finish();
+        progress.queue();
+        assert_eq!((progress.queued, progress.finished), (4, 2));
+
+        progress.finish();
+        progress.finish();
+        // Once everything is done it starts over, so the next batch counts from zero
+        assert_eq!(progress, Progress::default());
+        assert_eq!(progress.fraction(), 1.);
+        progress.queue();
+        assert_eq!(progress.fraction(), 0.);
+
+        // Forgetting the last one that's loading finishes the batch too
+        progress.forget();
+        assert!(!progress.is_loading());
+        assert_eq!(progress, Progress::default());
+    }
+
+    #[test]
+    fn loads_that_panic_still_finish(){
+        let loader = Loader::with_decoder(|path| match path.to_str() {
+            Some("panics") => panic!("broken decoder"),
+            _ => Ok(RgbaImage::new(1, 1))
+        });
+        let mut progress = Progress::default();
+        // More than there are workers, so one that panicked has to carry on
+        for path in &["panics", "a", "panics", "b", "panics", "c", "panics", "d", "panics"] {
+            loader.jobs.send(PathBuf::from(path)).unwrap();
+            progress.queue();
+        }
+
+        let mut failed = 0;
+        while progress.is_loading() {
+            let (path, image) = loader.decoded.recv_timeout(Duration::from_secs(5)).expect("a load was lost");
+            assert_eq!(image.is_err(), path == Path::new("panics"));
+            failed += image.is_err() as usize;
+            progress.finish();
+        }
+        assert_eq!(failed, 5);
+    }
+}
@@ -0,0 +1,269 @@
+use image;
+use image::{RgbaImage, GenericImage};
+
+use glium::Display;
+
+use std::path::Path;
+use std::collections::{HashMap, HashSet};
+
+use super::{Texture, TextureError};
+
+// Packs rectangles into rows ("shelves") starting from the top left corner
+#[derive(Debug)]
+pub struct ShelfPacker{
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    shelf_height: u32
+}
+
+impl ShelfPacker{
+    pub fn new(width: u32, height: u32) -> Self{
+        ShelfPacker{
+            width,
+            height,
+            x: 0,
+            y: 0,
+            shelf_height: 0
+        }
+    }
+    // Finds room for a rectangle, returning its top left corner
+    pub fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)>{
+        if width > self.width {
+            return None
+        }
+        if width > self.width - self.x {
+            self.y += self.shelf_height;
+            self.x = 0;
+            self.shelf_height = 0;
+        }
+        if height > self.height - self.y {
+            return None
+        }
+
+        let pos = (self.x, self.y);
+        self.x += width;
+        self.shelf_height = self.shelf_height.max(height);
+        Some(pos)
+    }
+    #[inline]
+    pub fn used_height(&self) -> u32{
+        self.y + self.shelf_height
+    }
+    #[inline]
+    pub fn clear(&mut self){
+        *self = ShelfPacker::new(self.width, self.height);
+    }
+}
+
+/// Packs many images into one `TextureAtlas` using the builder pattern
+pub struct AtlasBuilder{
+    images: Vec<(String, RgbaImage)>,
+    padding: u32
+}
+
+impl AtlasBuilder{
+    #[inline]
+    /// Creates a new `AtlasBuilder` with one pixel of padding between images
+    pub fn new() -> Self{
+        AtlasBuilder{
+            images: Vec::new(),
+            padding: 1
+        }
+    }
+    #[inline]
+    /// Sets the amount of transparent pixels between the packed images
+    pub fn padding(self, padding: u32) -> Self{
+        AtlasBuilder{
+            padding,
+            .. self
+        }
+    }
+    #[inline]
+    /// Adds an image to the atlas under the given name
+    pub fn add<S: Into<String>>(mut self, name: S, image: RgbaImage) -> Self{
+        self.images.push((name.into(), image));
+        self
+    }
+    #[inline]
+    /// Adds an image from a PNG-encoded byte slice to the atlas under the given name
+    pub fn add_png_bytes<S: Into<String>>(self, name: S, bytes: &[u8]) -> Result<Self, TextureError>{
+        image::load_from_memory_with_format(bytes, image::PNG)
+            .map(|img| self.add(name, img.to_rgba()))
+            .map_err(From::from)
+    }
+    #[inline]
+    /// Adds an image from a file to the atlas under the given name
+    pub fn add_file<S: Into<String>, P: AsRef<Path>>(self, name: S, path: P) -> Result<Self, TextureError>{
+        image::open(path)
+            .map(|img| self.add(name, img.to_rgba()))
+            .map_err(From::from)
+    }
+
+    /// Packs all the added images into one texture and uploads it
+    ///
+    /// Fails with `TextureError::DuplicateName` if two images have the same name,
+    /// and with `TextureError::TooBig` if they don't fit in a texture the graphics card can handle.
+    pub fn build(self, display: &Display) -> Result<TextureAtlas, TextureError>{
+        let AtlasBuilder{mut images, padding} = self;
+        if let Some(name) = duplicate_name(&images) {
+            return Err(TextureError::DuplicateName(name.to_owned()))
+        }
+        // Packing the tallest images first wastes less space
+        images.sort_by_key(|img| ::std::cmp::Reverse(img.1.height()));
+
+        let padded = |img: &RgbaImage| (img.width().saturating_add(padding), img.height().saturating_add(padding));
+        let sizes: Vec<_> = images.iter().map(|img| padded(&img.1)).collect();
+        let width = atlas_width(&sizes);
+
+        let mut packer = ShelfPacker::new(width, u32::MAX);
+        let positions: Option<Vec<_>> = sizes.iter().map(|&(w, h)| packer.pack(w, h)).collect();
+        let height = packer.used_height().max(1);
+
+        // glium doesn't tell the largest texture size, but the largest viewport is as big on the drivers around
+        let (max_width, max_height) = display.get_max_viewport_dimensions();
+        let positions = match positions {
+            Some(positions) if width <= max_width && height <= max_height => positions,
+            _ => return Err(TextureError::TooBig(width, height))
+        };
+
+        let mut atlas = RgbaImage::new(width, height);
+        for ((_, img), &(x, y)) in images.iter().zip(&positions) {
+            atlas.copy_from(img, x, y);
+        }
+
+        let texture = Texture::new(display, atlas)?;
+        let sprites = images.into_iter()
+            .zip(positions)
+            .map(|((name, img), (x, y))| {
+                let sprite = texture.sub_texture(x, y, img.width(), img.height());
+                (name, sprite)
+            })
+            .collect();
+
+        Ok(TextureAtlas{
+            texture,
+            sprites
+        })
+    }
+}
+
+// The width to pack images of the given sizes into, so the atlas is about square
+// but still fits the widest of them
+fn atlas_width(sizes: &[(u32, u32)]) -> u32{
+    let area: u64 = sizes.iter().map(|&(w, h)| u64::from(w) * u64::from(h)).sum();
+    let widest = sizes.iter().map(|size| size.0).max().unwrap_or(1);
+    ((area as f64).sqrt() as u32).checked_next_power_of_two().unwrap_or(u32::MAX).max(widest)
+}
+
+// Returns a name given to more than one image
+fn duplicate_name(images: &[(String, RgbaImage)]) -> Option<&str>{
+    let mut names = HashSet::new();
+    images.iter().map(|(name, _)| &**name).find(|&name| !names.insert(name))
+}
+
+impl Default for AtlasBuilder{
+    /// Same as `AtlasBuilder::new()`
+    fn default() -> Self{
+        AtlasBuilder::new()
+    }
+}
+
+/// Many images packed into one texture
+///
+/// Each image can be drawn as its own `Texture`,
+/// and as they all share one texture on the GPU, drawing them can be batched together.
+#[derive(Debug)]
+pub struct TextureAtlas{
+    texture: Texture,
+    sprites: HashMap<String, Texture>
+}
+
+impl TextureAtlas{
+    #[inline]
+    /// Returns the texture of the image with the given name
+    pub fn get(&self, name: &str) -> Option<&Texture>{
+        self.sprites.get(name)
+    }
+    #[inline]
+    /// Returns the whole atlas texture
+    pub fn texture(&self) -> &Texture{
+        &self.texture
+    }
+    #[inline]
+    /// Returns an iterator over the names and textures of all the images in the atlas
+    pub fn iter(&self) -> ::std::collections::hash_map::Iter<'_, String, Texture>{
+        self.sprites.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // Whether two rectangles given as `(x, y, width, height)` overlap
+    fn overlap(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool{
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn packed_rectangles_stay_apart_and_in_bounds(){
+        let mut packer = ShelfPacker::new(64, 64);
+        let sizes = [(20, 10), (30, 8), (20, 12), (40, 5), (10, 10), (64, 3), (5, 20)];
+
+        let rects: Vec<_> = sizes.iter()
+            .map(|&(w, h)| {
+                let (x, y) = packer.pack(w, h).unwrap();
+                (x, y, w, h)
+            })
+            .collect();
+
+        for (i, &a) in rects.iter().enumerate() {
+            assert!(a.0 + a.2 <= 64 && a.1 + a.3 <= 64, "{:?} is out of bounds", a);
+            for &b in &rects[i+1..] {
+                assert!(!overlap(a, b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+        // The next shelf starts below the tallest rectangle of the one before
+        assert_eq!(rects[2], (0, 10, 20, 12));
+        assert_eq!(rects[4], (0, 22, 10, 10));
+        assert_eq!(packer.used_height(), rects.last().map(|r| r.1 + r.3).unwrap());
+    }
+
+    #[test]
+    fn rectangles_that_dont_fit_are_refused(){
+        let mut packer = ShelfPacker::new(32, 16);
+        assert_eq!(packer.pack(33, 1), None);
+        assert_eq!(packer.pack(1, 17), None);
+
+        assert_eq!(packer.pack(32, 10), Some((0, 0)));
+        assert_eq!(packer.pack(8, 7), None);
+        assert_eq!(packer.pack(8, 6), Some((0, 10)));
+
+        packer.clear();
+        assert_eq!(packer.pack(32, 16), Some((0, 0)));
+        assert_eq!(packer.used_height(), 16);
+    }
+
+    #[test]
+    fn duplicate_names_are_found(){
+        let image = || RgbaImage::new(1, 1);
+        let images = vec![("a".to_owned(), image()), ("b".to_owned(), image()), ("a".to_owned(), image())];
+        assert_eq!(duplicate_name(&images), Some("a"));
+        assert_eq!(duplicate_name(&images[..2]), None);
+    }
+
+    #[test]
+    fn huge_images_dont_overflow(){
+        // Together their area doesn't fit in a `u32`
+        assert_eq!(atlas_width(&[(70_000, 70_000), (70_000, 70_000)]), 131_072);
+        assert_eq!(atlas_width(&[(u32::MAX, u32::MAX)]), u32::MAX);
+        assert_eq!(atlas_width(&[]), 1);
+
+        let mut packer = ShelfPacker::new(u32::MAX, u32::MAX);
+        assert_eq!(packer.pack(u32::MAX - 1, 1), Some((0, 0)));
+        assert_eq!(packer.pack(2, 1), Some((0, 1)));
+        assert_eq!(packer.pack(1, u32::MAX), None);
+    }
+}
@@ -0,0 +1,1010 @@
+use lewton::VorbisError;
+use lewton::inside_ogg::OggStreamReader;
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write, BufReader, BufWriter, Cursor};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+quick_error! {
+    /// Wraps together errors that can occur decoding a `Sound` or `Music`
+    #[derive(Debug)]
+    pub enum AudioError{
+        /// The data is well-formed but isn't audio korome understands
+        InvalidAudio(reason: &'static str){
+            description(reason)
+            display("invalid audio: {}", reason)
+        }
+        /// A `VorbisError` from an OGG Vorbis file
+        VorbisError(err: VorbisError){
+            from()
+            cause(err)
+            description(err.description())
+        }
+        /// An `io::Error` from reading the file
+        IoError(err: io::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+    }
+}
+
+// The sample formats of WAV files that can be decoded
+#[derive(Debug, Clone, Copy)]
+enum SampleFormat{
+    U8,
+    I16,
+    I24,
+    I32,
+    F32
+}
+
+impl SampleFormat{
+    fn bytes(self) -> usize{
+        match self {
+            SampleFormat::U8 => 1,
+            SampleFormat::I16 => 2,
+            SampleFormat::I24 => 3,
+            SampleFormat::I32 | SampleFormat::F32 => 4
+        }
+    }
+    fn decode(self, b: &[u8]) -> f32{
+        match self {
+            SampleFormat::U8 => (f32::from(b[0]) - 128.) / 128.,
+            SampleFormat::I16 => f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.,
+            SampleFormat::I24 => (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.,
+            SampleFormat::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.,
+            SampleFormat::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        }
+    }
+}
+
+// Something audio is decoded from bit by bit
+trait Decoder: Send{
+    fn channels(&self) -> usize;
+    fn sample_rate(&self) -> u32;
+    // Adds some more interleaved samples to `out`, returning how many, or 0 at the end
+    fn read(&mut self, out: &mut Vec<f32>) -> Result<usize, AudioError>;
+    // Goes back to the start
+    fn rewind(&mut self) -> Result<(), AudioError>;
+}
+
+// How many frames a `WavDecoder` decodes at once
+const WAV_CHUNK: usize = 4096;
+
+struct WavDecoder<R>{
+    reader: R,
+    format: SampleFormat,
+    channels: usize,
+    sample_rate: u32,
+    data_start: u64,
+    data_len: u64,
+    remaining: u64
+}
+
+impl<R: Read + Seek> WavDecoder<R>{
+    fn new(mut reader: R) -> Result<Self, AudioError>{
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != b"RIFF" || &header[8..] != b"WAVE" {
+            return Err(AudioError::InvalidAudio("not a RIFF WAVE file"))
+        }
+
+        let mut fmt = None;
+        loop {
+            let mut chunk = [0; 8];
+            if reader.read_exact(&mut chunk).is_err() {
+                return Err(AudioError::InvalidAudio("no data chunk"))
+            }
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+
+            match &chunk[..4] {
+                b"fmt " => {
+                    // Even WAVE_FORMAT_EXTENSIBLE only needs 40 bytes, so anything much bigger is broken
+                    if size > 256 {
+                        return Err(AudioError::InvalidAudio("fmt chunk is too long"))
+                    }
+                    let mut data = vec![0; size as usize];
+                    reader.read_exact(&mut data)?;
+                    if size % 2 == 1 {
+                        reader.seek(SeekFrom::Current(1))?;
+                    }
+                    fmt = Some(parse_fmt(&data)?);
+                }
+                b"data" => {
+                    let (format, channels, sample_rate) = fmt.ok_or(AudioError::InvalidAudio("data chunk before fmt chunk"))?;
+                    let data_start = reader.stream_position()?;
+                    let data_len = u64::from(size);
+                    return Ok(WavDecoder{
+                        reader,
+                        format,
+                        channels,
+                        sample_rate,
+                        data_start,
+                        data_len,
+                        remaining: data_len
+                    })
+                }
+                _ => {
+                    reader.seek(SeekFrom::Current(i64::from(size) + i64::from(size % 2)))?;
+                }
+            }
+        }
+    }
+}
+
+fn parse_fmt(data: &[u8]) -> Result<(SampleFormat, usize, u32), AudioError>{
+    if data.len() < 16 {
+        return Err(AudioError::InvalidAudio("fmt chunk is too short"))
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i+1]]);
+    let mut tag = u16_at(0);
+    let channels = usize::from(u16_at(2));
+    let sample_rate = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let bits = u16_at(14);
+
+    // WAVE_FORMAT_EXTENSIBLE keeps the real format at the start of its sub-format GUID
+    if tag == 0xFFFE && data.len() >= 26 {
+        tag = u16_at(24);
+    }
+    let format = match (tag, bits) {
+        (1, 8) => SampleFormat::U8,
+        (1, 16) => SampleFormat::I16,
+        (1, 24) => SampleFormat::I24,
+        (1, 32) => SampleFormat::I32,
+        (3, 32) => SampleFormat::F32,
+        _ => return Err(AudioError::InvalidAudio("only 8, 16, 24 and 32-bit PCM and 32-bit float WAV files are supported"))
+    };
+    if channels == 0 || sample_rate == 0 {
+        return Err(AudioError::InvalidAudio("no channels or a sample rate of zero"))
+    }
+    Ok((format, channels, sample_rate))
+}
+
+impl<R: Read + Seek + Send> Decoder for WavDecoder<R>{
+    fn channels(&self) -> usize{
+        self.channels
+    }
+    fn sample_rate(&self) -> u32{
+        self.sample_rate
+    }
+    fn read(&mut self, out: &mut Vec<f32>) -> Result<usize, AudioError>{
+        let sample_bytes = self.format.bytes();
+        let frame_bytes = (sample_bytes * self.channels) as u64;
+        let len = (self.remaining / frame_bytes).min(WAV_CHUNK as u64) * frame_bytes;
+
+        let mut bytes = vec![0; len as usize];
+        // A file cut short just ends early
+        let mut read = 0;
+        while read < bytes.len() {
+            match self.reader.read(&mut bytes[read..])? {
+                0 => break,
+                n => read += n
+            }
+        }
+        let read = read - read % frame_bytes as usize;
+        self.remaining = if read == bytes.len() { self.remaining - len } else { 0 };
+
+        let format = self.format;
+        out.extend(bytes[..read].chunks(sample_bytes).map(|b| format.decode(b)));
+        Ok(read / sample_bytes)
+    }
+    fn rewind(&mut self) -> Result<(), AudioError>{
+        self.reader.seek(SeekFrom::Start(self.data_start))?;
+        self.remaining = self.data_len;
+        Ok(())
+    }
+}
+
+struct OggDecoder<R: Read + Seek>{
+    // Only `None` while rewinding
+    reader: Option<OggStreamReader<R>>,
+    start: u64,
+    channels: usize,
+    sample_rate: u32
+}
+
+impl<R: Read + Seek> OggDecoder<R>{
+    fn new(mut reader: R) -> Result<Self, AudioError>{
+        let start = reader.stream_position()?;
+        let reader = OggStreamReader::new(reader)?;
+        Ok(OggDecoder{
+            channels: usize::from(reader.ident_hdr.audio_channels),
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            reader: Some(reader),
+            start
+        })
+    }
+}
+
+impl<R: Read + Seek + Send> Decoder for OggDecoder<R>{
+    fn channels(&self) -> usize{
+        self.channels
+    }
+    fn sample_rate(&self) -> u32{
+        self.sample_rate
+    }
+    fn read(&mut self, out: &mut Vec<f32>) -> Result<usize, AudioError>{
+        let reader = match self.reader {
+            Some(ref mut reader) => reader,
+            None => return Ok(0)
+        };
+        loop {
+            match reader.read_dec_packet_itl()? {
+                // Some packets don't make any samples
+                Some(ref packet) if packet.is_empty() => (),
+                Some(packet) => {
+                    out.extend(packet.iter().map(|&s| f32::from(s) / 32768.));
+                    return Ok(packet.len())
+                }
+                None => return Ok(0)
+            }
+        }
+    }
+    fn rewind(&mut self) -> Result<(), AudioError>{
+        // Seeking to the start would land on the headers and decode them as audio,
+        // so the stream is read again from the top instead
+        if let Some(reader) = self.reader.take() {
+            let mut inner = reader.into_inner().into_inner();
+            inner.seek(SeekFrom::Start(self.start))?;
+            self.reader = Some(OggStreamReader::new(inner)?);
+        }
+        Ok(())
+    }
+}
+
+// Picks a decoder by the first bytes of the data
+fn open<'a, R: Read + Seek + Send + 'a>(mut reader: R) -> Result<Box<dyn Decoder + 'a>, AudioError>{
+    let start = reader.stream_position()?;
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    let decoder: Box<dyn Decoder> = match &magic {
+        b"RIFF" => Box::new(WavDecoder::new(reader)?),
+        b"OggS" => Box::new(OggDecoder::new(reader)?),
+        _ => return Err(AudioError::InvalidAudio("only WAV and OGG Vorbis are supported"))
+    };
+    if decoder.channels() == 0 {
+        return Err(AudioError::InvalidAudio("no channels"))
+    }
+    Ok(decoder)
+}
+
+// Reads one stereo frame out of interleaved samples, mono sounds are played in both ears
+// and anything past the first two channels is dropped
+#[inline]
+fn frame(samples: &[f32], channels: usize, i: usize) -> (f32, f32){
+    let s = &samples[i * channels..];
+    if channels == 1 { (s[0], s[0]) } else { (s[0], s[1]) }
+}
+
+#[inline]
+fn lerp((a, b): (f32, f32), (c, d): (f32, f32), t: f32) -> (f32, f32){
+    (a + (c - a) * t, b + (d - b) * t)
+}
+
+// How much of a voice goes to the left and right, `pan` being from `-1.` to `1.`
+#[inline]
+fn pan_gains(volume: f32, pan: f32) -> (f32, f32){
+    let pan = pan.clamp(-1., 1.);
+    (volume * (1. - pan).min(1.), volume * (1. + pan).min(1.))
+}
+
+#[derive(Debug)]
+struct SoundData{
+    samples: Vec<f32>,
+    channels: usize,
+    sample_rate: u32
+}
+
+/// A short sound decoded entirely into memory, e.g. for sound effects
+///
+/// Clones share the same samples, so they are cheap.
+#[derive(Debug, Clone)]
+pub struct Sound{
+    data: Arc<SoundData>
+}
+
+impl Sound{
+    /// Decodes a WAV or OGG Vorbis file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, AudioError>{
+        Sound::decode(BufReader::new(File::open(path)?))
+    }
+    /// Decodes a WAV or OGG Vorbis file from its bytes, e.g. from `include_bytes!`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AudioError>{
+        Sound::decode(Cursor::new(bytes))
+    }
+    /// Creates a sound from interleaved samples from `-1.` to `1.`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no channels or the samples don't divide evenly into them.
+    pub fn from_samples(samples: Vec<f32>, channels: usize, sample_rate: u32) -> Self{
+        assert!(channels > 0 && samples.len() % channels == 0, "samples don't fit {} channels", channels);
+        Sound{
+            data: Arc::new(SoundData{
+                samples,
+                channels,
+                sample_rate
+            })
+        }
+    }
+    fn decode<R: Read + Seek + Send>(reader: R) -> Result<Self, AudioError>{
+        let mut decoder = open(reader)?;
+        let mut samples = Vec::new();
+        while decoder.read(&mut samples)? > 0 {}
+        Ok(Sound::from_samples(samples, decoder.channels(), decoder.sample_rate()))
+    }
+
+    #[inline]
+    /// Returns the interleaved samples
+    pub fn samples(&self) -> &[f32]{
+        &self.data.samples
+    }
+    #[inline]
+    /// Returns how many channels the sound has
+    pub fn channels(&self) -> usize{
+        self.data.channels
+    }
+    #[inline]
+    /// Returns how many samples of each channel there are each second
+    pub fn sample_rate(&self) -> u32{
+        self.data.sample_rate
+    }
+    #[inline]
+    /// Returns how many samples each channel has
+    pub fn frames(&self) -> usize{
+        self.data.samples.len() / self.data.channels
+    }
+    #[inline]
+    /// Returns how many seconds the sound lasts when played at normal pitch
+    pub fn duration(&self) -> f32{
+        self.frames() as f32 / self.data.sample_rate as f32
+    }
+
+    #[inline]
+    /// Makes a `SoundPlayer` for playing the sound
+    pub fn player(&self) -> SoundPlayer<'_>{
+        SoundPlayer{
+            sound: self,
+            volume: 1.,
+            pitch: 1.,
+            pan: 0.,
+            looping: false,
+            bus: Bus::Sfx
+        }
+    }
+}
+
+/// Builder for playing a `Sound` on a `Mixer`
+#[derive(Debug, Clone, Copy)]
+#[must_use = "the sound is only played by calling `play`"]
+pub struct SoundPlayer<'a>{
+    sound: &'a Sound,
+    volume: f32,
+    pitch: f32,
+    pan: f32,
+    looping: bool,
+    bus: Bus
+}
+
+impl<'a> SoundPlayer<'a>{
+    set!{/// Sets how loud the sound is, `1.` being as loud as it was recorded
+        fn SoundPlayer; volume: f32}
+    set!{/// Sets how fast the sound is played, `2.` being an octave higher and twice as short
+        fn SoundPlayer; pitch: f32}
+    set!{/// Sets where the sound is, from `-1.` for only the left to `1.` for only the right
+        fn SoundPlayer; pan: f32}
+    set!{/// Sets whether the sound starts over when it ends, until it's stopped
+        fn SoundPlayer; looping: bool}
+    set!{/// Sets which bus the sound is mixed into, `Bus::Sfx` by default
+        fn SoundPlayer; bus: Bus}
+
+    /// Starts playing the sound on the mixer
+    pub fn play(self, mixer: &mut Mixer) -> VoiceId{
+        let id = VoiceId(mixer.next_id);
+        mixer.next_id += 1;
+        mixer.voices.push(Voice{
+            id,
+            sound: self.sound.data.clone(),
+            pos: 0.,
+            volume: self.volume,
+            pitch: self.pitch,
+            pan: self.pan,
+            looping: self.looping,
+            bus: self.bus
+        });
+        id
+    }
+}
+
+/// A piece of music decoded bit by bit while it plays, so long tracks don't have to fit in memory
+pub struct Music{
+    decoder: Box<dyn Decoder>,
+    looping: bool,
+    volume: f32
+}
+
+impl Music{
+    /// Opens a WAV or OGG Vorbis file to be streamed
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AudioError>{
+        Music::new(BufReader::new(File::open(path)?))
+    }
+    /// Opens a WAV or OGG Vorbis file from its bytes, e.g. from `include_bytes!`
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, AudioError>{
+        Music::new(Cursor::new(bytes))
+    }
+    fn new<R: Read + Seek + Send + 'static>(reader: R) -> Result<Self, AudioError>{
+        Ok(Music{
+            decoder: open(reader)?,
+            looping: true,
+            volume: 1.
+        })
+    }
+    set!{/// Sets whether the music starts over when it ends, which it does by default
+        fn Music; looping: bool}
+    set!{/// Sets how loud the music is, `1.` being as loud as it was recorded
+        fn Music; volume: f32}
+}
+
+impl ::std::fmt::Debug for Music{
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result{
+        f.debug_struct("Music")
+            .field("channels", &self.decoder.channels())
+            .field("sample_rate", &self.decoder.sample_rate())
+            .field("looping", &self.looping)
+            .field("volume", &self.volume)
+            .finish()
+    }
+}
+
+/// The buses a `Mixer` mixes sounds through, each with its own volume
+///
+/// Sounds go through either `Sfx` or `Music` and then all of them through `Master`,
+/// so e.g. an options menu can have separate sliders for each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus{
+    /// Everything played
+    Master,
+    /// Sound effects
+    Sfx,
+    /// Background music
+    Music
+}
+
+/// Identifies a sound playing on a `Mixer`, for changing or stopping it while it plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+#[derive(Debug)]
+struct Voice{
+    id: VoiceId,
+    sound: Arc<SoundData>,
+    // In frames of the sound, between them when it isn't played at its own rate
+    pos: f64,
+    volume: f32,
+    pitch: f32,
+    pan: f32,
+    looping: bool,
+    bus: Bus
+}
+
+impl Voice{
+    fn ended(&self) -> bool{
+        !self.looping && self.pos >= (self.sound.samples.len() / self.sound.channels) as f64
+    }
+    // Returns the next stereo frame, or `None` once it's over
+    fn next(&mut self, rate: u32) -> Option<(f32, f32)>{
+        let sound = &*self.sound;
+        let frames = sound.samples.len() / sound.channels;
+        if frames == 0 {
+            return None
+        }
+        if self.pos >= frames as f64 {
+            if !self.looping {
+                return None
+            }
+            self.pos %= frames as f64;
+        }
+
+        let i = self.pos as usize;
+        let t = (self.pos - i as f64) as f32;
+        let j = if i + 1 < frames { i + 1 } else if self.looping { 0 } else { i };
+        let sample = lerp(frame(&sound.samples, sound.channels, i), frame(&sound.samples, sound.channels, j), t);
+
+        self.pos += f64::from(self.pitch.max(0.)) * f64::from(sound.sample_rate) / f64::from(rate);
+        let (l, r) = pan_gains(self.volume, self.pan);
+        Some((sample.0 * l, sample.1 * r))
+    }
+}
+
+// Music that's playing, fading in or fading out
+#[derive(Debug)]
+struct Track{
+    music: Music,
+    // Decoded samples not played yet, with the frame being played first
+    buffer: Vec<f32>,
+    pos: f64,
+    gain: f32,
+    // How much the gain changes each second
+    fade: f32,
+    // Whether nothing was decoded since the music last started over, so empty music doesn't loop forever
+    rewound: bool,
+    ended: bool
+}
+
+impl Track{
+    fn next(&mut self, rate: u32) -> Option<(f32, f32)>{
+        let channels = self.music.decoder.channels();
+        // Two frames are needed to blend between them
+        while !self.ended && self.buffer.len() / channels < self.pos as usize + 2 {
+            match self.music.decoder.read(&mut self.buffer) {
+                Ok(0) if self.music.looping && !self.rewound => {
+                    self.rewound = true;
+                    if self.music.decoder.rewind().is_err() {
+                        self.ended = true;
+                    }
+                }
+                Ok(0) | Err(_) => self.ended = true,
+                Ok(_) => self.rewound = false
+            }
+        }
+
+        let frames = self.buffer.len() / channels;
+        let i = self.pos as usize;
+        if i >= frames {
+            return None
+        }
+        let t = (self.pos - i as f64) as f32;
+        let sample = lerp(frame(&self.buffer, channels, i), frame(&self.buffer, channels, (i + 1).min(frames - 1)), t);
+
+        self.pos += f64::from(self.music.decoder.sample_rate()) / f64::from(rate);
+        // Drops played samples now and then instead of every frame
+        let played = self.pos as usize;
+        if played >= WAV_CHUNK {
+            self.buffer.drain(..played * channels);
+            self.pos -= played as f64;
+        }
+
+        let gain = self.gain * self.music.volume;
+        self.gain = (self.gain + self.fade / rate as f32).clamp(0., 1.);
+        Some((sample.0 * gain, sample.1 * gain))
+    }
+}
+
+/// Mixes sounds and music together into stereo samples
+///
+/// The mixer itself only makes samples, which `render_to` writes to an `AudioBackend`.
+/// For playing them as they are made, `Audio` runs a mixer on its own thread.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// use korome::*;
+///
+/// # fn main(){
+/// let audio = Audio::system(44100).unwrap();
+/// let jump = Sound::load("jump.wav").unwrap();
+///
+/// let mut mixer = audio.mixer();
+/// mixer.play_music(Music::open("theme.ogg").unwrap(), 2.);
+/// mixer.set_volume(Bus::Sfx, 0.8);
+/// jump.player().pitch(1.2).pan(-0.5).play(&mut mixer);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Mixer{
+    sample_rate: u32,
+    voices: Vec<Voice>,
+    next_id: u64,
+    tracks: Vec<Track>,
+    master: f32,
+    sfx: f32,
+    music: f32
+}
+
+impl Mixer{
+    /// Creates a mixer making `sample_rate` stereo samples each second
+    pub fn new(sample_rate: u32) -> Self{
+        Mixer{
+            sample_rate,
+            voices: Vec::new(),
+            next_id: 0,
+            tracks: Vec::new(),
+            master: 1.,
+            sfx: 1.,
+            music: 1.
+        }
+    }
+    #[inline]
+    /// Returns how many stereo samples the mixer makes each second
+    pub fn sample_rate(&self) -> u32{
+        self.sample_rate
+    }
+
+    fn bus_mut(&mut self, bus: Bus) -> &mut f32{
+        match bus {
+            Bus::Master => &mut self.master,
+            Bus::Sfx => &mut self.sfx,
+            Bus::Music => &mut self.music
+        }
+    }
+    /// Sets the volume of a bus, `1.` leaving what goes through it as it is
+    pub fn set_volume(&mut self, bus: Bus, volume: f32){
+        *self.bus_mut(bus) = volume.max(0.);
+    }
+    /// Returns the volume of a bus
+    pub fn volume(&self, bus: Bus) -> f32{
+        match bus {
+            Bus::Master => self.master,
+            Bus::Sfx => self.sfx,
+            Bus::Music => self.music
+        }
+    }
+
+    fn voice_mut(&mut self, id: VoiceId) -> Option<&mut Voice>{
+        self.voices.iter_mut().find(|v| v.id == id)
+    }
+    #[inline]
+    /// Checks whether a sound is still playing
+    pub fn is_playing(&self, id: VoiceId) -> bool{
+        self.voices.iter().any(|v| v.id == id)
+    }
+    #[inline]
+    /// Returns how many sounds are playing
+    pub fn voices(&self) -> usize{
+        self.voices.len()
+    }
+    /// Changes the volume of a playing sound, returning whether it was still playing
+    pub fn set_voice_volume(&mut self, id: VoiceId, volume: f32) -> bool{
+        self.voice_mut(id).map(|v| v.volume = volume).is_some()
+    }
+    /// Changes the pitch of a playing sound, returning whether it was still playing
+    pub fn set_voice_pitch(&mut self, id: VoiceId, pitch: f32) -> bool{
+        self.voice_mut(id).map(|v| v.pitch = pitch).is_some()
+    }
+    /// Changes the pan of a playing sound, returning whether it was still playing
+    pub fn set_voice_pan(&mut self, id: VoiceId, pan: f32) -> bool{
+        self.voice_mut(id).map(|v| v.pan = pan).is_some()
+    }
+    /// Stops a sound, doing nothing if it has already ended
+    pub fn stop(&mut self, id: VoiceId){
+        self.voices.retain(|v| v.id != id);
+    }
+    /// Stops all sounds, but not the music
+    pub fn stop_all(&mut self){
+        self.voices.clear();
+    }
+
+    /// Starts playing music, fading it in over `fade` seconds
+    ///
+    /// Any music already playing fades out over the same time, crossfading into the new music.
+    /// With a `fade` of zero the new music starts right away and the old music stops.
+    pub fn play_music(&mut self, music: Music, fade: f32){
+        self.stop_music(fade);
+        let fade = if fade > 0. { 1. / fade } else { 0. };
+        self.tracks.push(Track{
+            music,
+            buffer: Vec::new(),
+            pos: 0.,
+            gain: if fade > 0. { 0. } else { 1. },
+            fade,
+            rewound: false,
+            ended: false
+        });
+    }
+    /// Fades out the music over `fade` seconds, or stops it right away if `fade` is zero
+    pub fn stop_music(&mut self, fade: f32){
+        if fade > 0. {
+            for track in &mut self.tracks {
+                track.fade = -1. / fade;
+            }
+        } else {
+            self.tracks.clear();
+        }
+    }
+    #[inline]
+    /// Checks whether any music is playing, including music fading out
+    pub fn is_music_playing(&self) -> bool{
+        !self.tracks.is_empty()
+    }
+
+    /// Fills `out` with interleaved stereo samples of everything playing, moving it all forward
+    ///
+    /// Sounds and music that end are removed.
+    pub fn render(&mut self, out: &mut [f32]){
+        let rate = self.sample_rate;
+        let (sfx, music, master) = (self.sfx, self.music, self.master);
+
+        for out in out.chunks_mut(2) {
+            let (mut l, mut r) = (0., 0.);
+            self.voices.retain_mut(|voice| match voice.next(rate) {
+                Some((vl, vr)) => {
+                    let bus = if voice.bus == Bus::Music { music } else if voice.bus == Bus::Sfx { sfx } else { 1. };
+                    l += vl * bus;
+                    r += vr * bus;
+                    !voice.ended()
+                }
+                None => false
+            });
+            self.tracks.retain_mut(|track| match track.next(rate) {
+                Some((tl, tr)) => {
+                    l += tl * music;
+                    r += tr * music;
+                    !(track.fade < 0. && track.gain <= 0.)
+                }
+                None => false
+            });
+
+            out[0] = (l * master).clamp(-1., 1.);
+            if let Some(right) = out.get_mut(1) {
+                *right = (r * master).clamp(-1., 1.);
+            }
+        }
+    }
+    /// Renders `frames` stereo samples and writes them to a backend
+    pub fn render_to<B: AudioBackend + ?Sized>(&mut self, backend: &mut B, frames: usize) -> io::Result<()>{
+        let mut samples = vec![0.; frames * 2];
+        self.render(&mut samples);
+        backend.write(&samples)
+    }
+}
+
+/// Where the samples made by a `Mixer` go
+pub trait AudioBackend{
+    /// Takes interleaved stereo samples from `-1.` to `1.`
+    ///
+    /// Backends playing the samples as they come should block until there's room for more,
+    /// which is what keeps `Audio` from mixing ahead.
+    fn write(&mut self, samples: &[f32]) -> io::Result<()>;
+}
+
+/// A backend throwing the samples away, for running games on machines without a sound card
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullBackend{
+    /// How many stereo samples have been written
+    pub frames: u64
+}
+
+impl AudioBackend for NullBackend{
+    fn write(&mut self, samples: &[f32]) -> io::Result<()>{
+        self.frames += samples.len() as u64 / 2;
+        Ok(())
+    }
+}
+
+/// A backend writing the samples to a 16-bit stereo WAV file, e.g. for testing or recording
+///
+/// This is meant for `Mixer::render_to`, given to `Audio` it would only be filled in real time.
+/// The sizes in the header are filled in by `finish`, or when it's dropped.
+#[derive(Debug)]
+pub struct WavBackend<W: Write + Seek>{
+    writer: Option<W>,
+    data_len: u32
+}
+
+impl WavBackend<BufWriter<File>>{
+    /// Creates a WAV file to write to
+    pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32) -> io::Result<Self>{
+        WavBackend::new(BufWriter::new(File::create(path)?), sample_rate)
+    }
+}
+
+impl<W: Write + Seek> WavBackend<W>{
+    /// Starts a WAV file in a writer
+    pub fn new(mut writer: W, sample_rate: u32) -> io::Result<Self>{
+        writer.write_all(b"RIFF\0\0\0\0WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        // PCM, two channels
+        writer.write_all(&[1, 0, 2, 0])?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * 4).to_le_bytes())?;
+        // 4 bytes a frame, 16 bits a sample
+        writer.write_all(&[4, 0, 16, 0])?;
+        writer.write_all(b"data\0\0\0\0")?;
+
+        Ok(WavBackend{
+            writer: Some(writer),
+            data_len: 0
+        })
+    }
+    /// Fills in the header and returns the writer
+    pub fn finish(mut self) -> io::Result<W>{
+        self.write_header()?;
+        Ok(self.writer.take().unwrap())
+    }
+    fn write_header(&mut self) -> io::Result<()>{
+        if let Some(ref mut writer) = self.writer {
+            writer.seek(SeekFrom::Start(4))?;
+            writer.write_all(&(self.data_len + 36).to_le_bytes())?;
+            writer.seek(SeekFrom::Start(40))?;
+            writer.write_all(&self.data_len.to_le_bytes())?;
+            writer.seek(SeekFrom::End(0))?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> AudioBackend for WavBackend<W>{
+    fn write(&mut self, samples: &[f32]) -> io::Result<()>{
+        let bytes: Vec<u8> = samples.iter().flat_map(|&s| to_i16(s).to_le_bytes()).collect();
+        self.writer.as_mut().unwrap().write_all(&bytes)?;
+        self.data_len += bytes.len() as u32;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Drop for WavBackend<W>{
+    fn drop(&mut self){
+        let _ = self.write_header();
+    }
+}
+
+#[inline]
+fn to_i16(sample: f32) -> i16{
+    (sample.clamp(-1., 1.) * 32767.) as i16
+}
+
+// The programs tried in order for playing raw 16-bit stereo samples, followed by the sample rate
+const PLAY_COMMANDS: &[&[&str]] = &[
+    &["pacat", "--playback", "--raw", "--format=s16le", "--channels=2", "--latency-msec=40", "--rate"],
+    &["aplay", "-q", "-t", "raw", "-f", "S16_LE", "-c", "2", "-B", "40000", "-r"]
+];
+
+/// A backend piping the samples to `pacat` or `aplay`, whichever is installed
+///
+/// Writing blocks while the program has enough samples queued up.
+#[derive(Debug)]
+pub struct CommandBackend{
+    child: Child,
+    stdin: ChildStdin
+}
+
+impl CommandBackend{
+    /// Starts the first program found playing `sample_rate` samples each second
+    pub fn new(sample_rate: u32) -> io::Result<Self>{
+        let rate = sample_rate.to_string();
+        let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no program for playing audio");
+
+        for cmd in PLAY_COMMANDS {
+            let child = Command::new(cmd[0]).args(&cmd[1..]).arg(&rate)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+
+            match child {
+                Ok(mut child) => {
+                    let stdin = child.stdin.take().unwrap();
+                    shrink_pipe(&stdin);
+                    return Ok(CommandBackend{child, stdin})
+                }
+                Err(e) => last_err = e
+            }
+        }
+        Err(last_err)
+    }
+}
+
+// Pipes hold a lot of audio by default, a smaller one keeps the latency down
+#[cfg(target_os = "linux")]
+fn shrink_pipe(stdin: &ChildStdin){
+    use std::os::unix::io::AsRawFd;
+    unsafe {
+        libc::fcntl(stdin.as_raw_fd(), libc::F_SETPIPE_SZ, 4096);
+    }
+}
+#[cfg(not(target_os = "linux"))]
+fn shrink_pipe(_: &ChildStdin){}
+
+impl AudioBackend for CommandBackend{
+    fn write(&mut self, samples: &[f32]) -> io::Result<()>{
+        let bytes: Vec<u8> = samples.iter().flat_map(|&s| to_i16(s).to_le_bytes()).collect();
+        self.stdin.write_all(&bytes)
+    }
+}
+
+impl Drop for CommandBackend{
+    fn drop(&mut self){
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// How many stereo samples `Audio` mixes at once
+const CHUNK: usize = 512;
+// How far `Audio` mixes ahead of the clock for backends that don't block
+const AHEAD: f64 = 0.05;
+
+/// Plays a `Mixer` through a backend on its own thread
+///
+/// The mixer is locked with `mixer` to play things on it, which should be kept short
+/// as the audio thread waits for it meanwhile.
+/// The thread stops when this is dropped, or when the backend fails, see `error`.
+#[derive(Debug)]
+pub struct Audio{
+    mixer: Arc<Mutex<Mixer>>,
+    running: Arc<AtomicBool>,
+    error: Arc<Mutex<Option<io::Error>>>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl Audio{
+    /// Starts playing a new mixer through a backend
+    ///
+    /// Backends that don't block like `CommandBackend` does are kept to the sample clock,
+    /// never getting more than a few chunks ahead of real time.
+    pub fn new<B: AudioBackend + Send + 'static>(sample_rate: u32, mut backend: B) -> Self{
+        let mixer = Arc::new(Mutex::new(Mixer::new(sample_rate)));
+        let running = Arc::new(AtomicBool::new(true));
+        let error = Arc::new(Mutex::new(None));
+
+        let thread = {
+            let mixer = mixer.clone();
+            let running = running.clone();
+            let error = error.clone();
+            thread::spawn(move || {
+                let mut samples = vec![0.; CHUNK * 2];
+                let start = Instant::now();
+                let mut frames = 0u64;
+                while running.load(Ordering::Relaxed) {
+                    match mixer.lock() {
+                        Ok(mut mixer) => mixer.render(&mut samples),
+                        Err(_) => break
+                    }
+                    if let Err(e) = backend.write(&samples) {
+                        *error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+                        break
+                    }
+                    frames += CHUNK as u64;
+
+                    let ahead = frames as f64 / f64::from(sample_rate) - start.elapsed().as_secs_f64();
+                    if ahead > AHEAD {
+                        thread::sleep(Duration::from_secs_f64(ahead - AHEAD));
+                    }
+                }
+                running.store(false, Ordering::Relaxed);
+            })
+        };
+
+        Audio{
+            mixer,
+            running,
+            error,
+            thread: Some(thread)
+        }
+    }
+    /// Plays through the speakers with a `CommandBackend`
+    pub fn system(sample_rate: u32) -> io::Result<Self>{
+        Ok(Audio::new(sample_rate, CommandBackend::new(sample_rate)?))
+    }
+    /// Locks the mixer for playing sounds and music on it
+    pub fn mixer(&self) -> MutexGuard<'_, Mixer>{
+        self.mixer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    /// Whether the audio thread is still playing
+    pub fn is_running(&self) -> bool{
+        self.running.load(Ordering::Relaxed)
+    }
+    /// Takes the error the backend failed with, which stopped the audio thread
+    pub fn error(&self) -> Option<io::Error>{
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl Drop for Audio{
+    fn drop(&mut self){
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
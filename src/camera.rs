@@ -0,0 +1,60 @@
+/// A 2D camera deciding which part of the world is shown on the screen
+///
+/// Set it on a `Drawer` with `Drawer::set_camera` to apply it to everything drawn after.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera{
+    /// The position in the world at the centre of the screen
+    pub pos: (f32, f32),
+    /// How many times larger things are shown than their actual size
+    pub zoom: f32,
+    /// The rotation of the camera, the world is shown rotated the opposite way
+    pub rotation: f32
+}
+
+impl Camera{
+    #[inline]
+    /// Creates a camera at the origin with no zoom or rotation
+    pub fn new() -> Self{
+        Camera{
+            pos: (0., 0.),
+            zoom: 1.,
+            rotation: 0.
+        }
+    }
+
+    /// Converts a position on the screen (e.g. `FrameInfo::mousepos`) into a position in the world
+    pub fn screen_to_world(&self, (x, y): (f32, f32)) -> (f32, f32){
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (x / self.zoom, y / self.zoom);
+
+        (cos * x - sin * y + self.pos.0, sin * x + cos * y + self.pos.1)
+    }
+    /// Converts a position in the world into a position on the screen
+    pub fn world_to_screen(&self, (x, y): (f32, f32)) -> (f32, f32){
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (x - self.pos.0, y - self.pos.1);
+
+        (self.zoom * (cos * x + sin * y), self.zoom * (cos * y - sin * x))
+    }
+
+    // The matrix transforming world positions into screen positions
+    pub(crate) fn matrix(&self) -> [[f32; 4]; 4]{
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sin, cos) = (sin * self.zoom, cos * self.zoom);
+        let (x, y) = self.pos;
+
+        [
+            [ cos, -sin, 0., 0.],
+            [ sin,  cos, 0., 0.],
+            [  0.,   0., 1., 0.],
+            [-cos * x - sin * y, sin * x - cos * y, 0., 1.],
+        ]
+    }
+}
+
+impl Default for Camera{
+    /// Same as `Camera::new()`
+    fn default() -> Self{
+        Camera::new()
+    }
+}
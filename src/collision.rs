@@ -0,0 +1,471 @@
+use super::{Texture, Quad};
+
+use std::collections::HashMap;
+
+type Vector = (f32, f32);
+
+#[inline]
+fn add(a: Vector, b: Vector) -> Vector{
+    (a.0 + b.0, a.1 + b.1)
+}
+#[inline]
+fn sub(a: Vector, b: Vector) -> Vector{
+    (a.0 - b.0, a.1 - b.1)
+}
+#[inline]
+fn scale(a: Vector, s: f32) -> Vector{
+    (a.0 * s, a.1 * s)
+}
+#[inline]
+fn dot(a: Vector, b: Vector) -> f32{
+    a.0 * b.0 + a.1 * b.1
+}
+#[inline]
+fn rotate((x, y): Vector, (sin, cos): (f32, f32)) -> Vector{
+    (cos * x - sin * y, sin * x + cos * y)
+}
+fn normalise(a: Vector) -> Option<Vector>{
+    let length = dot(a, a).sqrt();
+    if length > 1e-6 {
+        Some(scale(a, 1. / length))
+    } else {
+        None
+    }
+}
+
+/// How two colliders overlap, see `Collider::collide`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact{
+    /// The direction the first collider has to move in to stop overlapping, of length 1
+    pub normal: (f32, f32),
+    /// How far the first collider has to move along the normal to stop overlapping
+    pub depth: f32
+}
+
+impl Contact{
+    #[inline]
+    /// Returns the minimum translation vector, the shortest movement of the first collider that separates the two
+    pub fn mtv(&self) -> (f32, f32){
+        scale(self.normal, self.depth)
+    }
+}
+
+/// Where a ray hit a collider, see `Collider::raycast`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit{
+    /// How far along the ray the collider was hit
+    pub distance: f32,
+    /// The point where the collider was hit
+    pub point: (f32, f32),
+    /// The direction the collider's surface faces where it was hit, of length 1
+    pub normal: (f32, f32)
+}
+
+/// A shape in the world that can be checked for collisions with other shapes
+///
+/// Positions are in the same coordinates things are drawn with, with y going up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Collider{
+    /// A rectangle that can't rotate
+    Aabb{
+        /// The centre of the rectangle
+        centre: (f32, f32),
+        /// Half the width and height of the rectangle
+        h_size: (f32, f32)
+    },
+    /// A circle
+    Circle{
+        /// The centre of the circle
+        centre: (f32, f32),
+        /// The radius of the circle
+        radius: f32
+    },
+    /// A rotated rectangle
+    Obb{
+        /// The centre of the rectangle
+        centre: (f32, f32),
+        /// Half the width and height of the rectangle before it's rotated
+        h_size: (f32, f32),
+        /// The rotation of the rectangle counter-clockwise
+        rotation: f32
+    },
+    /// A convex polygon of the points around it in either direction
+    Polygon(Vec<(f32, f32)>)
+}
+
+impl Collider{
+    /// Creates a collider covering a texture drawn at `pos` with the given rotation and scale,
+    /// like `TextureDrawer::collider`
+    pub fn from_texture(texture: &Texture, pos: (f32, f32), rotation: f32, scale: f32) -> Self{
+        let (w, h) = texture.get_size();
+        Collider::rect(pos, (w / 2. * scale, h / 2. * scale), rotation)
+    }
+    /// Creates a rectangle with the given half size, which is an `Aabb` when it isn't rotated and an `Obb` otherwise
+    pub fn rect(centre: (f32, f32), h_size: (f32, f32), rotation: f32) -> Self{
+        if rotation == 0. {
+            Collider::Aabb{centre, h_size}
+        } else {
+            Collider::Obb{centre, h_size, rotation}
+        }
+    }
+    /// Creates a collider covering a quad drawn at `pos` with the given rotation, like `QuadDrawer::collider`
+    pub fn from_quad(quad: &Quad, pos: (f32, f32), rotation: f32) -> Self{
+        let sin_cos = rotation.sin_cos();
+        Collider::Polygon(quad.vertices().iter().map(|&[x, y]| add(pos, rotate((x, y), sin_cos))).collect())
+    }
+
+    /// Returns the centre of the collider
+    pub fn centre(&self) -> (f32, f32){
+        match *self {
+            Collider::Aabb{centre, ..} | Collider::Circle{centre, ..} | Collider::Obb{centre, ..} => centre,
+            Collider::Polygon(ref points) => {
+                let sum = points.iter().fold((0., 0.), |sum, &p| add(sum, p));
+                scale(sum, 1. / points.len().max(1) as f32)
+            }
+        }
+    }
+    /// Moves the collider
+    pub fn translate(&mut self, by: (f32, f32)){
+        match *self {
+            Collider::Aabb{ref mut centre, ..} | Collider::Circle{ref mut centre, ..} | Collider::Obb{ref mut centre, ..} => {
+                *centre = add(*centre, by);
+            }
+            Collider::Polygon(ref mut points) => for p in points {
+                *p = add(*p, by);
+            }
+        }
+    }
+    /// Returns the bottom left and top right corners of the smallest rectangle around the collider
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)){
+        match *self {
+            Collider::Aabb{centre, h_size} => (sub(centre, h_size), add(centre, h_size)),
+            Collider::Circle{centre, radius} => (sub(centre, (radius, radius)), add(centre, (radius, radius))),
+            _ => {
+                let (x, y) = (self.project((1., 0.)), self.project((0., 1.)));
+                ((x.0, y.0), (x.1, y.1))
+            }
+        }
+    }
+
+    // The corners of any collider but a circle
+    fn vertices(&self) -> Option<Vec<Vector>>{
+        let rect = |centre, (w, h): Vector, sin_cos| [(-w, -h), (w, -h), (w, h), (-w, h)]
+            .iter()
+            .map(|&corner| add(centre, rotate(corner, sin_cos)))
+            .collect();
+
+        match *self {
+            Collider::Aabb{centre, h_size} => Some(rect(centre, h_size, (0., 1.))),
+            Collider::Obb{centre, h_size, rotation} => Some(rect(centre, h_size, rotation.sin_cos())),
+            Collider::Polygon(ref points) => Some(points.clone()),
+            Collider::Circle{..} => None
+        }
+    }
+    // The smallest and largest position of the collider along an axis
+    fn project(&self, axis: Vector) -> (f32, f32){
+        if let Collider::Circle{centre, radius} = *self {
+            let at = dot(centre, axis);
+            return (at - radius, at + radius)
+        }
+        self.vertices().unwrap_or_default().iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &p| {
+            let at = dot(p, axis);
+            (min.min(at), max.max(at))
+        })
+    }
+
+    /// Checks whether a point is inside the collider
+    pub fn contains_point(&self, point: (f32, f32)) -> bool{
+        match *self {
+            Collider::Circle{centre, radius} => {
+                let d = sub(point, centre);
+                dot(d, d) <= radius * radius
+            }
+            Collider::Aabb{centre, h_size} => {
+                let d = sub(point, centre);
+                d.0.abs() <= h_size.0 && d.1.abs() <= h_size.1
+            }
+            Collider::Obb{centre, h_size, rotation} => {
+                let (sin, cos) = rotation.sin_cos();
+                let d = rotate(sub(point, centre), (-sin, cos));
+                d.0.abs() <= h_size.0 && d.1.abs() <= h_size.1
+            }
+            Collider::Polygon(ref points) => edges(points).all(|(p, normal)| dot(normal, sub(point, p)) <= 0.)
+        }
+    }
+    #[inline]
+    /// Checks whether the colliders overlap
+    pub fn overlaps(&self, other: &Collider) -> bool{
+        self.collide(other).is_some()
+    }
+    /// Checks whether the colliders overlap and returns how to move this one to separate them
+    pub fn collide(&self, other: &Collider) -> Option<Contact>{
+        let (a, b) = (self.vertices(), other.vertices());
+        let mut axes: Vec<Vector> = Vec::new();
+
+        for points in a.iter().chain(b.iter()) {
+            axes.extend(edges(points).map(|(_, normal)| normal));
+        }
+        // Circles have no edges, so the axis towards the nearest point of the other shape is used
+        for &(circle, points, rest) in &[(self, &b, other), (other, &a, self)] {
+            if let Collider::Circle{centre, ..} = *circle {
+                let nearest = match *points {
+                    Some(ref points) => nearest(points, centre),
+                    None => rest.centre()
+                };
+                axes.extend(normalise(sub(centre, nearest)));
+            }
+        }
+        // Only happens for two circles with the same centre
+        if axes.is_empty() {
+            axes.push((0., 1.));
+        }
+
+        let mut best: Option<Contact> = None;
+        for axis in axes {
+            let ((a_min, a_max), (b_min, b_max)) = (self.project(axis), other.project(axis));
+            let (down, up) = (a_max - b_min, b_max - a_min);
+            let contact = if down < up {
+                Contact{normal: scale(axis, -1.), depth: down}
+            } else {
+                Contact{normal: axis, depth: up}
+            };
+            if contact.depth <= 0. {
+                return None
+            }
+            if best.map_or(true, |best| contact.depth < best.depth) {
+                best = Some(contact);
+            }
+        }
+        best
+    }
+    /// Casts a ray from `origin` in `direction` and returns where it first hits the collider,
+    /// if it does within `max_distance` (which can be `f32::INFINITY`).
+    ///
+    /// A ray starting inside the collider hits it right away.
+    pub fn raycast(&self, origin: (f32, f32), direction: (f32, f32), max_distance: f32) -> Option<RayHit>{
+        let dir = normalise(direction)?;
+        let hit = |distance, normal| Some(RayHit{
+            distance,
+            point: add(origin, scale(dir, distance)),
+            normal
+        });
+
+        if let Collider::Circle{centre, radius} = *self {
+            let m = sub(origin, centre);
+            let (b, c) = (dot(m, dir), dot(m, m) - radius * radius);
+            if c <= 0. {
+                return hit(0., scale(dir, -1.))
+            }
+            let discriminant = b * b - c;
+            if b > 0. || discriminant < 0. {
+                return None
+            }
+            let distance = -b - discriminant.sqrt();
+            if distance > max_distance {
+                return None
+            }
+            let point = add(origin, scale(dir, distance));
+            return hit(distance, normalise(sub(point, centre)).unwrap_or((0., 1.)))
+        }
+
+        // Clips the ray by the side of each edge that is inside
+        let points = self.vertices().unwrap_or_default();
+        let (mut enter, mut exit, mut normal) = (0., max_distance, scale(dir, -1.));
+        for (p, edge_normal) in edges(&points) {
+            let (along, inside) = (dot(edge_normal, dir), dot(edge_normal, sub(p, origin)));
+            if along.abs() < 1e-9 {
+                if inside < 0. {
+                    return None
+                }
+                continue
+            }
+            let t = inside / along;
+            if along < 0. {
+                if t > enter {
+                    enter = t;
+                    normal = edge_normal;
+                }
+            } else {
+                exit = exit.min(t);
+            }
+            if enter > exit {
+                return None
+            }
+        }
+        hit(enter, normal)
+    }
+}
+
+// Each edge's first point and its normal facing out of the polygon
+fn edges(points: &[Vector]) -> impl Iterator<Item=(Vector, Vector)> + '_{
+    let centre = scale(points.iter().fold((0., 0.), |sum, &p| add(sum, p)), 1. / points.len().max(1) as f32);
+
+    points.iter().zip(points.iter().cycle().skip(1)).filter_map(move |(&p, &q)| {
+        let (dx, dy) = sub(q, p);
+        let normal = normalise((dy, -dx))?;
+        Some((p, if dot(normal, sub(p, centre)) < 0. { scale(normal, -1.) } else { normal }))
+    })
+}
+
+fn nearest(points: &[Vector], to: Vector) -> Vector{
+    points.iter().cloned().fold((f32::INFINITY, to), |(best, nearest), p| {
+        let d = sub(p, to);
+        if dot(d, d) < best { (dot(d, d), p) } else { (best, nearest) }
+    }).1
+}
+
+type Bounds = ((f32, f32), (f32, f32));
+
+#[inline]
+fn bounds_overlap(a: &Bounds, b: &Bounds) -> bool{
+    (a.0).0 <= (b.1).0 && (b.0).0 <= (a.1).0 && (a.0).1 <= (b.1).1 && (b.0).1 <= (a.1).1
+}
+
+/// A broadphase that puts things into a grid of cells by their bounds,
+/// so only things that are near each other have to be checked against each other
+///
+/// For things that move, it's simplest to `clear` it and insert everything again each frame.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate korome;
+/// use korome::{SpatialHash, Collider};
+///
+/// # fn main(){
+/// let balls = vec![
+///     Collider::Circle{centre: (0., 0.), radius: 10.},
+///     Collider::Circle{centre: (15., 0.), radius: 10.},
+///     Collider::Circle{centre: (500., 0.), radius: 10.},
+/// ];
+/// let mut grid = SpatialHash::new(64.);
+/// for (i, ball) in balls.iter().enumerate() {
+///     grid.insert(i, ball);
+/// }
+///
+/// for (&a, &b) in grid.pairs() {
+///     if let Some(contact) = balls[a].collide(&balls[b]) {
+///         assert_eq!((a, b), (0, 1));
+///         assert_eq!(contact.mtv(), (-5., 0.));
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialHash<T>{
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    // Things too big for the cells, which are checked against everything instead
+    large: Vec<usize>,
+    items: Vec<(T, Bounds)>
+}
+
+// More cells than this and a thing goes into `large`, so huge colliders don't fill the map
+const MAX_CELLS: f64 = 4096.;
+
+impl<T> SpatialHash<T>{
+    /// Creates an empty grid with square cells of the given size,
+    /// which works best a bit larger than most of the things put in it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell size isn't a finite number above zero.
+    pub fn new(cell_size: f32) -> Self{
+        assert!(cell_size > 0. && cell_size.is_finite(), "spatial hash cells can't be {} wide", cell_size);
+        SpatialHash{
+            cell_size,
+            cells: HashMap::new(),
+            large: Vec::new(),
+            items: Vec::new()
+        }
+    }
+    #[inline]
+    /// Returns how many things are in the grid
+    pub fn len(&self) -> usize{
+        self.items.len()
+    }
+    #[inline]
+    /// Checks whether the grid is empty
+    pub fn is_empty(&self) -> bool{
+        self.items.is_empty()
+    }
+    /// Removes everything from the grid
+    pub fn clear(&mut self){
+        self.cells.clear();
+        self.large.clear();
+        self.items.clear();
+    }
+    /// Puts something, e.g. the index of an entity, into the grid covering the bounds of its collider
+    pub fn insert(&mut self, item: T, collider: &Collider){
+        let bounds = collider.bounds();
+        let index = self.items.len();
+        match self.cells_in(&bounds) {
+            Some(cells) => for cell in cells {
+                self.cells.entry(cell).or_default().push(index);
+            },
+            None => self.large.push(index)
+        }
+        self.items.push((item, bounds));
+    }
+
+    // None if the bounds cover too many cells, or aren't finite
+    fn cells_in(&self, &((left, bottom), (right, top)): &Bounds) -> Option<Vec<(i32, i32)>>{
+        let cell = |x: f32| (f64::from(x) / f64::from(self.cell_size)).floor();
+        let (left, bottom, right, top) = (cell(left), cell(bottom), cell(right), cell(top));
+        let count = (right - left + 1.) * (top - bottom + 1.);
+        if !count.is_finite() || count > MAX_CELLS || [left, bottom, right, top].iter().any(|&c| c.abs() > f64::from(i32::MAX)) {
+            return None
+        }
+        let (left, bottom, right, top) = (left as i32, bottom as i32, right as i32, top as i32);
+        Some((left..=right).flat_map(|x| (bottom..=top).map(move |y| (x, y))).collect())
+    }
+    // The indices of the items in the cells covering the bounds, each only once
+    fn candidates(&self, bounds: &Bounds) -> Vec<usize>{
+        let mut indices: Vec<usize> = match self.cells_in(bounds) {
+            Some(cells) => cells.iter()
+                .filter_map(|cell| self.cells.get(cell))
+                .flat_map(|cell| cell.iter().cloned())
+                .chain(self.large.iter().cloned())
+                .filter(|&i| bounds_overlap(&self.items[i].1, bounds))
+                .collect(),
+            None => (0..self.items.len()).filter(|&i| bounds_overlap(&self.items[i].1, bounds)).collect()
+        };
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Returns the things whose bounds overlap the bounds of the collider,
+    /// which can then be checked with `Collider::collide`
+    pub fn query(&self, collider: &Collider) -> Vec<&T>{
+        self.candidates(&collider.bounds()).into_iter().map(|i| &self.items[i].0).collect()
+    }
+    /// Returns the things whose bounds contain the point
+    pub fn query_point(&self, point: (f32, f32)) -> Vec<&T>{
+        self.candidates(&(point, point)).into_iter().map(|i| &self.items[i].0).collect()
+    }
+    /// Returns every pair of things whose bounds overlap, each pair once in the order they were inserted
+    pub fn pairs(&self) -> Vec<(&T, &T)>{
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for cell in self.cells.values() {
+            for (n, &i) in cell.iter().enumerate() {
+                for &j in &cell[n + 1..] {
+                    if bounds_overlap(&self.items[i].1, &self.items[j].1) {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+        }
+        for &i in &self.large {
+            for j in 0..self.items.len() {
+                let pair = (i.min(j), i.max(j));
+                if i != j && bounds_overlap(&self.items[i].1, &self.items[j].1) {
+                    pairs.push(pair);
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs.into_iter().map(|(i, j)| (&self.items[i].0, &self.items[j].0)).collect()
+    }
+}
@@ -2,23 +2,33 @@ use image;
 use image::RgbaImage;
 
 use glium::{DisplayBuild, VertexBuffer, Program, DrawParameters, Display, Surface};
-use glium::{IndexBuffer, Frame, Blend};
+use glium::{IndexBuffer, Frame, Blend, Rect, BlitTarget, DrawError};
 use glium::draw_parameters::Smooth;
-use glium::index::PrimitiveType;
-use glium::texture::{Texture2d, RawImage2d};
-use glium::glutin::WindowBuilder;
+use glium::index::{PrimitiveType, NoIndices, IndicesSource};
+use glium::texture::{Texture2d, RawImage2d, UncompressedFloatFormat, MipmapsOption};
+use glium::framebuffer::{SimpleFrameBuffer, MultiOutputFrameBuffer};
+use glium::uniforms::{self, MagnifySamplerFilter};
+use glium::vertex::MultiVerticesSource;
+use glium::glutin::{HeadlessRendererBuilder, CursorState};
 
 use std::path::Path;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
 use std::ops::{Deref, DerefMut};
 
-use super::{TextureResult, TextureError};
+use super::{TextureResult, TextureError, Camera, Shader, Uniforms, Effect, GraphicsBuilder, VirtualResolution, Collider, Assets, Handle};
+use shader::DrawUniforms;
 use ::vertex::{TextureVertex, ColourVertex};
 
 /// A 2D texture that is ready to be drawn
-#[derive(Debug)]
+///
+/// Cloning a `Texture` is cheap, as the clone refers to the same texture on the GPU.
+#[derive(Debug, Clone)]
 pub struct Texture{
-    tex: Texture2d,
-    vertex_buffer: VertexBuffer<TextureVertex>,
+    tex: Rc<Texture2d>,
+    h_size: (f32, f32),
+    // Left, bottom, right and top texture coordinates of the drawn region
+    tex_rect: [f32; 4]
 }
 
 impl Texture {
@@ -42,30 +52,74 @@ impl Texture {
         let (width, height) = image.dimensions();
         let image = RawImage2d::from_raw_rgba_reversed(image.into_raw(), (width, height));
 
-        let (w, h) = (width as f32 / 2.0, height as f32 / 2.0);
+        Texture2d::new(display, image).map(|tex| Texture::from_texture2d(Rc::new(tex))).map_err(From::from)
+    }
 
-        VertexBuffer::new(display, &[
-            TextureVertex::new([-w, -h], [0.0, 0.0]),
-            TextureVertex::new([ w, -h], [1.0, 0.0]),
-            TextureVertex::new([ w,  h], [1.0, 1.0]),
-            TextureVertex::new([-w,  h], [0.0, 1.0])
-        ])
-        .map_err(TextureError::from)
-        .and_then(|vb|
-            Texture2d::new(display, image).map(|tex|
-                Texture{
-                    tex: tex,
-                    vertex_buffer: vb
-                }
-            ).map_err(From::from)
-        )
+    // Makes a `Texture` covering the whole of `tex`
+    pub(crate) fn from_texture2d(tex: Rc<Texture2d>) -> Self{
+        let (width, height) = (tex.get_width(), tex.get_height().unwrap_or(1));
+
+        Texture{
+            tex,
+            h_size: (width as f32 / 2.0, height as f32 / 2.0),
+            tex_rect: [0., 0., 1., 1.]
+        }
+    }
+
+    /// Returns a `Texture` that draws only a rectangle of this texture.
+    ///
+    /// The rectangle is given in pixels from the top left corner of this texture.
+    /// The new texture shares the GPU texture with this one,
+    /// so its textures can be batched together.
+    pub fn sub_texture(&self, x: u32, y: u32, width: u32, height: u32) -> Texture{
+        let (full_w, full_h) = (self.tex.get_width() as f32, self.tex.get_height().unwrap_or(1) as f32);
+        let [left, _, _, top] = self.tex_rect;
+
+        let left = left + x as f32 / full_w;
+        let top = top - y as f32 / full_h;
+
+        Texture{
+            tex: self.tex.clone(),
+            h_size: (width as f32 / 2.0, height as f32 / 2.0),
+            tex_rect: [left, top - height as f32 / full_h, left + width as f32 / full_w, top]
+        }
+    }
+
+    #[inline]
+    // Gives other modules access to the GPU texture, e.g. to write to it
+    pub(crate) fn texture2d(&self) -> &Texture2d{
+        &self.tex
+    }
+
+    #[inline]
+    /// Returns the width and height of the texture in pixels
+    pub fn get_size(&self) -> (f32, f32){
+        (self.h_size.0 * 2., self.h_size.1 * 2.)
+    }
+    // The corners of the texture (bottom left, bottom right, top right and top left) drawn with the given options
+    pub(crate) fn corners(&self, (x, y): (f32, f32), (sin, cos): (f32, f32), scale: f32, colour: [f32; 4]) -> [TextureVertex; 4]{
+        let (w, h) = (self.h_size.0 * scale, self.h_size.1 * scale);
+        let [left, bottom, right, top] = self.tex_rect;
+
+        let vertex = |vx: f32, vy: f32, tex_coords| TextureVertex::new(
+            [x + cos * vx - sin * vy, y + sin * vx + cos * vy], tex_coords, colour);
+
+        [
+            vertex(-w, -h, [left , bottom]),
+            vertex( w, -h, [right, bottom]),
+            vertex( w,  h, [right, top]),
+            vertex(-w,  h, [left , top])
+        ]
     }
     /// Returns an object used for drawing the texture onto the screen with a `Drawer`
-    pub fn drawer(&self) -> TextureDrawer{
+    pub fn drawer(&self) -> TextureDrawer<'_>{
         TextureDrawer{
             pos: (0., 0.),
             sin_cos: (0., 1.),
+            scale: 1.,
             colour: [1., 1., 1., 1.],
+            shader: None,
+            uniforms: None,
             texture: self
         }
     }
@@ -95,9 +149,24 @@ quick_error! {
             cause(err)
             description(err.description())
         }
+        /// This shouldn't occur often either
+        VertexBufferCreationError(err: ::glium::vertex::BufferCreationError){
+            from()
+            cause(err)
+            description(err.description())
+        }
+        /// The surface of a headless `Graphics` couldn't be made
+        TextureCreationError(err: ::glium::texture::TextureCreationError){
+            from()
+            cause(err)
+            description("texture creation error")
+        }
     }
 }
 
+// Number of vertices the batch buffer has room for before it has to grow (64 sprites)
+const INITIAL_BATCH_CAPACITY: usize = 6 * 64;
+
 /// Contains the display and handles most of the graphics
 pub struct Graphics<'a> {
     display: Display,
@@ -105,24 +174,52 @@ pub struct Graphics<'a> {
     colour_program: Program,
     h_size : (f32, f32),
     params: DrawParameters<'a>,
-    indices: IndexBuffer<u8>
+    indices: IndexBuffer<u8>,
+    // Reused by every `Drawer` to upload its sprite batches
+    batch_buffer: RefCell<VertexBuffer<TextureVertex>>,
+    // Reused for drawing shapes
+    shape_buffer: RefCell<VertexBuffer<ColourVertex>>,
+    effects: RefCell<Vec<Effect>>,
+    // Textures the frame is copied into and passed between for post-processing
+    post_buffers: RefCell<Option<[Texture2d; 2]>>,
+    screen_quad: VertexBuffer<TextureVertex>,
+    cursor_state: Cell<CursorState>,
+    // A custom cursor and its hotspot in pixels from its top-left corner
+    cursor_image: RefCell<Option<(Texture, (f32, f32))>>,
+    // How the window was made, for making it again when going in or out of fullscreen
+    window_config: Option<GraphicsBuilder>,
+    virtual_resolution: Option<VirtualResolution>,
+    assets: Assets,
+    // What a headless `Graphics` draws to, as the headless context always claims to be 800x600
+    headless: Option<Texture2d>
 }
 
 impl<'a> Graphics<'a> {
-    /// Creates a new `Graphics` from a `Display` made using the arguments
+    #[inline]
+    /// Creates a new `Graphics` with a window of the given title and size with vsync.
+    ///
+    /// See `GraphicsBuilder` for more options.
     pub fn new(title: &str, width: u32, height: u32) -> Result<Self, GraphicsCreationError> {
-        WindowBuilder::new()
-            .with_title(title.to_string())
-            .with_dimensions(width, height)
-            .with_vsync()
-            .build_glium()
-            .map_err(From::from)
-            .and_then(|x| Self::from_display(x).map_err(From::from))
+        GraphicsBuilder::new(title, width, height).build()
+    }
+
+    /// Creates a new `Graphics` without a window, drawing off-screen to a surface of the given size.
+    ///
+    /// Useful for automated rendering tests, where frames can be read with `Drawer::capture`.
+    pub fn headless(width: u32, height: u32) -> Result<Self, GraphicsCreationError> {
+        let graphics = Self::from_display(HeadlessRendererBuilder::new(width, height).build_glium()?)?;
+        let surface = Texture2d::empty_with_format(&graphics.display, UncompressedFloatFormat::U8U8U8U8, MipmapsOption::NoMipmap, width, height)?;
+
+        Ok(Graphics{
+            h_size: (width as f32 / 2.0, height as f32 / 2.0),
+            headless: Some(surface),
+            .. graphics
+        })
     }
 
     /// Creates a new `Graphics` instance using the given display
-    pub fn from_display(display: Display) -> Result<Self, ::glium::index::BufferCreationError> {
-        let (w, h) = display.get_window().unwrap().get_inner_size().unwrap();
+    pub fn from_display(display: Display) -> Result<Self, GraphicsCreationError> {
+        let (w, h) = display.get_framebuffer_dimensions();
         let (w, h) = (w as f32 / 2.0, h as f32 / 2.0);
 
         let params = DrawParameters{
@@ -131,18 +228,44 @@ impl<'a> Graphics<'a> {
             .. Default::default()
         };
 
-        IndexBuffer::new(&display, PrimitiveType::TriangleStrip, &[0u8, 1, 3, 2])
-            .map(|indices|
-                Graphics{
-                    // Unwrap should be safe
-                    program: Program::from_source(&display, include_str!("shaders/texture.vs"), include_str!("shaders/texture.fs"), None).unwrap(),
-                    colour_program: Program::from_source(&display, include_str!("shaders/colour.vs"), include_str!("shaders/colour.fs"), None).unwrap(),
-                    display: display,
-                    params : params,
-                    indices: indices,
-                    h_size : (w, h)
-                }
-            )
+        let indices = IndexBuffer::new(&display, PrimitiveType::TriangleStrip, &[0u8, 1, 3, 2])?;
+        let batch_buffer = VertexBuffer::empty_dynamic(&display, INITIAL_BATCH_CAPACITY)?;
+        let shape_buffer = VertexBuffer::empty_dynamic(&display, INITIAL_BATCH_CAPACITY)?;
+        let white = [1., 1., 1., 1.];
+        let screen_quad = VertexBuffer::new(&display, &[
+            TextureVertex::new([-1., -1.], [0., 0.], white),
+            TextureVertex::new([ 1., -1.], [1., 0.], white),
+            TextureVertex::new([ 1.,  1.], [1., 1.], white),
+            TextureVertex::new([-1.,  1.], [0., 1.], white)
+        ])?;
+
+        Ok(Graphics{
+            // Unwrap should be safe
+            program: Program::from_source(&display, include_str!("shaders/texture.vs"), include_str!("shaders/texture.fs"), None).unwrap(),
+            colour_program: Program::from_source(&display, include_str!("shaders/colour.vs"), include_str!("shaders/colour.fs"), None).unwrap(),
+            display,
+            params,
+            indices,
+            h_size : (w, h),
+            batch_buffer: RefCell::new(batch_buffer),
+            shape_buffer: RefCell::new(shape_buffer),
+            effects: RefCell::new(Vec::new()),
+            post_buffers: RefCell::new(None),
+            screen_quad,
+            cursor_state: Cell::new(CursorState::Normal),
+            cursor_image: RefCell::new(None),
+            window_config: None,
+            virtual_resolution: None,
+            assets: Assets::new(),
+            headless: None
+        })
+    }
+    #[inline]
+    pub(crate) fn with_window_config(self, config: GraphicsBuilder) -> Self{
+        Graphics{
+            window_config: Some(config),
+            .. self
+        }
     }
 
     #[inline]
@@ -152,16 +275,236 @@ impl<'a> Graphics<'a> {
     }
 
     #[inline]
-    /// Returns half of the size of the window
+    /// Returns half of the size of the screen that is drawn to,
+    /// which is the virtual resolution if there is one, or else the size of the window
     pub fn get_h_size(&self) -> (f32, f32){
-        self.h_size
+        self.virtual_resolution.map_or(self.h_size, |res| res.h_size())
+    }
+
+    #[inline]
+    /// Sets a fixed logical size to draw at, or goes back to drawing at the size of the window with `None`
+    pub fn set_virtual_resolution(&mut self, resolution: Option<VirtualResolution>){
+        self.virtual_resolution = resolution;
+    }
+    #[inline]
+    /// Returns the virtual resolution, if there is one
+    pub fn virtual_resolution(&self) -> Option<VirtualResolution>{
+        self.virtual_resolution
+    }
+    #[inline]
+    /// Returns the asset manager textures are cached and reloaded by
+    pub fn assets(&self) -> &Assets{
+        &self.assets
+    }
+    #[inline]
+    /// Returns a handle to the texture in a file, loading it the first time it's asked for.
+    ///
+    /// See `Assets` for how the texture is reloaded when its file changes.
+    pub fn texture<P: AsRef<Path>>(&self, path: P) -> Result<Handle<Texture>, TextureError>{
+        self.assets.texture(&self.display, path)
+    }
+    #[inline]
+    /// Returns a handle to the texture in a file right away, decoding it on a worker thread the first time it's asked for.
+    ///
+    /// See `Assets` for how the texture gets into the handle and how to show the progress.
+    pub fn texture_async<P: AsRef<Path>>(&self, path: P) -> Result<Handle<Texture>, TextureError>{
+        self.assets.texture_async(&self.display, path)
+    }
+    #[inline]
+    fn window_size(&self) -> (u32, u32){
+        ((self.h_size.0 * 2.) as u32, (self.h_size.1 * 2.) as u32)
+    }
+    // The part of the window drawn to with the virtual resolution
+    fn viewport(&self) -> Option<Rect>{
+        let (w, h) = self.window_size();
+        self.virtual_resolution.map(|res| res.viewport(w, h))
+    }
+    // Converts a position in pixels from the top-left corner of the window to where it is on the screen
+    pub(crate) fn window_to_screen(&self, x: f32, y: f32) -> (f32, f32){
+        match self.virtual_resolution {
+            Some(res) => res.to_logical(self.window_size(), (x, y)),
+            None => (x - self.h_size.0, self.h_size.1 - y)
+        }
+    }
+    // Returns how many pixels one unit on the screen is
+    pub(crate) fn screen_scale(&self) -> (f32, f32){
+        self.virtual_resolution.map_or((1., 1.), |res| res.scale(self.window_size()))
     }
+
+    #[inline]
+    /// Reads the last frame shown in the window, or drawn by a headless `Graphics`, into an image
+    pub fn screenshot(&self) -> RgbaImage{
+        match self.headless {
+            Some(ref surface) => raw_to_image(surface.read()),
+            None => raw_to_image(self.display.read_front_buffer())
+        }
+    }
+
+    #[inline]
+    /// Sets the effects applied to every finished frame, in order, before it is shown
+    pub fn set_post_processing(&self, effects: Vec<Effect>){
+        *self.effects.borrow_mut() = effects;
+    }
+
+    /// Changes the title of the window
+    pub fn set_title(&mut self, title: &str){
+        if let Some(window) = self.display.get_window() {
+            window.set_title(title);
+        }
+        if let Some(ref mut config) = self.window_config {
+            config.title = title.to_owned();
+        }
+    }
+    /// Resizes the window to the given size in pixels
+    pub fn set_size(&mut self, width: u32, height: u32){
+        if let Some(window) = self.display.get_window() {
+            window.set_inner_size(width, height);
+        } else {
+            return
+        }
+        resize(self, width, height);
+    }
+    #[inline]
+    /// Checks whether the window is in fullscreen
+    pub fn is_fullscreen(&self) -> bool{
+        self.window_config.as_ref().is_some_and(|c| c.fullscreen)
+    }
+    /// Puts the window in or out of fullscreen on the primary monitor.
+    ///
+    /// The window is made again, but everything loaded, like textures, can still be used.
+    /// Only works for `Graphics` made with `new` or `GraphicsBuilder`, and does nothing for others.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) -> Result<(), GraphicsCreationError>{
+        let config = match self.window_config {
+            Some(ref config) if config.fullscreen != fullscreen => config.clone().fullscreen(fullscreen),
+            _ => return Ok(())
+        };
+        // The configuration is only changed once the window has been made again
+        config.window_builder().rebuild_glium(&self.display)?;
+        self.window_config = Some(config);
+
+        let (w, h) = self.display.get_framebuffer_dimensions();
+        self.h_size = (w as f32 / 2.0, h as f32 / 2.0);
+        // Only fails without a window
+        let _ = self.apply_cursor_state();
+        Ok(())
+    }
+
+    /// Sets whether the cursor is shown normally, hidden or grabbed when it's over the window.
+    ///
+    /// While grabbed, the cursor is hidden and kept in the middle of the window,
+    /// so `FrameInfo::mouse_delta` can be used for mouse-look.
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String>{
+        self.cursor_state.set(state);
+        self.apply_cursor_state()
+    }
+    #[inline]
+    /// Returns whether the cursor is shown normally, hidden or grabbed
+    pub fn cursor_state(&self) -> CursorState{
+        self.cursor_state.get()
+    }
+    /// Draws the texture in place of the system's cursor, with the hotspot in pixels from its top-left corner
+    pub fn set_cursor_texture(&self, texture: Texture, hotspot: (u32, u32)){
+        *self.cursor_image.borrow_mut() = Some((texture, (hotspot.0 as f32, hotspot.1 as f32)));
+        // Only fails without a window, where there is no cursor anyway
+        let _ = self.apply_cursor_state();
+    }
+    #[inline]
+    /// Draws the image in place of the system's cursor, with the hotspot in pixels from its top-left corner
+    pub fn set_cursor_image(&self, image: RgbaImage, hotspot: (u32, u32)) -> Result<(), TextureError>{
+        self.set_cursor_texture(Texture::new(self, image)?, hotspot);
+        Ok(())
+    }
+    /// Goes back to showing the system's cursor instead of a texture
+    pub fn reset_cursor_image(&self){
+        *self.cursor_image.borrow_mut() = None;
+        let _ = self.apply_cursor_state();
+    }
+
+    fn apply_cursor_state(&self) -> Result<(), String>{
+        let state = match self.cursor_state.get() {
+            // Hide the system's cursor under the custom one
+            CursorState::Normal if self.cursor_image.borrow().is_some() => CursorState::Hide,
+            state => state
+        };
+        match self.display.get_window() {
+            Some(window) => window.set_cursor_state(state),
+            None => Ok(())
+        }
+    }
+    // Moves the cursor to the middle of the window if it's grabbed, returning the new position in pixels
+    pub(crate) fn centre_grabbed_cursor(&self) -> Option<(i32, i32)>{
+        if self.cursor_state.get() != CursorState::Grab {
+            return None
+        }
+        let (x, y) = (self.h_size.0 as i32, self.h_size.1 as i32);
+        self.display.get_window()?.set_cursor_position(x, y).ok()?;
+        Some((x, y))
+    }
+    // Draws the custom cursor on top of everything else, ignoring the camera
+    pub(crate) fn draw_cursor(&self, drawer: &mut Drawer, mousepos: (f32, f32)){
+        if self.cursor_state.get() != CursorState::Normal {
+            return
+        }
+        if let Some((ref texture, (hx, hy))) = *self.cursor_image.borrow() {
+            let (w, h) = texture.get_size();
+            drawer.set_camera(Camera::new());
+            texture.drawer()
+                .pos((mousepos.0 + w / 2. - hx, mousepos.1 - h / 2. + hy))
+                .draw(drawer);
+        }
+    }
+
+    // Runs the effects over the finished frame
+    fn post_process<S: Surface>(&self, frame: &mut S){
+        let effects = self.effects.borrow();
+        if effects.is_empty() {
+            return
+        }
+
+        let (width, height) = frame.get_dimensions();
+        let mut buffers = self.post_buffers.borrow_mut();
+        if buffers.as_ref().map_or(true, |b| b[0].get_width() != width || b[0].get_height() != Some(height)) {
+            let buffer = || Texture2d::empty(&self.display, width, height).expect("failed to create post-processing buffer");
+            *buffers = Some([buffer(), buffer()]);
+        }
+        let buffers = buffers.as_ref().unwrap();
+
+        frame.fill(&buffers[0].as_surface(), MagnifySamplerFilter::Nearest);
+
+        let params = DrawParameters::default();
+        for (effect, (source, target)) in effects.iter().zip(post_passes(effects.len())) {
+            let (program, custom) = effect.parts();
+            let uniforms = DrawUniforms{
+                resolution: Some([width as f32, height as f32]),
+                tex: Some(&buffers[source]),
+                custom: Some(custom),
+                .. Default::default()
+            };
+
+            match target {
+                Some(target) => buffers[target].as_surface().draw(&self.screen_quad, &self.indices, program, &uniforms, &params),
+                None => frame.draw(&self.screen_quad, &self.indices, program, &uniforms, &params)
+            }.expect("post-processing failed");
+        }
+    }
+}
+
+// Which of the two buffers each effect reads from and draws to, in order.
+// The frame starts in the first buffer and the last effect draws to the frame (`None`).
+fn post_passes(effects: usize) -> impl Iterator<Item = (usize, Option<usize>)>{
+    (0..effects).map(move |i| (i % 2, if i + 1 == effects {None} else {Some((i + 1) % 2)}))
 }
 
 #[inline]
 // This function is only used inside `FrameInfo` when Event::Resized occurs
 pub fn resize(graphics: &mut Graphics, width: u32, height: u32){
     graphics.h_size = (width as f32 / 2.0, height as f32 / 2.0);
+    // Remember the size for when the window comes back out of fullscreen
+    if let Some(ref mut config) = graphics.window_config {
+        if !config.fullscreen {
+            config.size = (width, height);
+        }
+    }
 }
 
 impl<'a> Deref for Graphics<'a>{
@@ -173,77 +516,333 @@ impl<'a> Deref for Graphics<'a>{
     }
 }
 
+/// A surface a `Drawer` can draw to, e.g. the window's `Frame`
+pub trait DrawTarget: Surface{
+    /// Gets called when the `Drawer` drawing to it is dropped
+    fn finish(&mut self, _graphics: &Graphics){}
+}
+
+impl DrawTarget for Frame{
+    #[inline]
+    fn finish(&mut self, graphics: &Graphics){
+        graphics.post_process(self);
+        self.set_finish().unwrap()
+    }
+}
+
+impl<'a> DrawTarget for SimpleFrameBuffer<'a>{}
+
+/// What `Drawer::new` draws to, which is the window's next frame
+/// unless the `Graphics` is headless
+pub enum Screen<'a>{
+    /// The next frame of the window
+    Window(Frame),
+    /// The off-screen surface of a headless `Graphics`
+    Headless(SimpleFrameBuffer<'a>)
+}
+
+impl<'a> DrawTarget for Screen<'a>{
+    fn finish(&mut self, graphics: &Graphics){
+        graphics.post_process(self);
+        if let Screen::Window(ref mut frame) = *self {
+            frame.set_finish().unwrap()
+        }
+    }
+}
+
+// Forwards a method of `Surface` to whichever surface it is
+macro_rules! screen {
+    ($s:expr, $surface:ident => $e:expr) => (
+        match $s {
+            Screen::Window(ref $surface) => $e,
+            Screen::Headless(ref $surface) => $e
+        }
+    );
+    (mut $s:expr, $surface:ident => $e:expr) => (
+        match $s {
+            Screen::Window(ref mut $surface) => $e,
+            Screen::Headless(ref mut $surface) => $e
+        }
+    );
+}
+
+impl<'s> Surface for Screen<'s>{
+    fn clear(&mut self, rect: Option<&Rect>, color: Option<(f32, f32, f32, f32)>, color_srgb: bool, depth: Option<f32>, stencil: Option<i32>){
+        screen!(mut *self, s => s.clear(rect, color, color_srgb, depth, stencil))
+    }
+    fn get_dimensions(&self) -> (u32, u32){
+        screen!(*self, s => s.get_dimensions())
+    }
+    fn get_depth_buffer_bits(&self) -> Option<u16>{
+        screen!(*self, s => s.get_depth_buffer_bits())
+    }
+    fn get_stencil_buffer_bits(&self) -> Option<u16>{
+        screen!(*self, s => s.get_stencil_buffer_bits())
+    }
+    fn draw<'a, 'b, V, I, U>(&mut self, vertices: V, indices: I, program: &Program, uniforms: &U, params: &DrawParameters) -> Result<(), DrawError>
+        where V: MultiVerticesSource<'b>, I: Into<IndicesSource<'a>>, U: uniforms::Uniforms{
+        screen!(mut *self, s => s.draw(vertices, indices, program, uniforms, params))
+    }
+    fn blit_from_frame(&self, source_rect: &Rect, target_rect: &BlitTarget, filter: MagnifySamplerFilter){
+        screen!(*self, s => s.blit_from_frame(source_rect, target_rect, filter))
+    }
+    fn blit_from_simple_framebuffer(&self, source: &SimpleFrameBuffer, source_rect: &Rect, target_rect: &BlitTarget, filter: MagnifySamplerFilter){
+        screen!(*self, s => s.blit_from_simple_framebuffer(source, source_rect, target_rect, filter))
+    }
+    fn blit_from_multioutput_framebuffer(&self, source: &MultiOutputFrameBuffer, source_rect: &Rect, target_rect: &BlitTarget, filter: MagnifySamplerFilter){
+        screen!(*self, s => s.blit_from_multioutput_framebuffer(source, source_rect, target_rect, filter))
+    }
+    fn blit_color<S: Surface>(&self, source_rect: &Rect, target: &S, target_rect: &BlitTarget, filter: MagnifySamplerFilter){
+        screen!(*self, s => s.blit_color(source_rect, target, target_rect, filter))
+    }
+}
+
 /// Provides functionality for drawing.
 ///
-/// Can also be dereferenced into a `glium::Frame`.
-pub struct Drawer<'a>{
-    target: Frame,
+/// Textures drawn after one another with the same `Texture` are batched together
+/// and drawn in one draw call when the texture changes, when anything else is drawn,
+/// when the frame ends or when `flush` is called.
+///
+/// By default it draws to the window, but it can also draw to a `RenderTarget`.
+///
+/// Can also be dereferenced into its target, e.g. a `Screen`.
+/// Mutably dereferencing it flushes the current batch first, so draw order is kept.
+pub struct Drawer<'a, S: DrawTarget = Screen<'a>>{
+    target: S,
     /// Reference to the `Graphics` object
-    pub graphics: &'a Graphics<'a>
+    pub graphics: &'a Graphics<'a>,
+    h_size: (f32, f32),
+    params: DrawParameters<'a>,
+    camera: Camera,
+    batch: Batch
+}
+
+// Sprites waiting to be drawn with the same texture, shader and uniforms
+struct Batch{
+    texture: Option<Rc<Texture2d>>,
+    shader: Option<Rc<Program>>,
+    uniforms: Option<Uniforms>,
+    vertices: Vec<TextureVertex>
 }
 
 impl<'a> Drawer<'a>{
     #[inline]
     /// Creates a new `Drawer` to draw the next frame
     pub fn new(graphics: &'a Graphics) -> Self{
+        let screen = match graphics.headless {
+            Some(ref surface) => Screen::Headless(SimpleFrameBuffer::new(&graphics.display, surface).expect("failed to draw to the headless surface")),
+            None => Screen::Window(graphics.draw())
+        };
+        let mut drawer = Drawer::with_target(graphics, screen, graphics.get_h_size());
+
+        if let Some(viewport) = graphics.viewport() {
+            // Black bars around the virtual resolution
+            drawer.target.clear_color(0., 0., 0., 1.);
+            drawer.params.viewport = Some(viewport);
+        }
+        drawer
+    }
+}
+
+impl<'a, S: DrawTarget> Drawer<'a, S>{
+    // Creates a `Drawer` drawing to any target with the given half size
+    pub(crate) fn with_target(graphics: &'a Graphics, target: S, h_size: (f32, f32)) -> Self{
         Drawer{
-            target: graphics.draw(),
-            graphics: graphics
+            target,
+            graphics,
+            h_size,
+            params: graphics.params.clone(),
+            camera: Camera::new(),
+            batch: Batch{
+                texture: None,
+                shader: None,
+                uniforms: None,
+                vertices: Vec::new()
+            }
         }
     }
-    
+
+    #[inline]
+    /// Returns half of the size of the surface being drawn to
+    pub fn get_h_size(&self) -> (f32, f32){
+        self.h_size
+    }
     #[inline]
+    /// Returns the camera things are currently drawn with
+    pub fn camera(&self) -> Camera{
+        self.camera
+    }
+    /// Sets the camera everything drawn after this is drawn with
+    pub fn set_camera(&mut self, camera: Camera){
+        self.flush();
+        self.camera = camera;
+    }
+
+    /// Draws all the batched sprites now
+    pub fn flush(&mut self){
+        let texture = match self.batch.texture.take(){
+            Some(texture) => texture,
+            None => return
+        };
+        let graphics = self.graphics;
+        let vertices = &mut self.batch.vertices;
+        let len = vertices.len();
+
+        let mut buffer = graphics.batch_buffer.borrow_mut();
+        if buffer.len() < len {
+            *buffer = VertexBuffer::empty_dynamic(&graphics.display, len.next_power_of_two())
+                .expect("failed to grow batch buffer");
+        }
+        let slice = buffer.slice(0..len).unwrap();
+        slice.write(vertices);
+
+        let shader = self.batch.shader.take();
+        let custom = self.batch.uniforms.take();
+        let uniforms = DrawUniforms{
+            h_size: Some(self.h_size),
+            view  : Some(self.camera.matrix()),
+            tex   : Some(&texture),
+            custom: custom.as_ref(),
+            .. Default::default()
+        };
+        let program = shader.as_deref().unwrap_or(&graphics.program);
+
+        self.target.draw(slice, NoIndices(PrimitiveType::TrianglesList), program, &uniforms, &self.params)
+            .expect("draw failed");
+        vertices.clear();
+    }
+
+    // Makes the batch draw with the texture, shader and uniforms, flushing first if it didn't already
+    fn use_batch(&mut self, texture: &Rc<Texture2d>, shader: Option<&Shader>, uniforms: Option<&Uniforms>){
+        let batch = &self.batch;
+        let same = batch.texture.as_ref().is_some_and(|t| Rc::ptr_eq(t, texture))
+            && match (batch.shader.as_ref(), shader) {
+                (Some(a), Some(b)) => Rc::ptr_eq(a, b.program()),
+                (a, b) => a.is_none() && b.is_none()
+            }
+            && match (batch.uniforms.as_ref(), uniforms) {
+                (Some(a), Some(b)) => a.ptr_eq(b),
+                (a, b) => a.is_none() && b.is_none()
+            };
+
+        if !same {
+            self.flush();
+            self.batch.texture = Some(texture.clone());
+            self.batch.shader = shader.map(|s| s.program().clone());
+            self.batch.uniforms = uniforms.cloned();
+        }
+    }
+
+    // Adds a sprite to the batch, flushing first if it can't be drawn along with it
+    fn push_sprite(&mut self, texture: &Rc<Texture2d>, shader: Option<&Shader>, uniforms: Option<&Uniforms>, corners: [TextureVertex; 4]){
+        self.use_batch(texture, shader, uniforms);
+        let [a, b, c, d] = corners;
+        self.batch.vertices.extend_from_slice(&[a, b, d, d, b, c]);
+    }
+
+    // Adds many sprites of the same texture to the batch at once, given by their corners
+    pub(crate) fn push_sprites<I: IntoIterator<Item=[TextureVertex; 4]>>(&mut self, texture: &Texture, sprites: I){
+        self.use_batch(&texture.tex, None, None);
+        let vertices = &mut self.batch.vertices;
+        for [a, b, c, d] in sprites {
+            vertices.extend_from_slice(&[a, b, d, d, b, c]);
+        }
+    }
+
+    // Adds a texture to the batch with its corners (bottom left, bottom right, top right and top left)
+    // at the given positions, each showing the given point of the texture from (0, 0) at its bottom left to (1, 1)
+    pub(crate) fn push_texture(&mut self, texture: &Texture, corners: [([f32; 2], [f32; 2]); 4], colour: [f32; 4]){
+        let [left, bottom, right, top] = texture.tex_rect;
+        let vertex = |(pos, [u, v]): ([f32; 2], [f32; 2])|
+            TextureVertex::new(pos, [left + u * (right - left), bottom + v * (top - bottom)], colour);
+
+        let [a, b, c, d] = corners;
+        self.push_sprite(&texture.tex, None, None, [vertex(a), vertex(b), vertex(c), vertex(d)])
+    }
+
+    // Draws a list of triangles in the colour program's coordinates, with the given matrix
+    pub(crate) fn draw_triangles(&mut self, vertices: &[ColourVertex], matrix: [[f32; 4]; 4], shader: Option<&Shader>, uniforms: Option<&Uniforms>){
+        if vertices.is_empty() {
+            return
+        }
+        self.flush();
+        let graphics = self.graphics;
+
+        let mut buffer = graphics.shape_buffer.borrow_mut();
+        if buffer.len() < vertices.len() {
+            *buffer = VertexBuffer::empty_dynamic(&graphics.display, vertices.len().next_power_of_two())
+                .expect("failed to grow shape buffer");
+        }
+        let slice = buffer.slice(0..vertices.len()).unwrap();
+        slice.write(vertices);
+
+        let uniforms = DrawUniforms{
+            h_size: Some(self.h_size),
+            view  : Some(self.camera.matrix()),
+            matrix: Some(matrix),
+            custom: uniforms,
+            .. Default::default()
+        };
+        let program = shader.map(|s| &**s.program()).unwrap_or(&graphics.colour_program);
+
+        self.target.draw(slice, NoIndices(PrimitiveType::TrianglesList), program, &uniforms, &self.params)
+            .expect("draw failed");
+    }
+
     /// Clears the screen with the specified colour
     pub fn clear(&mut self, red: f32, green: f32, blue: f32){
-        self.clear_color(red, green, blue, 1.)
+        self.flush();
+        // Only inside the virtual resolution
+        let viewport = self.params.viewport;
+        self.target.clear(viewport.as_ref(), Some((red, green, blue, 1.)), false, None, None)
     }
+
+    /// Reads everything drawn so far into an image
+    pub fn capture(&mut self) -> RgbaImage{
+        self.flush();
+
+        let (width, height) = self.target.get_dimensions();
+        let tex = Texture2d::empty(&self.graphics.display, width, height)
+            .expect("failed to create texture to capture into");
+        self.target.fill(&tex.as_surface(), MagnifySamplerFilter::Nearest);
+
+        raw_to_image(tex.read())
+    }
+}
+
+// Turns pixels read from OpenGL into an image with the top row first
+fn raw_to_image(raw: RawImage2d<u8>) -> RgbaImage{
+    let row = raw.width as usize * 4;
+    // OpenGL gives the rows from the bottom up
+    let data = raw.data.chunks(row).rev().flat_map(|r| r.iter().cloned()).collect();
+
+    RgbaImage::from_raw(raw.width, raw.height, data).expect("texture data has the wrong size")
 }
 
-impl<'a> Deref for Drawer<'a>{
-    type Target = Frame;
+impl<'a, S: DrawTarget> Deref for Drawer<'a, S>{
+    type Target = S;
     #[inline]
-    fn deref(&self) -> &Frame{
+    fn deref(&self) -> &S{
         &self.target
     }
 }
 
-impl<'a> DerefMut for Drawer<'a>{
+impl<'a, S: DrawTarget> DerefMut for Drawer<'a, S>{
     #[inline]
-    fn deref_mut(&mut self) -> &mut Frame{
+    fn deref_mut(&mut self) -> &mut S{
+        self.flush();
         &mut self.target
     }
 }
 
-impl<'a> Drop for Drawer<'a>{
+impl<'a, S: DrawTarget> Drop for Drawer<'a, S>{
     #[inline]
     fn drop(&mut self){
-        self.target.set_finish().unwrap()
+        self.flush();
+        self.target.finish(self.graphics)
     }
 }
 
-macro_rules! set {
-    ($(#[$m:meta])* fn $d:ident; $f:ident: $T:ty) => (
-        #[inline]
-        $(#[$m])*
-        pub fn $f(self, $f: $T) -> Self{
-            $d{
-                $f: $f,
-                .. self
-            }
-        }
-    );
-    ($(#[$m:meta])* fn $d:ident; $f:ident: $T:ty => $field:ident: $e:expr) => (
-        #[inline]
-        $(#[$m])*
-        pub fn $f(self, $f: $T) -> Self{
-            $d{
-                $field: $e,
-                .. self
-            }
-        }
-    );
-}
-
 /// Object for drawing textures to the screen using the builder pattern
 #[must_use = "drawers are lazy and do nothing until consumed"]
 #[derive(Debug, Clone)]
@@ -251,8 +850,12 @@ pub struct TextureDrawer<'a>{
     /// The position on the screen where the texture will be drawn
     pub pos: (f32, f32),
     sin_cos: (f32, f32),
+    /// How many times larger than its actual size the texture will be drawn
+    pub scale: f32,
     /// The colour the texture will drawn with
     pub colour: [f32; 4],
+    shader: Option<&'a Shader>,
+    uniforms: Option<&'a Uniforms>,
     texture: &'a Texture
 }
 
@@ -263,31 +866,29 @@ impl<'a> TextureDrawer<'a> {
         fn TextureDrawer; colour: [f32; 4]}
     set!{/// Sets the rotation of the texture to be drawn on the screen
         fn TextureDrawer; rotation: f32 => sin_cos: rotation.sin_cos()}
-    /// Consumes self and draws the texture to the screen with the given options
-    pub fn draw(self, drawer: &mut Drawer){
-        let TextureDrawer{pos: (x, y), sin_cos: (sin, cos), colour, texture} = self;
-
-        let uniforms = uniform! {
-            h_size: drawer.graphics.h_size,
-            tex   : &texture.tex,
-            colour: colour,
-            matrix: [
-                [ cos, sin, 0., 0.],
-                [-sin, cos, 0., 0.],
-                [  0.,  0., 1., 0.],
-                [  x ,  y , 0., 1.],
-            ]
-        };
-
-        drawer.draw(&texture.vertex_buffer, &drawer.graphics.indices, &drawer.graphics.program, &uniforms, &drawer.graphics.params)
-            .expect("draw failed")
+    set!{/// Sets how many times larger than its actual size the texture will be drawn
+        fn TextureDrawer; scale: f32}
+    set!{/// Sets a custom shader to draw the texture with instead of korome's own
+        fn TextureDrawer; shader: &'a Shader => shader: Some(shader)}
+    set!{/// Sets the values of the custom shader's uniforms
+        fn TextureDrawer; uniforms: &'a Uniforms => uniforms: Some(uniforms)}
+    /// Returns a collider covering the texture where it would be drawn
+    pub fn collider(&self) -> Collider{
+        Collider::from_texture(self.texture, self.pos, self.sin_cos.0.atan2(self.sin_cos.1), self.scale)
+    }
+    /// Consumes self and adds the texture to the `Drawer`'s batch with the given options
+    pub fn draw<S: DrawTarget>(self, drawer: &mut Drawer<S>){
+        let TextureDrawer{pos, sin_cos, scale, colour, shader, uniforms, texture} = self;
+        drawer.push_sprite(&texture.tex, shader, uniforms, texture.corners(pos, sin_cos, scale, colour))
     }
 }
 
 /// A simple rectangle that can be drawn on the screen
 #[derive(Debug)]
 pub struct Quad{
-    vertex_buffer: VertexBuffer<ColourVertex>
+    vertex_buffer: VertexBuffer<ColourVertex>,
+    // Kept for building colliders
+    vertices: [[f32; 2]; 4]
 }
 
 impl Quad {
@@ -298,21 +899,15 @@ impl Quad {
             ColourVertex::new(vertices[1], colour),
             ColourVertex::new(vertices[2], colour),
             ColourVertex::new(vertices[3], colour)
-        ]).map(|vb| Quad{
-            vertex_buffer: vb
+        ]).map(|vertex_buffer| Quad{
+            vertex_buffer,
+            vertices
         })
     }
     /// Creates a new rectangular quad from the width and height with one colour
     pub fn new_rect(display: &Display, colour: [f32; 4], width: f32, height: f32) -> Result<Self, ::glium::vertex::BufferCreationError>{
         let (w, h) = (width/2., height/2.);
-        VertexBuffer::new(display, &[
-            ColourVertex::new([-w, -h], colour),
-            ColourVertex::new([ w, -h], colour),
-            ColourVertex::new([ w,  h], colour),
-            ColourVertex::new([-w,  h], colour)
-        ]).map(|vb| Quad{
-            vertex_buffer: vb
-        })
+        Quad::new(display, colour, [[-w, -h], [w, -h], [w, h], [-w, h]])
     }
     /// Creates a new quad from the vertices with each with vertice having its own colour
     pub fn with_colours(display: &Display, vertices: [[f32; 2]; 4], colours: [[f32; 4]; 4]) -> Result<Self, ::glium::vertex::BufferCreationError>{
@@ -321,16 +916,24 @@ impl Quad {
             ColourVertex::new(vertices[1], colours[1]),
             ColourVertex::new(vertices[2], colours[2]),
             ColourVertex::new(vertices[3], colours[3]),
-        ]).map(|vb| Quad{
-            vertex_buffer: vb
+        ]).map(|vertex_buffer| Quad{
+            vertex_buffer,
+            vertices
         })
     }
+    #[inline]
+    /// Returns the vertices the quad was created with
+    pub fn vertices(&self) -> [[f32; 2]; 4]{
+        self.vertices
+    }
     /// Returns an object used for drawing the quad onto the screen with a `Drawer`
-    pub fn drawer(&self) -> QuadDrawer{
+    pub fn drawer(&self) -> QuadDrawer<'_>{
         QuadDrawer{
             quad: self,
             pos: (0., 0.),
-            sin_cos: (0., 1.)
+            sin_cos: (0., 1.),
+            shader: None,
+            uniforms: None
         }
     }
 }
@@ -342,7 +945,9 @@ pub struct QuadDrawer<'a>{
     quad: &'a Quad,
     /// The position on the screen where the it will be drawn
     pub pos: (f32, f32),
-    sin_cos: (f32, f32)
+    sin_cos: (f32, f32),
+    shader: Option<&'a Shader>,
+    uniforms: Option<&'a Uniforms>
 }
 
 impl<'a> QuadDrawer<'a>{
@@ -350,22 +955,49 @@ impl<'a> QuadDrawer<'a>{
         fn QuadDrawer; pos: (f32, f32)}
     set!{/// Sets the rotation of the rectangle to be drawn on the screen
         fn QuadDrawer; rotation: f32 => sin_cos: rotation.sin_cos()}
+    set!{/// Sets a custom shader to draw the rectangle with instead of korome's own
+        fn QuadDrawer; shader: &'a Shader => shader: Some(shader)}
+    set!{/// Sets the values of the custom shader's uniforms
+        fn QuadDrawer; uniforms: &'a Uniforms => uniforms: Some(uniforms)}
+    /// Returns a collider covering the quad where it would be drawn
+    pub fn collider(&self) -> Collider{
+        Collider::from_quad(self.quad, self.pos, self.sin_cos.0.atan2(self.sin_cos.1))
+    }
     /// Consumes self and draws the rectangle to the screen with the given options
-    pub fn draw(self, drawer: &mut Drawer){
-        let QuadDrawer{quad, pos: (x, y), sin_cos: (sin, cos)} = self;
+    pub fn draw<S: DrawTarget>(self, drawer: &mut Drawer<S>){
+        let QuadDrawer{quad, pos: (x, y), sin_cos: (sin, cos), shader, uniforms} = self;
 
-        let uniforms = uniform! {
-            h_size : drawer.graphics.h_size,
-            matrix: [
+        let uniforms = DrawUniforms{
+            h_size: Some(drawer.h_size),
+            view  : Some(drawer.camera.matrix()),
+            matrix: Some([
                 [ cos, sin, 0., 0.],
                 [-sin, cos, 0., 0.],
                 [  0.,  0., 1., 0.],
                 [  x ,  y , 0., 1.],
-            ]
+            ]),
+            custom: uniforms,
+            .. Default::default()
         };
 
+        let graphics = drawer.graphics;
+        let params = drawer.params.clone();
+        let program = shader.map(|s| &**s.program()).unwrap_or(&graphics.colour_program);
         // If this panics, it is a problem with korome
-        drawer.draw(&quad.vertex_buffer, &drawer.graphics.indices, &drawer.graphics.colour_program, &uniforms, &drawer.graphics.params)
+        drawer.draw(&quad.vertex_buffer, &graphics.indices, program, &uniforms, &params)
             .expect("draw failed")
     }
 }
+
+#[cfg(test)]
+mod tests{
+    use super::post_passes;
+
+    #[test]
+    fn effects_are_chained_in_order(){
+        assert_eq!(post_passes(0).count(), 0);
+        assert_eq!(post_passes(1).collect::<Vec<_>>(), [(0, None)]);
+        // Each effect reads what the one before it drew, going back and forth between the buffers
+        assert_eq!(post_passes(4).collect::<Vec<_>>(), [(0, Some(1)), (1, Some(0)), (0, Some(1)), (1, None)]);
+    }
+}
@@ -0,0 +1,169 @@
+use super::{Game, GameUpdate, FrameInfo, Drawer};
+use logic::EventQueue;
+
+use std::collections::VecDeque;
+
+/// Methods a `FixedStep` will call, for games whose logic runs at a fixed rate
+pub trait FixedGame{
+    /// The return type of the `update` function, see `Game::ReturnType`
+    type ReturnType: Into<GameUpdate>;
+    /// Advances the game by `dt` seconds, which is always the same.
+    ///
+    /// It can be called several times or not at all between two frames.
+    /// `info.delta` is also `dt`, and events that happened since the last update are in `info`.
+    fn update(&mut self, info: &FrameInfo, dt: f32) -> Self::ReturnType;
+    /// Draws the game each frame.
+    ///
+    /// `alpha` is how far the current time is from the last update to the next,
+    /// between `0.` and `1.`, for interpolating between the last two states.
+    fn render(&mut self, drawer: &mut Drawer, alpha: f32);
+}
+
+/// Decides how many fixed steps to take each frame by accumulating the time passed
+#[derive(Debug, Clone)]
+pub struct FixedTimestep{
+    dt: f32,
+    accumulator: f32,
+    max_steps: u32
+}
+
+impl FixedTimestep{
+    #[inline]
+    /// Creates a timestep of `dt` seconds that takes at most 5 steps per frame
+    pub fn new(dt: f32) -> Self{
+        FixedTimestep{
+            dt,
+            accumulator: 0.,
+            max_steps: 5
+        }
+    }
+    set!{/// Sets the most steps taken in one frame before the rest of the time is dropped.
+        ///
+        /// This keeps a slow game from falling further and further behind.
+        fn FixedTimestep; max_steps: u32}
+
+    #[inline]
+    /// Returns the length of a step in seconds
+    pub fn dt(&self) -> f32{
+        self.dt
+    }
+    /// Adds `delta` seconds and returns how many steps should be taken
+    pub fn advance(&mut self, delta: f32) -> u32{
+        self.accumulator += delta;
+
+        let steps = (self.accumulator / self.dt) as u32;
+        if steps > self.max_steps {
+            self.accumulator = 0.;
+            self.max_steps
+        } else {
+            self.accumulator -= steps as f32 * self.dt;
+            steps
+        }
+    }
+    /// Adds `delta` seconds and calls `step` for every step that should be taken, which is what `FixedStep` does
+    ///
+    /// The time of every step has been used up, so they're all taken even if one returns an update.
+    /// Updates other than `Nothing` are put at the back of `updates`, except for `Close`,
+    /// which stops the steps and is returned. Otherwise the front of `updates` is returned.
+    pub fn take_steps<F>(&mut self, delta: f32, updates: &mut VecDeque<GameUpdate>, mut step: F) -> GameUpdate
+    where F: FnMut() -> GameUpdate{
+        for _ in 0..self.advance(delta) {
+            match step() {
+                GameUpdate::Nothing => (),
+                GameUpdate::Close => return GameUpdate::Close,
+                update => updates.push_back(update)
+            }
+        }
+        updates.pop_front().unwrap_or(GameUpdate::Nothing)
+    }
+    #[inline]
+    /// Returns how far the accumulated time is towards the next step, between `0.` and `1.`
+    pub fn alpha(&self) -> f32{
+        (self.accumulator / self.dt).min(1.)
+    }
+}
+
+/// Turns a `FixedGame` into a `Game` that `run_until_closed` can run
+///
+/// All the steps of a frame are taken even if some return a `GameUpdate`, except for `Close`.
+/// As only one update can be done a frame, the rest are done on the frames after.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// use korome::*;
+///
+/// struct Ball{y: f32, last_y: f32, speed: f32}
+///
+/// impl FixedGame for Ball{
+///     type ReturnType = ();
+///     fn update(&mut self, _: &FrameInfo, dt: f32){
+///         self.last_y = self.y;
+///         self.speed -= 500. * dt;
+///         self.y += self.speed * dt;
+///     }
+///     fn render(&mut self, drawer: &mut Drawer, alpha: f32){
+///         let y = self.last_y + (self.y - self.last_y) * alpha;
+///         drawer.clear(0., 0., 0.);
+///         // Draw the ball at `y`
+///     }
+/// }
+///
+/// # fn main(){
+/// let graphics = Graphics::new("Ball", 800, 600).unwrap();
+/// run_until_closed(graphics, FixedStep::new(Ball{y: 0., last_y: 0., speed: 0.}, 1. / 60.));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FixedStep<G>{
+    game: G,
+    timestep: FixedTimestep,
+    events: EventQueue,
+    // Updates returned by steps, handed on one a frame
+    updates: VecDeque<GameUpdate>
+}
+
+impl<G: FixedGame> FixedStep<G>{
+    #[inline]
+    /// Runs the game with steps of `dt` seconds
+    pub fn new(game: G, dt: f32) -> Self{
+        FixedStep::with_timestep(game, FixedTimestep::new(dt))
+    }
+    #[inline]
+    /// Runs the game with the given timestep
+    pub fn with_timestep(game: G, timestep: FixedTimestep) -> Self{
+        FixedStep{
+            game,
+            timestep,
+            events: EventQueue::default(),
+            updates: VecDeque::new()
+        }
+    }
+    #[inline]
+    /// Returns a reference to the game
+    pub fn game(&self) -> &G{
+        &self.game
+    }
+    #[inline]
+    /// Returns a mutable reference to the game
+    pub fn game_mut(&mut self) -> &mut G{
+        &mut self.game
+    }
+}
+
+impl<G: FixedGame> Game for FixedStep<G>{
+    type ReturnType = GameUpdate;
+
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> GameUpdate{
+        let FixedStep{ref mut game, ref mut timestep, ref mut events, ref mut updates} = *self;
+        events.push(info);
+        let dt = timestep.dt();
+
+        let update = timestep.take_steps(info.delta, updates, || game.update(&events.take(info, dt), dt).into());
+        if update != GameUpdate::Close {
+            game.render(drawer, timestep.alpha());
+        }
+        update
+    }
+}
@@ -0,0 +1,434 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+
+/// A button on a gamepad, laid out like an Xbox controller
+///
+/// Buttons that aren't known are given by their number from the driver as `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton{
+    /// The bottom face button
+    A,
+    /// The right face button
+    B,
+    /// The left face button
+    X,
+    /// The top face button
+    Y,
+    /// The left shoulder button
+    LeftBumper,
+    /// The right shoulder button
+    RightBumper,
+    /// The select or back button
+    Select,
+    /// The start button
+    Start,
+    /// The button in the middle, e.g. the Xbox button
+    Mode,
+    /// Pressing the left stick
+    LeftStick,
+    /// Pressing the right stick
+    RightStick,
+    /// Any other button
+    Other(u8)
+}
+
+/// An analog axis on a gamepad, laid out like an Xbox controller
+///
+/// Sticks go from `-1.` to `1.` with up and right being positive like the screen coordinates,
+/// while triggers go from `0.` when released to `1.` when fully pressed.
+/// Axes that aren't known are given by their number from the driver as `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis{
+    /// The horizontal axis of the left stick
+    LeftStickX,
+    /// The vertical axis of the left stick
+    LeftStickY,
+    /// The horizontal axis of the right stick
+    RightStickX,
+    /// The vertical axis of the right stick
+    RightStickY,
+    /// The left trigger
+    LeftTrigger,
+    /// The right trigger
+    RightTrigger,
+    /// The horizontal axis of the D-pad
+    DPadX,
+    /// The vertical axis of the D-pad
+    DPadY,
+    /// Any other axis
+    Other(u8)
+}
+
+impl GamepadAxis{
+    #[inline]
+    /// Checks whether the axis is a trigger, which only goes from `0.` to `1.`
+    pub fn is_trigger(&self) -> bool{
+        matches!(*self, GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger)
+    }
+}
+
+/// Something that has happened to a gamepad, each gamepad is identified by a number
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent{
+    /// A gamepad with the name was connected
+    Connected(usize, String),
+    /// A gamepad was disconnected
+    Disconnected(usize),
+    /// A button was pressed (`true`) or released (`false`)
+    Button(usize, GamepadButton, bool),
+    /// An axis moved to the value
+    Axis(usize, GamepadAxis, f32)
+}
+
+/// Where `Gamepads` gets its events from
+///
+/// Backends report axes without any dead zone applied, which `Gamepads` takes care of.
+pub trait GamepadBackend{
+    /// Adds all events that have happened since the last poll to `events`
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+}
+
+/// A backend where the events are supplied by hand, for testing games without a gamepad
+///
+/// Clones share the same events, so one clone can be given to `Gamepads` while events are pushed to the other.
+#[derive(Debug, Clone, Default)]
+pub struct MockBackend{
+    events: Rc<RefCell<Vec<GamepadEvent>>>
+}
+
+impl MockBackend{
+    #[inline]
+    /// Creates a backend with no events
+    pub fn new() -> Self{
+        MockBackend::default()
+    }
+    #[inline]
+    /// Adds an event for the next poll
+    pub fn push(&self, event: GamepadEvent){
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl GamepadBackend for MockBackend{
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>){
+        events.append(&mut self.events.borrow_mut());
+    }
+}
+
+// Used on platforms where korome can't read gamepads
+struct NoBackend;
+
+impl GamepadBackend for NoBackend{
+    fn poll(&mut self, _events: &mut Vec<GamepadEvent>){}
+}
+
+#[derive(Debug, Default)]
+struct GamepadState{
+    name: String,
+    buttons: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>
+}
+
+/// The state of all connected gamepads, as it is in `FrameInfo::gamepads`
+pub struct Gamepads{
+    backend: Box<dyn GamepadBackend>,
+    pads: HashMap<usize, GamepadState>,
+    dead_zone: f32
+}
+
+impl Debug for Gamepads{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let pads: Vec<_> = self.connected().collect();
+        f.debug_struct("Gamepads")
+            .field("connected", &pads)
+            .field("dead_zone", &self.dead_zone)
+            .finish()
+    }
+}
+
+impl Default for Gamepads{
+    /// Reads gamepads through the Linux joystick interface (`/dev/input/js*`),
+    /// or doesn't find any gamepads on other platforms
+    fn default() -> Self{
+        #[cfg(target_os = "linux")]
+        let backend = JoystickBackend::new();
+        #[cfg(not(target_os = "linux"))]
+        let backend = NoBackend;
+
+        Gamepads::new(Box::new(backend))
+    }
+}
+
+impl Gamepads{
+    /// Creates the gamepad state with events from the backend and a dead zone of `0.15`
+    pub fn new(backend: Box<dyn GamepadBackend>) -> Self{
+        Gamepads{
+            backend,
+            pads: HashMap::new(),
+            dead_zone: 0.15
+        }
+    }
+    #[inline]
+    /// Creates the gamepad state without any gamepads
+    pub fn none() -> Self{
+        Gamepads::new(Box::new(NoBackend))
+    }
+    #[inline]
+    /// Sets how far from the centre axes have to be before they aren't `0.`, between `0.` and `1.`
+    ///
+    /// Values outside the dead zone are scaled so axes still go smoothly from `0.` to `1.`
+    pub fn set_dead_zone(&mut self, dead_zone: f32){
+        self.dead_zone = dead_zone;
+    }
+    #[inline]
+    /// Returns the dead zone of axes
+    pub fn dead_zone(&self) -> f32{
+        self.dead_zone
+    }
+
+    /// Polls the backend and updates the state, returning what has changed
+    ///
+    /// `run_until_closed` calls this every frame.
+    pub fn update(&mut self) -> Vec<GamepadEvent>{
+        let mut raw = Vec::new();
+        self.backend.poll(&mut raw);
+
+        let mut events = Vec::with_capacity(raw.len());
+        for event in raw {
+            match event {
+                GamepadEvent::Connected(id, name) => {
+                    self.pads.insert(id, GamepadState{name: name.clone(), .. GamepadState::default()});
+                    events.push(GamepadEvent::Connected(id, name));
+                }
+                GamepadEvent::Disconnected(id) => if self.pads.remove(&id).is_some() {
+                    events.push(GamepadEvent::Disconnected(id));
+                },
+                GamepadEvent::Button(id, button, pressed) => if let Some(pad) = self.pads.get_mut(&id) {
+                    let changed = if pressed {
+                        pad.buttons.insert(button)
+                    } else {
+                        pad.buttons.remove(&button)
+                    };
+                    if changed {
+                        events.push(GamepadEvent::Button(id, button, pressed));
+                    }
+                },
+                GamepadEvent::Axis(id, axis, value) => if let Some(pad) = self.pads.get_mut(&id) {
+                    let value = apply_dead_zone(value, self.dead_zone);
+                    let old = pad.axes.insert(axis, value).unwrap_or(0.);
+                    if old != value {
+                        events.push(GamepadEvent::Axis(id, axis, value));
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// Returns the numbers of all connected gamepads
+    pub fn connected(&self) -> impl Iterator<Item = usize> + '_{
+        let mut ids: Vec<_> = self.pads.keys().cloned().collect();
+        ids.sort_unstable();
+        ids.into_iter()
+    }
+    #[inline]
+    /// Checks whether the gamepad is connected
+    pub fn is_connected(&self, id: usize) -> bool{
+        self.pads.contains_key(&id)
+    }
+    #[inline]
+    /// Returns the name of the gamepad if it's connected
+    pub fn name(&self, id: usize) -> Option<&str>{
+        self.pads.get(&id).map(|pad| &*pad.name)
+    }
+    #[inline]
+    /// Checks whether a button on the gamepad is pressed down
+    pub fn is_down(&self, id: usize, button: GamepadButton) -> bool{
+        self.pads.get(&id).is_some_and(|pad| pad.buttons.contains(&button))
+    }
+    #[inline]
+    /// Returns the value of an axis on the gamepad with the dead zone applied,
+    /// or `0.` if it hasn't moved or the gamepad isn't connected
+    pub fn axis(&self, id: usize, axis: GamepadAxis) -> f32{
+        self.pads.get(&id).and_then(|pad| pad.axes.get(&axis)).cloned().unwrap_or(0.)
+    }
+    #[inline]
+    /// Returns both axes of the left stick
+    pub fn left_stick(&self, id: usize) -> (f32, f32){
+        (self.axis(id, GamepadAxis::LeftStickX), self.axis(id, GamepadAxis::LeftStickY))
+    }
+    #[inline]
+    /// Returns both axes of the right stick
+    pub fn right_stick(&self, id: usize) -> (f32, f32){
+        (self.axis(id, GamepadAxis::RightStickX), self.axis(id, GamepadAxis::RightStickY))
+    }
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32{
+    let magnitude = value.abs();
+    if magnitude <= dead_zone {
+        0.
+    } else {
+        value.signum() * ((magnitude - dead_zone) / (1. - dead_zone)).min(1.)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::linux::JoystickBackend;
+
+#[cfg(target_os = "linux")]
+mod linux{
+    use super::{GamepadBackend, GamepadEvent, GamepadButton, GamepadAxis};
+
+    use std::fs::{File, OpenOptions};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::io::{Read, ErrorKind};
+    use std::time::{Instant, Duration};
+
+    use libc;
+
+    // From linux/joystick.h
+    const JS_EVENT_BUTTON: u8 = 0x01;
+    const JS_EVENT_AXIS: u8 = 0x02;
+    const JS_EVENT_INIT: u8 = 0x80;
+    const NAME_LEN: usize = 128;
+    // JSIOCGNAME(NAME_LEN)
+    const JSIOCGNAME: libc::Ioctl = (2 << 30) | ((NAME_LEN as libc::Ioctl) << 16) | (0x6a << 8) | 0x13;
+
+    // How many `/dev/input/js*` devices are looked for
+    const MAX_DEVICES: usize = 16;
+    const RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Reads gamepads from the Linux joystick interface (`/dev/input/js*`)
+    ///
+    /// The number of each gamepad is the number of its device, and new devices are looked for every second.
+    /// Buttons and axes are mapped as the `xpad` driver reports Xbox controllers,
+    /// which most other drivers for gamepads follow as well.
+    #[derive(Debug)]
+    pub struct JoystickBackend{
+        devices: Vec<Option<File>>,
+        last_scan: Option<Instant>
+    }
+
+    impl Default for JoystickBackend{
+        fn default() -> Self{
+            JoystickBackend::new()
+        }
+    }
+
+    impl JoystickBackend{
+        /// Creates the backend, which finds the connected gamepads on the first poll
+        pub fn new() -> Self{
+            JoystickBackend{
+                devices: (0..MAX_DEVICES).map(|_| None).collect(),
+                last_scan: None
+            }
+        }
+
+        fn scan(&mut self, events: &mut Vec<GamepadEvent>){
+            for (id, device) in self.devices.iter_mut().enumerate() {
+                if device.is_some() {
+                    continue
+                }
+                let file = OpenOptions::new()
+                    .read(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open(format!("/dev/input/js{}", id));
+
+                // Devices that don't exist or can't be read are simply looked for again next time
+                if let Ok(file) = file {
+                    events.push(GamepadEvent::Connected(id, device_name(&file)));
+                    *device = Some(file);
+                }
+            }
+        }
+    }
+
+    fn device_name(file: &File) -> String{
+        let mut name = [0u8; NAME_LEN];
+        let len = unsafe{libc::ioctl(file.as_raw_fd(), JSIOCGNAME, name.as_mut_ptr())};
+        if len <= 0 {
+            return "Unknown gamepad".to_owned()
+        }
+        let end = name.iter().position(|&b| b == 0).unwrap_or(NAME_LEN);
+        String::from_utf8_lossy(&name[..end]).into_owned()
+    }
+
+    fn button(number: u8) -> GamepadButton{
+        match number {
+            0 => GamepadButton::A,
+            1 => GamepadButton::B,
+            2 => GamepadButton::X,
+            3 => GamepadButton::Y,
+            4 => GamepadButton::LeftBumper,
+            5 => GamepadButton::RightBumper,
+            6 => GamepadButton::Select,
+            7 => GamepadButton::Start,
+            8 => GamepadButton::Mode,
+            9 => GamepadButton::LeftStick,
+            10 => GamepadButton::RightStick,
+            n => GamepadButton::Other(n)
+        }
+    }
+
+    fn axis(number: u8, value: i16) -> (GamepadAxis, f32){
+        let value = (f32::from(value) / 32767.).max(-1.);
+        match number {
+            0 => (GamepadAxis::LeftStickX, value),
+            // The joystick interface has down as positive
+            1 => (GamepadAxis::LeftStickY, -value),
+            2 => (GamepadAxis::LeftTrigger, (value + 1.) / 2.),
+            3 => (GamepadAxis::RightStickX, value),
+            4 => (GamepadAxis::RightStickY, -value),
+            5 => (GamepadAxis::RightTrigger, (value + 1.) / 2.),
+            6 => (GamepadAxis::DPadX, value),
+            7 => (GamepadAxis::DPadY, -value),
+            n => (GamepadAxis::Other(n), value)
+        }
+    }
+
+    impl GamepadBackend for JoystickBackend{
+        fn poll(&mut self, events: &mut Vec<GamepadEvent>){
+            if self.last_scan.map_or(true, |last| last.elapsed() >= RESCAN_INTERVAL) {
+                self.scan(events);
+                self.last_scan = Some(Instant::now());
+            }
+
+            let mut buf = [0u8; 8 * 64];
+            for (id, device) in self.devices.iter_mut().enumerate() {
+                let disconnected = match *device {
+                    Some(ref mut file) => loop {
+                        match file.read(&mut buf) {
+                            Ok(0) => break true,
+                            // Every event is 8 bytes: time (u32), value (i16), type (u8) and number (u8)
+                            Ok(n) => for event in buf[..n].chunks(8).filter(|e| e.len() == 8) {
+                                let value = i16::from_ne_bytes([event[4], event[5]]);
+                                let (kind, number) = (event[6] & !JS_EVENT_INIT, event[7]);
+
+                                if kind == JS_EVENT_BUTTON {
+                                    events.push(GamepadEvent::Button(id, button(number), value != 0));
+                                } else if kind == JS_EVENT_AXIS {
+                                    let (axis, value) = axis(number, value);
+                                    events.push(GamepadEvent::Axis(id, axis, value));
+                                }
+                            },
+                            Err(ref e) if e.kind() == ErrorKind::WouldBlock => break false,
+                            Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                            // ENODEV when the gamepad is unplugged
+                            Err(_) => break true
+                        }
+                    },
+                    None => false
+                };
+
+                if disconnected {
+                    *device = None;
+                    events.push(GamepadEvent::Disconnected(id));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::fs;
+use std::io;
+
+use super::{FrameInfo, VirtualKeyCode, MouseButton};
+
+quick_error! {
+    /// Wraps together errors that can occur loading an `InputMap`
+    #[derive(Debug)]
+    pub enum InputMapError{
+        /// A line in the config couldn't be understood
+        ParseError(line: usize, reason: &'static str){
+            description(reason)
+            display("line {} of input config: {}", line, reason)
+        }
+        /// An `std::io::Error`
+        IoError(err: io::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+    }
+}
+
+/// A key or mouse button that an action or axis can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding{
+    /// A key on the keyboard
+    Key(VirtualKeyCode),
+    /// A button on the mouse
+    Mouse(MouseButton)
+}
+
+impl From<VirtualKeyCode> for Binding{
+    #[inline]
+    fn from(key: VirtualKeyCode) -> Self{
+        Binding::Key(key)
+    }
+}
+
+impl From<MouseButton> for Binding{
+    #[inline]
+    fn from(button: MouseButton) -> Self{
+        Binding::Mouse(button)
+    }
+}
+
+macro_rules! key_names {
+    ($($key:ident),*) => (
+        fn key_from_name(name: &str) -> Option<VirtualKeyCode>{
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None
+            }
+        }
+        fn key_name(key: VirtualKeyCode) -> &'static str{
+            match key {
+                $(VirtualKeyCode::$key => stringify!($key),)*
+            }
+        }
+    );
+}
+
+key_names!{
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15,
+    Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down, Back, Return, Space, Compose,
+    Numlock, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    AbntC1, AbntC2, Add, Apostrophe, Apps, At, Ax, Backslash, Calculator, Capital, Colon, Comma, Convert,
+    Decimal, Divide, Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl, LMenu, LShift, LWin, Mail,
+    MediaSelect, MediaStop, Minus, Multiply, Mute, MyComputer, NavigateForward, NavigateBackward,
+    NextTrack, NoConvert, NumpadComma, NumpadEnter, NumpadEquals, OEM102, Period, PlayPause, Power,
+    PrevTrack, RAlt, RBracket, RControl, RMenu, RShift, RWin, Semicolon, Slash, Sleep, Stop, Subtract,
+    Sysrq, Tab, Underline, Unlabeled, VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward,
+    WebHome, WebRefresh, WebSearch, WebStop, Yen
+}
+
+impl Binding{
+    /// Parses a binding written like in an input config,
+    /// i.e. the name of a `VirtualKeyCode` variant or `MouseLeft`, `MouseRight`, `MouseMiddle` or `Mouse` followed by a number
+    pub fn from_name(name: &str) -> Option<Self>{
+        match name {
+            "MouseLeft" => Some(Binding::Mouse(MouseButton::Left)),
+            "MouseRight" => Some(Binding::Mouse(MouseButton::Right)),
+            "MouseMiddle" => Some(Binding::Mouse(MouseButton::Middle)),
+            _ if name.starts_with("Mouse") => name[5..].parse().ok().map(|n| Binding::Mouse(MouseButton::Other(n))),
+            _ => key_from_name(name).map(Binding::Key)
+        }
+    }
+    /// Checks whether the key or button is held down
+    pub fn is_held(&self, info: &FrameInfo) -> bool{
+        match *self {
+            Binding::Key(ref key) => info.is_down(key),
+            Binding::Mouse(ref button) => info.is_mouse_down(button)
+        }
+    }
+    /// Checks whether the key or button was pressed this frame
+    pub fn is_pressed(&self, info: &FrameInfo) -> bool{
+        self.has_event(info, true)
+    }
+    /// Checks whether the key or button was released this frame
+    pub fn is_released(&self, info: &FrameInfo) -> bool{
+        self.has_event(info, false)
+    }
+
+    fn has_event(&self, info: &FrameInfo, pressed: bool) -> bool{
+        match *self {
+            Binding::Key(key) => info.get_key_events().contains(&(pressed, key)),
+            Binding::Mouse(button) => info.get_mouse_events().contains(&(pressed, button))
+        }
+    }
+}
+
+impl Display for Binding{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match *self {
+            Binding::Key(key) => f.write_str(key_name(key)),
+            Binding::Mouse(MouseButton::Left) => f.write_str("MouseLeft"),
+            Binding::Mouse(MouseButton::Right) => f.write_str("MouseRight"),
+            Binding::Mouse(MouseButton::Middle) => f.write_str("MouseMiddle"),
+            Binding::Mouse(MouseButton::Other(n)) => write!(f, "Mouse{}", n)
+        }
+    }
+}
+
+/// Named actions and axes bound to keys and mouse buttons so players can rebind the controls
+///
+/// Actions are queried with `FrameInfo::action_pressed`, `action_released` and `action_held`,
+/// axes with `FrameInfo::axis`.
+///
+/// # Config format
+///
+/// Each line binds an action or an axis to a comma-separated list of bindings.
+/// Axes are bound to pairs of a negative and a positive binding separated by a slash.
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// ```text
+/// action jump = Space, W
+/// action fire = MouseLeft
+/// axis move_x = A/D, Left/Right
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputMap{
+    actions: BTreeMap<String, Vec<Binding>>,
+    axes: BTreeMap<String, Vec<(Binding, Binding)>>
+}
+
+impl InputMap{
+    #[inline]
+    /// Creates an input map without any bindings
+    pub fn new() -> Self{
+        InputMap::default()
+    }
+    /// Adds a binding to an action
+    pub fn with_action<S: Into<String>, B: Into<Binding>>(mut self, action: S, binding: B) -> Self{
+        self.bind(action, binding);
+        self
+    }
+    /// Adds a pair of bindings to an axis
+    pub fn with_axis<S: Into<String>, B: Into<Binding>, C: Into<Binding>>(mut self, axis: S, negative: B, positive: C) -> Self{
+        self.bind_axis(axis, negative, positive);
+        self
+    }
+    /// Adds a binding to an action
+    pub fn bind<S: Into<String>, B: Into<Binding>>(&mut self, action: S, binding: B){
+        let binding = binding.into();
+        let bindings = self.actions.entry(action.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+    /// Adds a pair of bindings to an axis.
+    ///
+    /// The axis is `-1.` while `negative` is held and `1.` while `positive` is held.
+    pub fn bind_axis<S: Into<String>, B: Into<Binding>, C: Into<Binding>>(&mut self, axis: S, negative: B, positive: C){
+        let pair = (negative.into(), positive.into());
+        let pairs = self.axes.entry(axis.into()).or_default();
+        if !pairs.contains(&pair) {
+            pairs.push(pair);
+        }
+    }
+    /// Removes all bindings of an action and returns them
+    pub fn unbind(&mut self, action: &str) -> Vec<Binding>{
+        self.actions.remove(action).unwrap_or_default()
+    }
+    /// Removes all bindings of an axis and returns them
+    pub fn unbind_axis(&mut self, axis: &str) -> Vec<(Binding, Binding)>{
+        self.axes.remove(axis).unwrap_or_default()
+    }
+    /// Returns the bindings of an action
+    pub fn action_bindings(&self, action: &str) -> &[Binding]{
+        self.actions.get(action).map_or(&[], |b| b)
+    }
+    /// Returns the pairs of negative and positive bindings of an axis
+    pub fn axis_bindings(&self, axis: &str) -> &[(Binding, Binding)]{
+        self.axes.get(axis).map_or(&[], |b| b)
+    }
+
+    /// Parses an input map from a config, see `InputMap` for the format
+    pub fn from_config(config: &str) -> Result<Self, InputMapError>{
+        let mut map = InputMap::new();
+
+        for (i, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let err = |reason| InputMapError::ParseError(i + 1, reason);
+
+            let eq = line.find('=').ok_or_else(|| err("missing `=`"))?;
+            let (head, bindings) = (line[..eq].trim(), line[eq+1..].trim());
+
+            let mut head = head.split_whitespace();
+            let (kind, name) = match (head.next(), head.next(), head.next()) {
+                (Some(kind), Some(name), None) => (kind, name),
+                _ => return Err(err("expected `action <name>` or `axis <name>` before `=`"))
+            };
+
+            let parse = |name: &str| Binding::from_name(name.trim()).ok_or_else(|| err("unknown key or mouse button"));
+            let bindings = bindings.split(',').filter(|b| !b.trim().is_empty());
+
+            match kind {
+                "action" => {
+                    map.actions.entry(name.to_owned()).or_default();
+                    for binding in bindings {
+                        map.bind(name, parse(binding)?);
+                    }
+                }
+                "axis" => {
+                    map.axes.entry(name.to_owned()).or_default();
+                    for pair in bindings {
+                        let slash = pair.find('/').ok_or_else(|| err("axis bindings need a `/` between the negative and positive binding"))?;
+                        map.bind_axis(name, parse(&pair[..slash])?, parse(&pair[slash+1..])?);
+                    }
+                }
+                _ => return Err(err("expected `action` or `axis`"))
+            }
+        }
+
+        Ok(map)
+    }
+    /// Writes the input map as a config that `from_config` can read
+    pub fn to_config(&self) -> String{
+        let mut config = String::new();
+
+        for (action, bindings) in &self.actions {
+            let bindings: Vec<_> = bindings.iter().map(Binding::to_string).collect();
+            config.push_str(&format!("action {} = {}\n", action, bindings.join(", ")));
+        }
+        for (axis, pairs) in &self.axes {
+            let pairs: Vec<_> = pairs.iter().map(|&(n, p)| format!("{}/{}", n, p)).collect();
+            config.push_str(&format!("axis {} = {}\n", axis, pairs.join(", ")));
+        }
+
+        config
+    }
+    #[inline]
+    /// Loads an input map from a config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, InputMapError>{
+        InputMap::from_config(&fs::read_to_string(path)?)
+    }
+    #[inline]
+    /// Saves the input map to a config file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()>{
+        fs::write(path, self.to_config())
+    }
+}
+
+impl<'a> FrameInfo<'a>{
+    /// Checks whether any binding of the action was pressed this frame
+    pub fn action_pressed(&self, map: &InputMap, action: &str) -> bool{
+        map.action_bindings(action).iter().any(|b| b.is_pressed(self))
+    }
+    /// Checks whether any binding of the action was released this frame
+    pub fn action_released(&self, map: &InputMap, action: &str) -> bool{
+        map.action_bindings(action).iter().any(|b| b.is_released(self))
+    }
+    /// Checks whether any binding of the action is held down
+    pub fn action_held(&self, map: &InputMap, action: &str) -> bool{
+        map.action_bindings(action).iter().any(|b| b.is_held(self))
+    }
+    /// Returns the value of the axis between `-1.` and `1.` from the bindings that are held down
+    pub fn axis(&self, map: &InputMap, axis: &str) -> f32{
+        let value: f32 = map.axis_bindings(axis).iter()
+            .map(|&(negative, positive)| {
+                let held = |b: Binding| if b.is_held(self) {1.} else {0.};
+                held(positive) - held(negative)
+            })
+            .sum();
+        value.clamp(-1., 1.)
+    }
+}
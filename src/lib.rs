@@ -8,13 +8,89 @@ pub extern crate glium;
 extern crate image;
 #[macro_use]
 extern crate quick_error;
+extern crate rusttype;
+extern crate xml;
+extern crate serde_json;
+extern crate inflate;
+extern crate lewton;
+#[cfg(target_os = "linux")]
+extern crate libc;
+
+// Makes a builder method setting one field of a drawer
+macro_rules! set {
+    ($(#[$m:meta])* fn $d:ident; $f:ident: $T:ty) => (
+        #[inline]
+        $(#[$m])*
+        pub fn $f(self, $f: $T) -> Self{
+            $d{
+                $f,
+                .. self
+            }
+        }
+    );
+    ($(#[$m:meta])* fn $d:ident; $f:ident: $T:ty => $field:ident: $e:expr) => (
+        #[inline]
+        $(#[$m])*
+        pub fn $f(self, $f: $T) -> Self{
+            $d{
+                $field: $e,
+                .. self
+            }
+        }
+    );
+}
 
 mod vertex;
 mod draw;
 mod logic;
+mod atlas;
+mod animation;
+mod text;
+mod camera;
+mod target;
+mod shader;
+mod fixed;
+mod scene;
+mod input;
+mod gamepad;
+mod textfield;
+mod replay;
+mod window;
+mod resolution;
+mod shape;
+mod tilemap;
+mod particle;
+mod collision;
+mod audio;
+mod assets;
 
-pub use draw::{Graphics, Texture, Drawer, TextureDrawer, Quad, QuadDrawer, GraphicsCreationError};
-pub use logic::{run_until_closed, Game, GameUpdate, FrameInfo, VirtualKeyCode, MouseButton, Event};
+pub use draw::{Graphics, Texture, Drawer, DrawTarget, Screen, TextureDrawer, Quad, QuadDrawer, GraphicsCreationError};
+pub use logic::{run_until_closed, Game, GameUpdate, UpdateError, FrameInfo, VirtualKeyCode, MouseButton, Event};
+pub use glium::glutin::CursorState;
+pub use atlas::{AtlasBuilder, TextureAtlas};
+pub use animation::{SpriteSheet, Animation, AnimationPlayer, PlayMode};
+pub use text::{Font, TextDrawer, Align, FontError};
+pub use camera::Camera;
+pub use target::RenderTarget;
+pub use shader::{Shader, Uniform, Uniforms, Effect};
+pub use fixed::{FixedGame, FixedStep, FixedTimestep};
+pub use scene::{Scene, SceneStack, SceneUpdate};
+pub use input::{InputMap, Binding, InputMapError};
+pub use gamepad::{Gamepads, GamepadButton, GamepadAxis, GamepadEvent, GamepadBackend, MockBackend};
+#[cfg(target_os = "linux")]
+pub use gamepad::JoystickBackend;
+pub use textfield::{TextField, KeyRepeat, Clipboard, LocalClipboard};
+#[cfg(target_os = "linux")]
+pub use textfield::SystemClipboard;
+pub use replay::{record_until_closed, replay_until_closed, Replay, ReplayError};
+pub use window::GraphicsBuilder;
+pub use resolution::{VirtualResolution, ScaleMode};
+pub use shape::{ShapeDrawer, triangulate};
+pub use tilemap::{TileMap, TileMapDrawer, TileMapError, Tile, Tileset, TileInfo, TilesetImage, Layer, LayerKind, Object, ObjectShape, Property, Properties};
+pub use particle::{ParticleEmitter, Particle};
+pub use collision::{Collider, Contact, RayHit, SpatialHash};
+pub use audio::{Sound, SoundPlayer, Music, Mixer, Bus, VoiceId, Audio, AudioBackend, NullBackend, WavBackend, CommandBackend, AudioError};
+pub use assets::{Assets, Handle};
 
 /// Result type for `korome::TextureError`
 pub type TextureResult = Result<Texture, TextureError>;
@@ -47,5 +123,16 @@ quick_error! {
             cause(err)
             description(err.description())
         }
+        /// Two images were added to an `AtlasBuilder` under the same name
+        DuplicateName(name: String){
+            description("duplicate image name in atlas")
+            display("more than one image in the atlas is named {:?}", name)
+        }
+        /// The images added to an `AtlasBuilder` need a texture of this width and height,
+        /// which is bigger than the graphics card can handle
+        TooBig(width: u32, height: u32){
+            description("atlas is too big for a texture")
+            display("the atlas would be {}x{}, which is too big for a texture", width, height)
+        }
     }
 }
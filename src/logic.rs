@@ -1,10 +1,14 @@
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::mem;
+use std::fmt;
 
-use super::{Graphics, Drawer};
+use super::{Graphics, Drawer, Gamepads, GamepadEvent};
 use draw::resize;
 use std::time::Instant;
 
-use glium::glutin::ElementState;
+use glium::glutin::{ElementState, MouseScrollDelta};
 
 pub use glium::glutin::{VirtualKeyCode, MouseButton, Event};
 
@@ -21,7 +25,7 @@ pub trait Game{
     /// Method that gets called each frame from `run_until_closed()`.
     ///
     /// Should return a `GameUpdate` specifying things the game should do.
-    fn frame(&mut self, &FrameInfo, &mut Drawer) -> Self::ReturnType;
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> Self::ReturnType;
 }
 
 impl<R: Into<GameUpdate>, F: FnMut(&FrameInfo, &mut Drawer) -> R> Game for F {
@@ -34,12 +38,42 @@ impl<R: Into<GameUpdate>, F: FnMut(&FrameInfo, &mut Drawer) -> R> Game for F {
 /// This is returned each frame from an object implementing `Game`.
 ///
 /// It describes anything the game should do, e.g. closing the game.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum GameUpdate{
     /// Tells the game to close
     Close,
     /// Tells it do nothing
-    Nothing
+    Nothing,
+    /// Saves the frame as a PNG file at the path once it has been shown
+    Screenshot(PathBuf),
+    /// Starts saving every frame as a numbered PNG file (`frame00000.png` and so on)
+    /// in the directory, which is created if it doesn't exist
+    ///
+    /// The recording stops if a frame can't be saved.
+    StartRecording(PathBuf),
+    /// Stops saving every frame
+    StopRecording,
+    /// Changes the title of the window
+    SetTitle(String),
+    /// Puts the window in (`true`) or out of fullscreen, see `Graphics::set_fullscreen`
+    SetFullscreen(bool),
+    /// Resizes the window to the width and height in pixels
+    Resize(u32, u32)
+}
+
+/// A `GameUpdate` that couldn't be done, which is reported on the next `FrameInfo`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateError{
+    /// What failed, which is a `Screenshot` of the frame when saving a recorded frame fails
+    pub update: GameUpdate,
+    /// Why it failed
+    pub reason: String
+}
+
+impl fmt::Display for UpdateError{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "couldn't do {:?}: {}", self.update, self.reason)
+    }
 }
 
 impl From<()> for GameUpdate {
@@ -68,88 +102,313 @@ impl Default for GameUpdate{
 }
 
 /// Runs the game until the window is closed
-pub fn run_until_closed<G: Game>(mut graphics: Graphics, mut game: G){
-    let mut last = Instant::now();
-    let mut mousepos = (0., 0.);
-    let mut down_keys = HashSet::new();
+pub fn run_until_closed<G: Game>(graphics: Graphics, game: G){
+    run(graphics, game, &mut LiveInput::new())
+}
+
+// Where the input of each frame comes from, which is the window unless a recording is replayed
+pub trait InputSource{
+    // Returns the next frame's input, or `None` if the game should stop
+    fn next_frame(&mut self, graphics: &mut Graphics) -> Option<FrameInput>;
+    fn gamepads(&self) -> &Gamepads;
+    // Whether textures loading in the background are waited for each frame, so recordings play back the same
+    fn waits_for_loads(&self) -> bool{
+        false
+    }
+}
+
+// Runs the game with input from any source
+pub fn run<G: Game, I: InputSource>(mut graphics: Graphics, mut game: G, input: &mut I){
+    // The directory frames are being saved in and the number of the next frame
+    let mut recording: Option<(PathBuf, u32)> = None;
+    let mut errors = Vec::new();
+
+    while let Some(mut frame) = input.next_frame(&mut graphics) {
+        frame.update_errors = mem::take(&mut errors);
+        let info = frame.info(input.gamepads());
+        if input.waits_for_loads() {
+            graphics.assets().wait_for_loads(&graphics);
+        }
+        graphics.assets().update(&graphics);
 
-    'game: loop{
+        let update = {
+            let mut drawer = Drawer::new(&graphics);
+            let update = game.frame(&info, &mut drawer).into();
+            if info.mouse_inside {
+                graphics.draw_cursor(&mut drawer, info.mousepos);
+            }
+            update
+        };
+
+        if let Some((dir, frame)) = recording.take() {
+            match save_screenshot(&graphics, frame_path(&dir, frame)) {
+                Ok(()) => recording = Some((dir, frame + 1)),
+                Err(e) => errors.push(e)
+            }
+        }
+
+        let result = match update {
+            GameUpdate::Close => break,
+            GameUpdate::Nothing => Ok(()),
+            GameUpdate::Screenshot(path) => save_screenshot(&graphics, path),
+            GameUpdate::StartRecording(dir) => start_recording(dir).map(|dir| recording = Some((dir, 0))),
+            GameUpdate::StopRecording => {
+                recording = None;
+                Ok(())
+            }
+            GameUpdate::SetTitle(title) => {
+                graphics.set_title(&title);
+                Ok(())
+            }
+            GameUpdate::SetFullscreen(fullscreen) => graphics.set_fullscreen(fullscreen)
+                .map_err(|e| UpdateError{update: GameUpdate::SetFullscreen(fullscreen), reason: e.to_string()}),
+            GameUpdate::Resize(width, height) => {
+                graphics.set_size(width, height);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            errors.push(e);
+        }
+    }
+}
+
+// Everything that happened in a frame, which `FrameInfo` is made from
+#[derive(Debug, Default)]
+pub struct FrameInput{
+    pub delta: f32,
+    pub mousepos: (f32, f32),
+    pub mouse_delta: (f32, f32),
+    pub scroll_lines: (f32, f32),
+    pub scroll_pixels: (f32, f32),
+    pub mouse_inside: bool,
+    pub key_events: Vec<(bool, VirtualKeyCode)>,
+    pub mouse_events: Vec<(bool, MouseButton)>,
+    pub misc_events: Vec<Event>,
+    pub gamepad_events: Vec<GamepadEvent>,
+    pub text_input: String,
+    pub down_keys: HashSet<VirtualKeyCode>,
+    pub down_mouse: HashSet<MouseButton>,
+    pub update_errors: Vec<UpdateError>
+}
+
+impl FrameInput{
+    fn info<'a>(&'a self, gamepads: &'a Gamepads) -> FrameInfo<'a>{
+        FrameInfo{
+            delta: self.delta,
+            mousepos: self.mousepos,
+            mouse_delta: self.mouse_delta,
+            scroll_lines: self.scroll_lines,
+            scroll_pixels: self.scroll_pixels,
+            mouse_inside: self.mouse_inside,
+            key_events: self.key_events.clone(),
+            mouse_events: self.mouse_events.clone(),
+            misc_events: self.misc_events.clone(),
+            gamepad_events: self.gamepad_events.clone(),
+            text_input: self.text_input.clone(),
+            update_errors: self.update_errors.clone(),
+            down_keys: &self.down_keys,
+            down_mouse: &self.down_mouse,
+            gamepads
+        }
+    }
+}
+
+// Input from the window and the gamepads
+pub struct LiveInput{
+    last: Instant,
+    mousepos: (f32, f32),
+    // The last position of the cursor in pixels, for working out how far it has moved
+    last_cursor: Option<(i32, i32)>,
+    mouse_inside: bool,
+    down_keys: HashSet<VirtualKeyCode>,
+    down_mouse: HashSet<MouseButton>,
+    gamepads: Gamepads
+}
+
+impl LiveInput{
+    pub fn new() -> Self{
+        LiveInput{
+            last: Instant::now(),
+            mousepos: (0., 0.),
+            last_cursor: None,
+            // There's no event if the cursor starts out inside the window
+            mouse_inside: true,
+            down_keys: HashSet::new(),
+            down_mouse: HashSet::new(),
+            gamepads: Gamepads::default()
+        }
+    }
+}
+
+// Tracks a key, leaving out the presses the system repeats while it's held
+fn key_event(down_keys: &mut HashSet<VirtualKeyCode>, keys: &mut Vec<(bool, VirtualKeyCode)>, state: ElementState, vkc: VirtualKeyCode){
+    match state {
+        ElementState::Pressed => if down_keys.insert(vkc) {
+            keys.push((true, vkc));
+        },
+        ElementState::Released => {
+            down_keys.remove(&vkc);
+            keys.push((false, vkc));
+        }
+    }
+}
+
+impl InputSource for LiveInput{
+    fn next_frame(&mut self, graphics: &mut Graphics) -> Option<FrameInput>{
         let mut keys = Vec::new();
         // mice?
         let mut mouses = Vec::new();
         let mut misc_events = Vec::new();
+        let mut text_input = String::new();
+        let mut mouse_delta = (0., 0.);
+        let mut scroll_lines = (0., 0.);
+        let mut scroll_pixels = (0., 0.);
 
         let mut resized = None;
 
         for ev in graphics.poll_events() {
             match ev {
-                Event::Closed => break 'game,
-                Event::KeyboardInput(es, _, Some(vkc)) => match es{
-                    ElementState::Pressed  => {
-                        down_keys.insert( vkc);
-                        keys.push((true , vkc));
-                    },
-                    ElementState::Released => {
-                        down_keys.remove(&vkc);
-                        keys.push((false, vkc));
+                Event::Closed => return None,
+                Event::KeyboardInput(es, _, Some(vkc)) => key_event(&mut self.down_keys, &mut keys, es, vkc),
+                Event::MouseMoved(x, y) => {
+                    self.mousepos = graphics.window_to_screen(x as f32, y as f32);
+                    if let Some(last) = self.last_cursor {
+                        let (dx, dy) = cursor_delta(last, (x, y), graphics.screen_scale());
+                        mouse_delta.0 += dx;
+                        mouse_delta.1 += dy;
                     }
+                    self.last_cursor = Some((x, y));
                 },
-                Event::MouseMoved(x, y) => {
-                    let (w, h) = graphics.get_h_size();
-
-                    mousepos = (x as f32 - w, h - y as f32);
+                Event::MouseEntered => {
+                    self.mouse_inside = true;
+                    self.last_cursor = None;
+                },
+                Event::MouseLeft => self.mouse_inside = false,
+                Event::MouseWheel(MouseScrollDelta::LineDelta(x, y), _) => {
+                    scroll_lines.0 += x;
+                    scroll_lines.1 += y;
+                },
+                Event::MouseWheel(MouseScrollDelta::PixelDelta(x, y), _) => {
+                    scroll_pixels.0 += x;
+                    scroll_pixels.1 += y;
                 },
                 // This is only neccessary because `graphics` gets immutably borrowed for this for-loop
                 Event::Resized(w, h) => resized = Some((w, h)),
-                Event::MouseInput(state, button) => mouses.push((state == ElementState::Pressed, button)),
+                Event::MouseInput(state, button) => {
+                    let pressed = state == ElementState::Pressed;
+                    if pressed {
+                        self.down_mouse.insert(button);
+                    } else {
+                        self.down_mouse.remove(&button);
+                    }
+                    mouses.push((pressed, button));
+                },
+                // Control characters like backspace are left to the key events
+                Event::ReceivedCharacter(c) => if !c.is_control() {
+                    text_input.push(c);
+                },
                 // Put any other event in the misc_events vector in case the game needs them.
                 misc_event => misc_events.push(misc_event)
             }
         }
 
         if let Some((w, h)) = resized{
-            resize(&mut graphics, w, h);
+            resize(graphics, w, h);
+        }
+
+        // Keep a grabbed cursor from hitting the edges, so it can keep moving
+        if let Some(centre) = graphics.centre_grabbed_cursor() {
+            self.last_cursor = Some(centre);
         }
 
-        let dur = last.elapsed();
+        let dur = self.last.elapsed();
         let delta = dur.as_secs() as f32 + dur.subsec_nanos() as f32 / 1e9;
-        last = Instant::now();
+        self.last = Instant::now();
 
-        let update = FrameInfo{
-            delta    : delta,
+        Some(FrameInput{
+            delta,
+            mousepos: self.mousepos,
+            mouse_delta,
+            scroll_lines,
+            scroll_pixels,
+            mouse_inside: self.mouse_inside,
             key_events: keys,
             mouse_events: mouses,
-            misc_events: misc_events,
-            down_keys: &down_keys,
-            mousepos : mousepos
-        };
+            misc_events,
+            gamepad_events: self.gamepads.update(),
+            text_input,
+            down_keys: self.down_keys.clone(),
+            down_mouse: self.down_mouse.clone(),
+            update_errors: Vec::new()
+        })
+    }
+    #[inline]
+    fn gamepads(&self) -> &Gamepads{
+        &self.gamepads
+    }
+}
 
-        let update = game.frame(&update, &mut Drawer::new(&graphics)).into();
+// How far the cursor moved in screen coordinates, with up being positive
+fn cursor_delta((last_x, last_y): (i32, i32), (x, y): (i32, i32), (sx, sy): (f32, f32)) -> (f32, f32){
+    ((x - last_x) as f32 / sx, (last_y - y) as f32 / sy)
+}
 
-        if let GameUpdate::Close = update {
-            break
-        }
+// Makes the directory frames are recorded into
+fn start_recording(dir: PathBuf) -> Result<PathBuf, UpdateError>{
+    match fs::create_dir_all(&dir) {
+        Ok(()) => Ok(dir),
+        Err(e) => Err(UpdateError{update: GameUpdate::StartRecording(dir), reason: e.to_string()})
     }
 }
 
+// Where a recorded frame is saved
+fn frame_path(dir: &Path, frame: u32) -> PathBuf{
+    dir.join(format!("frame{:05}.png", frame))
+}
+
+fn save_screenshot(graphics: &Graphics, path: PathBuf) -> Result<(), UpdateError>{
+    graphics.screenshot().save(&path).map_err(|e| UpdateError{update: GameUpdate::Screenshot(path), reason: e.to_string()})
+}
+
 /// Wraps together useful data about what has happened (e.g. events)
 #[derive(Debug)]
 pub struct FrameInfo<'a>{
     /// The amount of time passed since last frame
     pub delta: f32,
-    /// The last position of the mouse on the screen
+    /// The last position of the mouse on the screen,
+    /// in the same coordinates as things are drawn in (see `Graphics::set_virtual_resolution`)
     pub mousepos: (f32, f32),
+    /// How far the mouse has moved since last frame, with up being positive like `mousepos`.
+    ///
+    /// It keeps working while the cursor is grabbed, see `Graphics::set_cursor_state`.
+    pub mouse_delta: (f32, f32),
+    /// How far the scroll wheel has scrolled horizontally and vertically in lines since last frame
+    pub scroll_lines: (f32, f32),
+    /// How far has been scrolled horizontally and vertically in pixels since last frame, e.g. on a touchpad
+    pub scroll_pixels: (f32, f32),
+    /// Whether the cursor is over the window
+    pub mouse_inside: bool,
     mouse_events: Vec<(bool, MouseButton)>,
     key_events: Vec<(bool, VirtualKeyCode)>,
     misc_events: Vec<Event>,
+    gamepad_events: Vec<GamepadEvent>,
+    text_input: String,
+    update_errors: Vec<UpdateError>,
 
     // All keys that are pressed down
-    down_keys: &'a HashSet<VirtualKeyCode>
+    down_keys: &'a HashSet<VirtualKeyCode>,
+    // All mouse buttons that are pressed down
+    down_mouse: &'a HashSet<MouseButton>,
+    gamepads: &'a Gamepads
 }
 
 impl<'a> FrameInfo<'a>{
     #[inline]
     /// Returns a slice of all key events that have happened
+    ///
+    /// A key held down only gives one press, as the presses the system repeats are left out.
+    /// See `KeyRepeat` for repeating keys in e.g. text fields.
     pub fn get_key_events(&self) -> &[(bool, VirtualKeyCode)]{
         &self.key_events
     }
@@ -168,6 +427,86 @@ impl<'a> FrameInfo<'a>{
     pub fn is_down(&self, key: &VirtualKeyCode) -> bool{
         self.down_keys.contains(key)
     }
+    #[inline]
+    /// Checks whether a mouse button is pressed down
+    pub fn is_mouse_down(&self, button: &MouseButton) -> bool{
+        self.down_mouse.contains(button)
+    }
+    #[inline]
+    /// Returns a slice of all gamepad events that have happened
+    pub fn get_gamepad_events(&self) -> &[GamepadEvent]{
+        &self.gamepad_events
+    }
+    #[inline]
+    /// Returns the text that has been typed, with shifted symbols and non-ASCII characters as they were entered
+    ///
+    /// Control characters like backspace are left out, as they can be found in the key events.
+    /// See `TextField` for editing text with it.
+    pub fn get_text_input(&self) -> &str{
+        &self.text_input
+    }
+    #[inline]
+    /// Returns the state of all connected gamepads
+    pub fn gamepads(&self) -> &Gamepads{
+        self.gamepads
+    }
+    #[inline]
+    /// Returns the `GameUpdate`s from last frame that failed, like a screenshot that couldn't be saved
+    pub fn get_update_errors(&self) -> &[UpdateError]{
+        &self.update_errors
+    }
+}
+
+// Events collected over several frames, for games that don't handle every frame
+#[derive(Debug, Default)]
+pub struct EventQueue{
+    mouse_delta: (f32, f32),
+    scroll_lines: (f32, f32),
+    scroll_pixels: (f32, f32),
+    mouse_events: Vec<(bool, MouseButton)>,
+    key_events: Vec<(bool, VirtualKeyCode)>,
+    misc_events: Vec<Event>,
+    gamepad_events: Vec<GamepadEvent>,
+    text_input: String,
+    update_errors: Vec<UpdateError>
+}
+
+impl EventQueue{
+    pub fn push(&mut self, info: &FrameInfo){
+        fn add(a: &mut (f32, f32), b: (f32, f32)){
+            a.0 += b.0;
+            a.1 += b.1;
+        }
+        add(&mut self.mouse_delta, info.mouse_delta);
+        add(&mut self.scroll_lines, info.scroll_lines);
+        add(&mut self.scroll_pixels, info.scroll_pixels);
+        self.mouse_events.extend_from_slice(&info.mouse_events);
+        self.key_events.extend_from_slice(&info.key_events);
+        self.misc_events.extend_from_slice(&info.misc_events);
+        self.gamepad_events.extend_from_slice(&info.gamepad_events);
+        self.text_input.push_str(&info.text_input);
+        self.update_errors.extend_from_slice(&info.update_errors);
+    }
+    // Makes a copy of `info` with all the queued events instead of its own and another delta
+    pub fn take<'a>(&mut self, info: &FrameInfo<'a>, delta: f32) -> FrameInfo<'a>{
+        FrameInfo{
+            delta,
+            mousepos: info.mousepos,
+            mouse_delta: mem::take(&mut self.mouse_delta),
+            scroll_lines: mem::take(&mut self.scroll_lines),
+            scroll_pixels: mem::take(&mut self.scroll_pixels),
+            mouse_inside: info.mouse_inside,
+            mouse_events: mem::take(&mut self.mouse_events),
+            key_events: mem::take(&mut self.key_events),
+            misc_events: mem::take(&mut self.misc_events),
+            gamepad_events: mem::take(&mut self.gamepad_events),
+            text_input: mem::take(&mut self.text_input),
+            update_errors: mem::take(&mut self.update_errors),
+            down_keys: info.down_keys,
+            down_mouse: info.down_mouse,
+            gamepads: info.gamepads
+        }
+    }
 }
 
 /// Macro for easily doing things if particular keys are down
@@ -194,3 +533,46 @@ macro_rules! is_down{
         $( if $( $info.is_down(&$crate::VirtualKeyCode::$key) )||+ $b )+
     }}
 }
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn repeated_presses_are_dropped(){
+        use self::VirtualKeyCode::{A, B};
+        let (mut down, mut keys) = (HashSet::new(), Vec::new());
+
+        for &(state, key) in &[(ElementState::Pressed, A), (ElementState::Pressed, A), (ElementState::Pressed, B),
+                               (ElementState::Pressed, A), (ElementState::Released, A), (ElementState::Pressed, A)] {
+            key_event(&mut down, &mut keys, state, key);
+        }
+        assert_eq!(keys, [(true, A), (true, B), (false, A), (true, A)]);
+        assert!(down.contains(&A) && down.contains(&B));
+    }
+
+    #[test]
+    fn cursor_moves_are_scaled_and_go_up(){
+        assert_eq!(cursor_delta((10, 10), (14, 4), (1., 1.)), (4., 6.));
+        // A window twice the size of the virtual resolution moves half as far
+        assert_eq!(cursor_delta((10, 10), (14, 4), (2., 3.)), (2., 2.));
+        assert_eq!(cursor_delta((10, 10), (10, 10), (2., 2.)), (0., 0.));
+    }
+
+    #[test]
+    fn recordings_number_their_frames(){
+        let dir = env::temp_dir().join("korome_recording_dirs");
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(start_recording(dir.join("frames")), Ok(dir.join("frames")));
+        assert!(dir.join("frames").is_dir());
+        assert_eq!(frame_path(&dir, 12), dir.join("frame00012.png"));
+
+        // A directory can't be made inside a file
+        fs::write(dir.join("file"), b"").unwrap();
+        let error = start_recording(dir.join("file").join("frames")).unwrap_err();
+        assert_eq!(error.update, GameUpdate::StartRecording(dir.join("file").join("frames")));
+        assert!(!error.reason.is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+use super::{Texture, Drawer, DrawTarget, FrameInfo};
+
+use std::f32::consts::PI;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One particle of a `ParticleEmitter`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle{
+    /// The position of the particle in the world
+    pub pos: (f32, f32),
+    /// How far the particle moves each second
+    pub velocity: (f32, f32),
+    /// The rotation of the particle
+    pub rotation: f32,
+    /// How much the particle rotates each second
+    pub spin: f32,
+    /// How many seconds the particle has lived
+    pub age: f32,
+    /// How many seconds the particle lives in total
+    pub lifetime: f32
+}
+
+impl Particle{
+    #[inline]
+    /// Returns how far the particle is through its life, from `0.` when spawned to `1.` when it dies
+    ///
+    /// Particles that don't live at all are always at the end of their life.
+    pub fn life(&self) -> f32{
+        if self.lifetime > 0. {
+            self.age / self.lifetime
+        } else {
+            1.
+        }
+    }
+}
+
+// A small xorshift generator, so emitters don't need a dependency for random numbers
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng{
+    fn new(seed: u64) -> Self{
+        // Zero would only ever give zero
+        Rng(seed.max(1))
+    }
+    fn next(&mut self) -> u64{
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    // A number from `min` up to `max`
+    fn range(&mut self, (min, max): (f32, f32)) -> f32{
+        let unit = (self.next() >> 40) as f32 / (1u64 << 24) as f32;
+        min + (max - min) * unit
+    }
+}
+
+/// Spawns, moves and draws many particles at once, e.g. for explosions, smoke or sparks
+///
+/// Settings are chosen with the builder methods, where ranges are given as `(min, max)`
+/// and each particle gets a random value in between.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// use korome::*;
+///
+/// # fn main(){
+/// let graphics = Graphics::new("Smoke", 800, 600).unwrap();
+/// let texture = Texture::from_file(&graphics, "smoke.png").unwrap();
+/// let mut smoke = ParticleEmitter::new(40.)
+///     .lifetime((1., 2.))
+///     .speed((20., 40.))
+///     .angle((1.2, 1.9))
+///     .gravity((10., 0.))
+///     .colour_over_life(vec![(0., [1., 1., 1., 0.8]), (1., [0.3, 0.3, 0.3, 0.])])
+///     .size_over_life(vec![(0., 0.5), (1., 2.)]);
+///
+/// run_until_closed(graphics, |info: &FrameInfo, drawer: &mut Drawer| {
+///     smoke.update(info);
+///     drawer.clear(0., 0., 0.);
+///     smoke.draw(&texture, drawer);
+/// });
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParticleEmitter{
+    /// Where new particles are spawned
+    pub pos: (f32, f32),
+    /// Whether new particles are spawned over time, particles already spawned keep going either way
+    pub emitting: bool,
+    rate: f32,
+    area: (f32, f32),
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    angle: (f32, f32),
+    rotation: (f32, f32),
+    spin: (f32, f32),
+    gravity: (f32, f32),
+    colours: Vec<(f32, [f32; 4])>,
+    sizes: Vec<(f32, f32)>,
+    max_particles: usize,
+    particles: Vec<Particle>,
+    // Fractions of particles waiting to be spawned
+    accumulator: f32,
+    rng: Rng
+}
+
+impl ParticleEmitter{
+    /// Creates an emitter spawning `rate` particles each second at the origin.
+    ///
+    /// By default particles live one second, fly in any direction at 50 pixels per second,
+    /// keep their colour and size, and there can be at most 10000 at once.
+    pub fn new(rate: f32) -> Self{
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() ^ u64::from(d.subsec_nanos())).unwrap_or(0);
+
+        ParticleEmitter{
+            pos: (0., 0.),
+            emitting: true,
+            rate,
+            area: (0., 0.),
+            lifetime: (1., 1.),
+            speed: (50., 50.),
+            angle: (0., 2. * PI),
+            rotation: (0., 0.),
+            spin: (0., 0.),
+            gravity: (0., 0.),
+            colours: Vec::new(),
+            sizes: Vec::new(),
+            max_particles: 10_000,
+            particles: Vec::new(),
+            accumulator: 0.,
+            rng: Rng::new(seed)
+        }
+    }
+    set!{/// Sets where new particles are spawned
+        fn ParticleEmitter; pos: (f32, f32)}
+    set!{/// Sets how many particles are spawned each second
+        fn ParticleEmitter; rate: f32}
+    set!{/// Sets half the width and height of the rectangle around the position particles are spawned in
+        fn ParticleEmitter; area: (f32, f32)}
+    set!{/// Sets the range of how many seconds particles live
+        fn ParticleEmitter; lifetime: (f32, f32)}
+    set!{/// Sets the range of how fast particles start moving in pixels per second
+        fn ParticleEmitter; speed: (f32, f32)}
+    set!{/// Sets the range of directions particles start moving in, as angles counter-clockwise from the right
+        fn ParticleEmitter; angle: (f32, f32)}
+    set!{/// Sets the range of rotations particles are spawned with
+        fn ParticleEmitter; rotation: (f32, f32)}
+    set!{/// Sets the range of how much particles rotate each second
+        fn ParticleEmitter; spin: (f32, f32)}
+    set!{/// Sets the acceleration of all particles, e.g. `(0., -200.)` to make them fall
+        fn ParticleEmitter; gravity: (f32, f32)}
+    set!{/// Sets the colours particles have through their life, as points from `0.` to `1.`
+        /// and the colour at that point, which the colours in between blend between.
+        ///
+        /// Particles are drawn with the texture's own colours when empty.
+        fn ParticleEmitter; colour_over_life: Vec<(f32, [f32; 4])> => colours: colour_over_life}
+    set!{/// Sets how many times larger than the texture particles are through their life,
+        /// as points from `0.` to `1.` and the size at that point, which the sizes in between blend between.
+        ///
+        /// Particles keep the texture's size when empty.
+        fn ParticleEmitter; size_over_life: Vec<(f32, f32)> => sizes: size_over_life}
+    set!{/// Sets the most particles there can be at once, no more are spawned while there are this many
+        fn ParticleEmitter; max_particles: usize}
+    /// Sets the seed of the random numbers, so the same settings always give the same particles
+    pub fn seed(self, seed: u64) -> Self{
+        ParticleEmitter{
+            rng: Rng::new(seed),
+            .. self
+        }
+    }
+
+    #[inline]
+    /// Returns the living particles
+    pub fn particles(&self) -> &[Particle]{
+        &self.particles
+    }
+    #[inline]
+    /// Returns how many particles are alive
+    pub fn len(&self) -> usize{
+        self.particles.len()
+    }
+    #[inline]
+    /// Checks whether there are no particles alive,
+    /// e.g. to remove a one-off explosion once it's over
+    pub fn is_empty(&self) -> bool{
+        self.particles.is_empty()
+    }
+    #[inline]
+    /// Removes all the particles
+    pub fn clear(&mut self){
+        self.particles.clear();
+    }
+
+    /// Spawns `count` particles right away, e.g. for an explosion
+    pub fn burst(&mut self, count: usize){
+        let count = count.min(self.max_particles.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+    }
+
+    fn spawn(&mut self) -> Particle{
+        let rng = &mut self.rng;
+        let pos = (self.pos.0 + rng.range((-self.area.0, self.area.0)), self.pos.1 + rng.range((-self.area.1, self.area.1)));
+        let (sin, cos) = rng.range(self.angle).sin_cos();
+        let speed = rng.range(self.speed);
+
+        Particle{
+            pos,
+            velocity: (cos * speed, sin * speed),
+            rotation: rng.range(self.rotation),
+            spin: rng.range(self.spin),
+            age: 0.,
+            lifetime: rng.range(self.lifetime)
+        }
+    }
+
+    #[inline]
+    /// Steps the particles by the time passed since the last frame
+    pub fn update(&mut self, info: &FrameInfo){
+        self.step(info.delta)
+    }
+    /// Moves and ages the particles by `delta` seconds, removing those whose life is over,
+    /// and spawns new particles if it's emitting
+    pub fn step(&mut self, delta: f32){
+        let (gx, gy) = self.gravity;
+        self.particles.retain_mut(|p| {
+            p.age += delta;
+            if p.age >= p.lifetime {
+                return false
+            }
+            p.velocity = (p.velocity.0 + gx * delta, p.velocity.1 + gy * delta);
+            p.pos = (p.pos.0 + p.velocity.0 * delta, p.pos.1 + p.velocity.1 * delta);
+            p.rotation += p.spin * delta;
+            true
+        });
+
+        if self.emitting {
+            self.accumulator += self.rate * delta;
+            let count = self.accumulator as usize;
+            self.accumulator -= count as f32;
+            self.burst(count);
+        }
+    }
+
+    /// Returns the colour of a particle at a point in its life, from `0.` to `1.`
+    pub fn colour_at(&self, life: f32) -> [f32; 4]{
+        let stops = &self.colours;
+        match blend(stops.iter().map(|s| s.0), life) {
+            Some((a, b, t)) => {
+                let (a, b) = (stops[a].1, stops[b].1);
+                [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+            }
+            None => [1., 1., 1., 1.]
+        }
+    }
+    /// Returns how many times larger than the texture a particle is at a point in its life, from `0.` to `1.`
+    pub fn size_at(&self, life: f32) -> f32{
+        let stops = &self.sizes;
+        match blend(stops.iter().map(|s| s.0), life) {
+            Some((a, b, t)) => stops[a].1 + (stops[b].1 - stops[a].1) * t,
+            None => 1.
+        }
+    }
+
+    /// Returns the size and colour a particle is drawn with at its point in life
+    pub fn appearance(&self, particle: &Particle) -> (f32, [f32; 4]){
+        let life = particle.life();
+        (self.size_at(life), self.colour_at(life))
+    }
+
+    /// Adds all the particles to the `Drawer`'s batch as the texture in one go
+    pub fn draw<S: DrawTarget>(&self, texture: &Texture, drawer: &mut Drawer<S>){
+        let sprites = self.particles.iter().map(|p| {
+            let (size, colour) = self.appearance(p);
+            texture.corners(p.pos, p.rotation.sin_cos(), size, colour)
+        });
+        drawer.push_sprites(texture, sprites);
+    }
+}
+
+// Finds the two stops around `t` and how far it is between them,
+// or `None` if there are no stops
+fn blend<I: Iterator<Item=f32>>(stops: I, t: f32) -> Option<(usize, usize, f32)>{
+    let mut last = None;
+    for (i, stop) in stops.enumerate() {
+        match last {
+            None if t <= stop => return Some((i, i, 0.)),
+            Some((j, prev)) if t <= stop => {
+                let span = stop - prev;
+                let along = if span > 0. { (t - prev) / span } else { 1. };
+                return Some((j, i, along))
+            }
+            _ => last = Some((i, stop))
+        }
+    }
+    last.map(|(i, _)| (i, i, 0.))
+}
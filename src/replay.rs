@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+use std::io::{self, Write, BufWriter};
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::fs::{self, File};
+use std::str::FromStr;
+
+use super::{Graphics, Game, Gamepads, GamepadEvent, GamepadButton, GamepadAxis, MockBackend, Binding};
+use logic::{run, InputSource, LiveInput, FrameInput};
+
+quick_error! {
+    /// Wraps together errors that can occur loading a `Replay`
+    #[derive(Debug)]
+    pub enum ReplayError{
+        /// A line in the recording couldn't be understood
+        ParseError(line: usize, reason: &'static str){
+            description(reason)
+            display("line {} of recording: {}", line, reason)
+        }
+        /// An `std::io::Error`
+        IoError(err: io::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+    }
+}
+
+const HEADER: &str = "korome recording 1";
+
+/// Runs the game like `run_until_closed` while recording the input of every frame to a file
+///
+/// The recording has the time passed, the key, mouse, text and gamepad events, the mouse position and
+/// the keys and buttons held down in each frame, and can be played back with `replay_until_closed`.
+/// Events that end up in `FrameInfo::get_misc_events` aren't recorded.
+pub fn record_until_closed<G: Game, P: AsRef<Path>>(graphics: Graphics, game: G, path: P) -> io::Result<()>{
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "{}", HEADER)?;
+
+    let mut input = RecordInput{
+        live: LiveInput::new(),
+        out,
+        error: None
+    };
+    run(graphics, game, &mut input);
+
+    match input.error {
+        Some(e) => Err(e),
+        None => input.out.flush()
+    }
+}
+
+/// Runs the game with the input from a recording instead of the window, until the recording ends or the game closes
+///
+/// Input from the window is ignored, except for it being closed and resized,
+/// and the game gets the same time passed each frame as when it was recorded,
+/// so a game that only depends on its input does exactly the same as when it was recorded.
+/// Textures loaded with `Graphics::texture_async` are waited for at the start of the next frame,
+/// both here and while recording, so they're ready on the same frame each time.
+/// This works with `Graphics::headless` too, for testing the game logic.
+pub fn replay_until_closed<G: Game>(graphics: Graphics, game: G, replay: Replay){
+    let backend = MockBackend::new();
+    let mut gamepads = Gamepads::new(Box::new(backend.clone()));
+    // The recorded axes have already been through the dead zone
+    gamepads.set_dead_zone(0.);
+
+    run(graphics, game, &mut ReplayInput{
+        live: LiveInput::new(),
+        frames: replay.frames.into(),
+        backend,
+        gamepads
+    });
+}
+
+/// The input of a game recorded by `record_until_closed`, which `replay_until_closed` plays back
+#[derive(Debug, Default)]
+pub struct Replay{
+    frames: Vec<FrameInput>
+}
+
+impl Replay{
+    #[inline]
+    /// Loads a recording from a file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError>{
+        Replay::parse(&fs::read_to_string(path)?)
+    }
+    /// Parses a recording
+    pub fn parse(recording: &str) -> Result<Self, ReplayError>{
+        let mut lines = recording.lines().enumerate();
+        if lines.next().map(|(_, l)| l.trim()) != Some(HEADER) {
+            return Err(ReplayError::ParseError(1, "not a korome recording"))
+        }
+
+        let mut frames: Vec<FrameInput> = Vec::new();
+        for (i, line) in lines {
+            let err = |reason| ReplayError::ParseError(i + 1, reason);
+            let mut words = line.split_whitespace();
+            let kind = match words.next() {
+                Some(kind) => kind,
+                None => continue
+            };
+
+            if kind == "frame" {
+                frames.push(FrameInput{
+                    delta: parse(words.next(), err)?,
+                    .. FrameInput::default()
+                });
+                continue
+            }
+            let frame = frames.last_mut().ok_or_else(|| err("input before the first frame"))?;
+
+            match kind {
+                "mouse" => {
+                    frame.mousepos = (parse(words.next(), err)?, parse(words.next(), err)?);
+                    frame.mouse_delta = (parse(words.next(), err)?, parse(words.next(), err)?);
+                    frame.mouse_inside = match words.next() {
+                        Some("inside") => true,
+                        Some("outside") => false,
+                        _ => return Err(err("expected `inside` or `outside`"))
+                    };
+                }
+                "scroll" => {
+                    frame.scroll_lines = (parse(words.next(), err)?, parse(words.next(), err)?);
+                    frame.scroll_pixels = (parse(words.next(), err)?, parse(words.next(), err)?);
+                }
+                "key" | "button" => {
+                    let pressed = parse_state(words.next(), err)?;
+                    match (kind, parse_binding(words.next(), err)?) {
+                        ("key", Binding::Key(key)) => frame.key_events.push((pressed, key)),
+                        ("button", Binding::Mouse(button)) => frame.mouse_events.push((pressed, button)),
+                        _ => return Err(err("expected a key for `key` and a mouse button for `button`"))
+                    }
+                }
+                "text" => for code in words {
+                    let c = code.parse().ok().and_then(::std::char::from_u32).ok_or_else(|| err("invalid character code"))?;
+                    frame.text_input.push(c);
+                },
+                "down" => for name in words {
+                    match parse_binding(Some(name), err)? {
+                        Binding::Key(key) => frame.down_keys.insert(key),
+                        Binding::Mouse(button) => frame.down_mouse.insert(button)
+                    };
+                },
+                "pad" => {
+                    let event = words.next();
+                    let id = parse(words.next(), err)?;
+                    frame.gamepad_events.push(match event {
+                        Some("connected") => GamepadEvent::Connected(id, words.collect::<Vec<_>>().join(" ")),
+                        Some("disconnected") => GamepadEvent::Disconnected(id),
+                        Some("button") => {
+                            let pressed = parse_state(words.next(), err)?;
+                            let button = words.next().and_then(button_from_name).ok_or_else(|| err("unknown gamepad button"))?;
+                            GamepadEvent::Button(id, button, pressed)
+                        }
+                        Some("axis") => {
+                            let axis = words.next().and_then(axis_from_name).ok_or_else(|| err("unknown gamepad axis"))?;
+                            GamepadEvent::Axis(id, axis, parse(words.next(), err)?)
+                        }
+                        _ => return Err(err("unknown gamepad event"))
+                    });
+                }
+                _ => return Err(err("unknown kind of input"))
+            }
+        }
+
+        Ok(Replay{frames})
+    }
+    #[inline]
+    /// Returns the amount of frames recorded
+    pub fn len(&self) -> usize{
+        self.frames.len()
+    }
+    #[inline]
+    /// Checks whether no frames were recorded
+    pub fn is_empty(&self) -> bool{
+        self.frames.is_empty()
+    }
+    /// Returns the time the recording lasts in seconds
+    pub fn duration(&self) -> f32{
+        self.frames.iter().map(|f| f.delta).sum()
+    }
+    /// Saves the recording to a file, which is the same as what `to_string` gives
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()>{
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()
+    }
+
+    fn write<W: Write>(&self, out: &mut W) -> io::Result<()>{
+        writeln!(out, "{}", HEADER)?;
+        for frame in &self.frames {
+            write_frame(out, frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Replay{
+    /// Writes the recording in the format it's saved in
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let mut buf = Vec::new();
+        self.write(&mut buf).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&buf))
+    }
+}
+
+fn parse<T: FromStr, F: Fn(&'static str) -> ReplayError>(word: Option<&str>, err: F) -> Result<T, ReplayError>{
+    word.and_then(|w| w.parse().ok()).ok_or_else(|| err("missing or invalid number"))
+}
+
+fn parse_state<F: Fn(&'static str) -> ReplayError>(word: Option<&str>, err: F) -> Result<bool, ReplayError>{
+    match word {
+        Some("press") => Ok(true),
+        Some("release") => Ok(false),
+        _ => Err(err("expected `press` or `release`"))
+    }
+}
+
+fn parse_binding<F: Fn(&'static str) -> ReplayError>(word: Option<&str>, err: F) -> Result<Binding, ReplayError>{
+    word.and_then(Binding::from_name).ok_or_else(|| err("unknown key or mouse button"))
+}
+
+macro_rules! names {
+    ($from_name:ident, $T:ident; $($variant:ident),*) => (
+        fn $from_name(name: &str) -> Option<$T>{
+            match name {
+                $(stringify!($variant) => Some($T::$variant),)*
+                // Written as `Other(n)` like `Debug` does
+                _ if name.starts_with("Other(") && name.ends_with(')') => name[6..name.len()-1].parse().ok().map($T::Other),
+                _ => None
+            }
+        }
+    );
+}
+
+names!{button_from_name, GamepadButton;
+    A, B, X, Y, LeftBumper, RightBumper, Select, Start, Mode, LeftStick, RightStick}
+names!{axis_from_name, GamepadAxis;
+    LeftStickX, LeftStickY, RightStickX, RightStickY, LeftTrigger, RightTrigger, DPadX, DPadY}
+
+fn state(pressed: bool) -> &'static str{
+    if pressed {"press"} else {"release"}
+}
+
+fn write_list<W: Write, T: Display, I: Iterator<Item = T>>(out: &mut W, kind: &str, items: I) -> io::Result<()>{
+    let items: Vec<_> = items.map(|i| i.to_string()).collect();
+    if items.is_empty() {
+        Ok(())
+    } else {
+        writeln!(out, "{} {}", kind, items.join(" "))
+    }
+}
+
+fn write_frame<W: Write>(out: &mut W, frame: &FrameInput) -> io::Result<()>{
+    writeln!(out, "frame {}", frame.delta)?;
+    let ((x, y), (dx, dy)) = (frame.mousepos, frame.mouse_delta);
+    writeln!(out, "mouse {} {} {} {} {}", x, y, dx, dy, if frame.mouse_inside {"inside"} else {"outside"})?;
+    if frame.scroll_lines != (0., 0.) || frame.scroll_pixels != (0., 0.) {
+        let ((lx, ly), (px, py)) = (frame.scroll_lines, frame.scroll_pixels);
+        writeln!(out, "scroll {} {} {} {}", lx, ly, px, py)?;
+    }
+    for &(pressed, key) in &frame.key_events {
+        writeln!(out, "key {} {}", state(pressed), Binding::Key(key))?;
+    }
+    for &(pressed, button) in &frame.mouse_events {
+        writeln!(out, "button {} {}", state(pressed), Binding::Mouse(button))?;
+    }
+    write_list(out, "text", frame.text_input.chars().map(|c| c as u32))?;
+
+    // Sorted so the same input gives the same recording
+    let mut down: Vec<_> = frame.down_keys.iter().map(|&k| Binding::Key(k))
+        .chain(frame.down_mouse.iter().map(|&b| Binding::Mouse(b)))
+        .map(|b| b.to_string())
+        .collect();
+    down.sort();
+    write_list(out, "down", down.iter())?;
+
+    for event in &frame.gamepad_events {
+        match *event {
+            GamepadEvent::Connected(id, ref name) => writeln!(out, "pad connected {} {}", id, name)?,
+            GamepadEvent::Disconnected(id) => writeln!(out, "pad disconnected {}", id)?,
+            GamepadEvent::Button(id, button, pressed) => writeln!(out, "pad button {} {} {:?}", id, state(pressed), button)?,
+            GamepadEvent::Axis(id, axis, value) => writeln!(out, "pad axis {} {:?} {}", id, axis, value)?
+        }
+    }
+    Ok(())
+}
+
+// Live input that's written to a file as well
+struct RecordInput{
+    live: LiveInput,
+    out: BufWriter<File>,
+    error: Option<io::Error>
+}
+
+impl InputSource for RecordInput{
+    fn next_frame(&mut self, graphics: &mut Graphics) -> Option<FrameInput>{
+        let frame = self.live.next_frame(graphics)?;
+        if let Err(e) = write_frame(&mut self.out, &frame) {
+            // Stop the game, since the rest of it can't be recorded anyway
+            self.error = Some(e);
+            return None
+        }
+        Some(frame)
+    }
+    #[inline]
+    fn gamepads(&self) -> &Gamepads{
+        self.live.gamepads()
+    }
+    #[inline]
+    fn waits_for_loads(&self) -> bool{
+        true
+    }
+}
+
+struct ReplayInput{
+    // Only used for the window being closed or resized
+    live: LiveInput,
+    frames: VecDeque<FrameInput>,
+    backend: MockBackend,
+    gamepads: Gamepads
+}
+
+impl InputSource for ReplayInput{
+    fn next_frame(&mut self, graphics: &mut Graphics) -> Option<FrameInput>{
+        self.live.next_frame(graphics)?;
+        let frame = self.frames.pop_front()?;
+
+        for event in &frame.gamepad_events {
+            self.backend.push(event.clone());
+        }
+        self.gamepads.update();
+
+        Some(frame)
+    }
+    #[inline]
+    fn gamepads(&self) -> &Gamepads{
+        &self.gamepads
+    }
+    #[inline]
+    fn waits_for_loads(&self) -> bool{
+        true
+    }
+}
@@ -0,0 +1,110 @@
+use glium::Rect;
+
+/// How a `VirtualResolution` is scaled to fit the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode{
+    /// Scales it as much as possible while keeping its aspect ratio,
+    /// with black bars on the sides or at the top and bottom
+    Fit,
+    /// Stretches it to fill the whole window
+    Stretch,
+    /// Scales it by the largest whole number that fits, with black bars around it, to keep pixel art sharp.
+    ///
+    /// Falls back to `Fit` when the window is smaller than the virtual resolution.
+    PixelPerfect
+}
+
+/// A fixed logical size that everything is drawn at, no matter how big the window is
+///
+/// Set it with `Graphics::set_virtual_resolution`,
+/// after which drawing and `FrameInfo::mousepos` use the logical size instead of the window's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualResolution{
+    /// The logical width
+    pub width: f32,
+    /// The logical height
+    pub height: f32,
+    /// How it's scaled to the window
+    pub mode: ScaleMode
+}
+
+impl VirtualResolution{
+    #[inline]
+    /// Creates a virtual resolution of the given logical size
+    pub fn new(width: f32, height: f32, mode: ScaleMode) -> Self{
+        VirtualResolution{
+            width,
+            height,
+            mode
+        }
+    }
+    #[inline]
+    /// Returns half of the logical size
+    pub fn h_size(&self) -> (f32, f32){
+        (self.width / 2., self.height / 2.)
+    }
+
+    /// Returns the part of a window of the given size in pixels that is drawn to
+    ///
+    /// It's empty if the window or the virtual resolution has no size, e.g. while the window is minimised.
+    pub fn viewport(&self, window_width: u32, window_height: u32) -> Rect{
+        let (win_w, win_h) = (window_width as f32, window_height as f32);
+        let fit = (win_w / self.width).min(win_h / self.height);
+        if self.mode != ScaleMode::Stretch && !(self.width > 0. && self.height > 0. && fit > 0.) {
+            return Rect{
+                left: window_width / 2,
+                bottom: window_height / 2,
+                width: 0,
+                height: 0
+            }
+        }
+
+        let scale = match self.mode {
+            ScaleMode::Stretch => return Rect{
+                left: 0,
+                bottom: 0,
+                width: window_width,
+                height: window_height
+            },
+            ScaleMode::Fit => fit,
+            ScaleMode::PixelPerfect if fit >= 1. => fit.floor(),
+            ScaleMode::PixelPerfect => fit
+        };
+
+        let width = ((self.width * scale).round() as u32).min(window_width);
+        let height = ((self.height * scale).round() as u32).min(window_height);
+        Rect{
+            left: (window_width - width) / 2,
+            bottom: (window_height - height) / 2,
+            width,
+            height
+        }
+    }
+    /// Converts a position in pixels from the top-left corner of a window of the given size
+    /// into logical coordinates with the origin in the centre and up being positive.
+    ///
+    /// Positions in the black bars end up outside the logical size.
+    /// Everything is at the origin when nothing is drawn, see `viewport`.
+    pub fn to_logical(&self, window_size: (u32, u32), (x, y): (f32, f32)) -> (f32, f32){
+        let (sx, sy) = self.scale(window_size);
+        let viewport = self.viewport(window_size.0, window_size.1);
+        if viewport.width == 0 || viewport.height == 0 || !(self.width > 0. && self.height > 0.) {
+            return (0., 0.)
+        }
+        let (left, bottom) = (viewport.left as f32, viewport.bottom as f32);
+        let (w, h) = (viewport.width as f32, viewport.height as f32);
+
+        (
+            (x - left - w / 2.) / sx,
+            (window_size.1 as f32 - y - bottom - h / 2.) / sy
+        )
+    }
+    /// Returns how many pixels wide and high one logical unit is in a window of the given size
+    ///
+    /// It's `1.` when nothing is drawn, so it can always be divided by.
+    pub fn scale(&self, (window_width, window_height): (u32, u32)) -> (f32, f32){
+        let viewport = self.viewport(window_width, window_height);
+        let scale = |pixels: u32, logical: f32| if pixels > 0 && logical > 0. {pixels as f32 / logical} else {1.};
+        (scale(viewport.width, self.width), scale(viewport.height, self.height))
+    }
+}
@@ -0,0 +1,147 @@
+use super::{Game, GameUpdate, FrameInfo, Drawer};
+
+/// What a `Scene` wants to happen after its frame, much like `GameUpdate`
+pub enum SceneUpdate{
+    /// Keeps going with the same scene
+    Nothing,
+    /// Pauses the scene and puts a new scene on top of it
+    Push(Box<dyn Scene>),
+    /// Removes the scene, resuming the one below it, or closing the game if there is none
+    Pop,
+    /// Removes the scene and puts a new scene in its place
+    Replace(Box<dyn Scene>),
+    /// Passes a `GameUpdate` on to `run_until_closed`, e.g. to close the game
+    Game(GameUpdate)
+}
+
+impl From<()> for SceneUpdate{
+    /// Simply returns `SceneUpdate::Nothing`
+    fn from(_: ()) -> Self{
+        SceneUpdate::Nothing
+    }
+}
+
+impl From<GameUpdate> for SceneUpdate{
+    /// Wraps the `GameUpdate` in `SceneUpdate::Game`
+    fn from(update: GameUpdate) -> Self{
+        SceneUpdate::Game(update)
+    }
+}
+
+/// One part of a game, like a menu, a level or a pause screen, that a `SceneStack` switches between
+///
+/// Only `frame` has to be implemented, the hooks do nothing by default.
+pub trait Scene{
+    /// Called each frame while the scene is at the top of the stack
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> SceneUpdate;
+    /// Called each frame instead of `frame` while the scene is shown below an overlay
+    fn draw(&mut self, _drawer: &mut Drawer){}
+    /// Whether the scene below this one should still be drawn under it, e.g. for a pause menu
+    fn is_overlay(&self) -> bool{
+        false
+    }
+    /// Called when the scene is put onto the stack
+    fn enter(&mut self){}
+    /// Called when the scene is removed from the stack
+    fn exit(&mut self){}
+    /// Called when another scene is put on top of this one
+    fn pause(&mut self){}
+    /// Called when the scene on top of this one is removed
+    fn resume(&mut self){}
+}
+
+/// A stack of `Scene`s where the top one is running, which can be run with `run_until_closed`
+///
+/// Scenes change the stack by what they return from `Scene::frame`.
+/// The game closes when the last scene is popped.
+#[derive(Default)]
+pub struct SceneStack{
+    scenes: Vec<Box<dyn Scene>>
+}
+
+impl SceneStack{
+    #[inline]
+    /// Creates a stack with just the first scene
+    pub fn new(first: Box<dyn Scene>) -> Self{
+        let mut stack = SceneStack::default();
+        stack.push(first);
+        stack
+    }
+    /// Pauses the top scene and puts a new scene on top of it
+    pub fn push(&mut self, mut scene: Box<dyn Scene>){
+        if let Some(top) = self.scenes.last_mut() {
+            top.pause();
+        }
+        scene.enter();
+        self.scenes.push(scene);
+    }
+    /// Removes the top scene and resumes the one below it
+    pub fn pop(&mut self) -> Option<Box<dyn Scene>>{
+        let mut scene = self.scenes.pop();
+        if let Some(ref mut scene) = scene {
+            scene.exit();
+        }
+        if let Some(top) = self.scenes.last_mut() {
+            top.resume();
+        }
+        scene
+    }
+    /// Removes the top scene and puts a new scene in its place
+    pub fn replace(&mut self, mut scene: Box<dyn Scene>) -> Option<Box<dyn Scene>>{
+        let mut old = self.scenes.pop();
+        if let Some(ref mut old) = old {
+            old.exit();
+        }
+        scene.enter();
+        self.scenes.push(scene);
+        old
+    }
+    #[inline]
+    /// Returns the amount of scenes on the stack
+    pub fn len(&self) -> usize{
+        self.scenes.len()
+    }
+    #[inline]
+    /// Checks whether there are no scenes on the stack
+    pub fn is_empty(&self) -> bool{
+        self.scenes.is_empty()
+    }
+}
+
+impl Game for SceneStack{
+    type ReturnType = GameUpdate;
+
+    fn frame(&mut self, info: &FrameInfo, drawer: &mut Drawer) -> GameUpdate{
+        let top = match self.scenes.len().checked_sub(1){
+            Some(top) => top,
+            None => return GameUpdate::Close
+        };
+
+        // Draw every scene that is visible under the overlays on top, from the bottom up
+        let mut bottom = top;
+        while bottom > 0 && self.scenes[bottom].is_overlay() {
+            bottom -= 1;
+        }
+        for scene in &mut self.scenes[bottom..top] {
+            scene.draw(drawer);
+        }
+
+        match self.scenes[top].frame(info, drawer) {
+            SceneUpdate::Nothing => (),
+            SceneUpdate::Push(scene) => self.push(scene),
+            SceneUpdate::Pop => {
+                self.pop();
+            }
+            SceneUpdate::Replace(scene) => {
+                self.replace(scene);
+            }
+            SceneUpdate::Game(update) => return update
+        }
+
+        if self.scenes.is_empty() {
+            GameUpdate::Close
+        } else {
+            GameUpdate::Nothing
+        }
+    }
+}
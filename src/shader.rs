@@ -0,0 +1,251 @@
+use glium::{Display, Program, ProgramCreationError};
+use glium::texture::Texture2d;
+use glium::uniforms::{self, UniformValue};
+
+use std::rc::Rc;
+
+use super::Texture;
+
+/// A shader program supplied by the user to draw with instead of korome's own
+///
+/// Vertex shaders for textures get the attributes `vec2 position`, `vec2 tex_coords` and `vec4 colour`,
+/// and vertex shaders for quads get `vec2 position` and `vec4 colour`.
+/// Positions are in the world, so they have to be transformed by the `mat4 view` uniform
+/// and divided by the `vec2 h_size` uniform (half the size of the screen) like korome's shaders do.
+/// Quads also get the `mat4 matrix` uniform with their position and rotation,
+/// and textures get their texture as `sampler2D tex`.
+#[derive(Debug, Clone)]
+pub struct Shader{
+    program: Rc<Program>
+}
+
+impl Shader{
+    /// Compiles a shader program from the source of a vertex and a fragment shader
+    pub fn new(display: &Display, vertex_shader: &str, fragment_shader: &str) -> Result<Self, ProgramCreationError>{
+        Program::from_source(display, vertex_shader, fragment_shader, None)
+            .map(|program| Shader{program: Rc::new(program)})
+    }
+    #[inline]
+    /// Compiles a shader program for textures using korome's vertex shader.
+    ///
+    /// The fragment shader gets `vec2 v_tex_coords` and `vec4 v_colour` as inputs.
+    pub fn for_textures(display: &Display, fragment_shader: &str) -> Result<Self, ProgramCreationError>{
+        Shader::new(display, include_str!("shaders/texture.vs"), fragment_shader)
+    }
+    #[inline]
+    /// Compiles a shader program for quads using korome's vertex shader.
+    ///
+    /// The fragment shader gets `vec4 v_colour` as input.
+    pub fn for_quads(display: &Display, fragment_shader: &str) -> Result<Self, ProgramCreationError>{
+        Shader::new(display, include_str!("shaders/colour.vs"), fragment_shader)
+    }
+
+    #[inline]
+    pub(crate) fn program(&self) -> &Rc<Program>{
+        &self.program
+    }
+}
+
+/// The value of a custom uniform
+#[derive(Debug, Clone)]
+pub enum Uniform{
+    /// A `float`
+    Float(f32),
+    /// A `vec2`
+    Vec2([f32; 2]),
+    /// A `vec3`
+    Vec3([f32; 3]),
+    /// A `vec4`
+    Vec4([f32; 4]),
+    /// A `mat4`
+    Mat4([[f32; 4]; 4]),
+    /// An `int`
+    Int(i32),
+    /// A `sampler2D`
+    Texture(Texture)
+}
+
+macro_rules! uniform_from {
+    ($($T:ty => $variant:ident),*) => ($(
+        impl From<$T> for Uniform{
+            #[inline]
+            fn from(value: $T) -> Self{
+                Uniform::$variant(value)
+            }
+        }
+    )*);
+}
+
+uniform_from!{
+    f32 => Float,
+    [f32; 2] => Vec2,
+    [f32; 3] => Vec3,
+    [f32; 4] => Vec4,
+    [[f32; 4]; 4] => Mat4,
+    i32 => Int,
+    Texture => Texture
+}
+
+/// A set of named values passed to the uniforms of a custom `Shader`
+///
+/// Cloning it is cheap, and textures drawn with the same (not cloned) `Uniforms` can be batched together.
+#[derive(Debug, Clone, Default)]
+pub struct Uniforms{
+    values: Rc<Vec<(String, Uniform)>>
+}
+
+impl Uniforms{
+    #[inline]
+    /// Creates an empty set of uniforms
+    pub fn new() -> Self{
+        Uniforms::default()
+    }
+    /// Sets the uniform with the given name to the value
+    pub fn with<S: Into<String>, U: Into<Uniform>>(mut self, name: S, value: U) -> Self{
+        self.set(name, value);
+        self
+    }
+    /// Sets the uniform with the given name to the value
+    pub fn set<S: Into<String>, U: Into<Uniform>>(&mut self, name: S, value: U){
+        let (name, value) = (name.into(), value.into());
+        let values = Rc::make_mut(&mut self.values);
+
+        match values.iter_mut().find(|&&mut (ref n, _)| *n == name) {
+            Some(uniform) => uniform.1 = value,
+            None => values.push((name, value))
+        }
+    }
+    /// Returns the value of the uniform with the given name, if it has been set
+    pub fn get(&self, name: &str) -> Option<&Uniform>{
+        self.values.iter().find(|(n, _)| n == name).map(|(_, value)| value)
+    }
+    /// Returns the names and values of the uniforms in the order they were first set
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Uniform)> + '_{
+        self.values.iter().map(|(name, value)| (&**name, value))
+    }
+    #[inline]
+    /// Returns how many uniforms have been set
+    pub fn len(&self) -> usize{
+        self.values.len()
+    }
+    #[inline]
+    /// Checks whether no uniforms have been set
+    pub fn is_empty(&self) -> bool{
+        self.values.is_empty()
+    }
+    #[inline]
+    pub(crate) fn ptr_eq(&self, other: &Uniforms) -> bool{
+        Rc::ptr_eq(&self.values, &other.values)
+    }
+}
+
+// The uniforms a draw call is made with, korome's own followed by any custom ones
+#[derive(Default)]
+pub struct DrawUniforms<'a>{
+    pub h_size: Option<(f32, f32)>,
+    pub view: Option<[[f32; 4]; 4]>,
+    pub matrix: Option<[[f32; 4]; 4]>,
+    pub resolution: Option<[f32; 2]>,
+    pub tex: Option<&'a Texture2d>,
+    pub custom: Option<&'a Uniforms>
+}
+
+impl<'b> uniforms::Uniforms for DrawUniforms<'b>{
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F){
+        if let Some((w, h)) = self.h_size {
+            visit("h_size", UniformValue::Vec2([w, h]));
+        }
+        if let Some(view) = self.view {
+            visit("view", UniformValue::Mat4(view));
+        }
+        if let Some(matrix) = self.matrix {
+            visit("matrix", UniformValue::Mat4(matrix));
+        }
+        if let Some(resolution) = self.resolution {
+            visit("resolution", UniformValue::Vec2(resolution));
+        }
+        if let Some(tex) = self.tex {
+            visit("tex", UniformValue::Texture2d(tex, None));
+        }
+        for (name, value) in self.custom.into_iter().flat_map(|u| u.values.iter()) {
+            visit(name, match *value {
+                Uniform::Float(f) => UniformValue::Float(f),
+                Uniform::Vec2(v) => UniformValue::Vec2(v),
+                Uniform::Vec3(v) => UniformValue::Vec3(v),
+                Uniform::Vec4(v) => UniformValue::Vec4(v),
+                Uniform::Mat4(m) => UniformValue::Mat4(m),
+                Uniform::Int(i) => UniformValue::SignedInt(i),
+                Uniform::Texture(ref texture) => UniformValue::Texture2d(texture.texture2d(), None)
+            });
+        }
+    }
+}
+
+/// A full-screen effect applied to every finished frame, see `Graphics::set_post_processing`
+///
+/// The fragment shader gets `vec2 v_tex_coords` as input along with the uniforms
+/// `sampler2D tex` (the frame so far) and `vec2 resolution` (its size in pixels).
+#[derive(Debug, Clone)]
+pub struct Effect{
+    shader: Shader,
+    uniforms: Uniforms
+}
+
+impl Effect{
+    /// Compiles an effect from the source of its fragment shader
+    pub fn new(display: &Display, fragment_shader: &str) -> Result<Self, ProgramCreationError>{
+        Shader::new(display, include_str!("shaders/post.vs"), fragment_shader)
+            .map(|shader| Effect{
+                shader,
+                uniforms: Uniforms::new()
+            })
+    }
+    #[inline]
+    /// Makes everything gray
+    pub fn grayscale(display: &Display) -> Result<Self, ProgramCreationError>{
+        Effect::new(display, include_str!("shaders/grayscale.fs"))
+    }
+    #[inline]
+    /// Blurs everything by about `radius` pixels
+    pub fn blur(display: &Display, radius: f32) -> Result<Self, ProgramCreationError>{
+        Effect::new(display, include_str!("shaders/blur.fs"))
+            .map(|effect| effect.uniform("radius", radius))
+    }
+    #[inline]
+    /// Makes it look like an old CRT screen with scanlines and curved glass
+    pub fn crt(display: &Display) -> Result<Self, ProgramCreationError>{
+        Effect::new(display, include_str!("shaders/crt.fs"))
+    }
+    #[inline]
+    /// Multiplies the colours by a matrix.
+    ///
+    /// Each colour is multiplied as the column vector `(red, green, blue, 1.)`,
+    /// so the last column of the matrix is added to the colour.
+    /// The matrix is given as columns like in GLSL.
+    pub fn colour_grading(display: &Display, grading: [[f32; 4]; 4]) -> Result<Self, ProgramCreationError>{
+        Effect::new(display, include_str!("shaders/colour_grading.fs"))
+            .map(|effect| effect.uniform("grading", grading))
+    }
+
+    #[inline]
+    /// Sets a uniform of the effect's shader
+    pub fn uniform<S: Into<String>, U: Into<Uniform>>(mut self, name: S, value: U) -> Self{
+        self.uniforms.set(name, value);
+        self
+    }
+    #[inline]
+    /// Returns the uniforms of the effect's shader
+    pub fn uniforms(&self) -> &Uniforms{
+        &self.uniforms
+    }
+    #[inline]
+    /// Returns a mutable reference to the uniforms of the effect's shader
+    pub fn uniforms_mut(&mut self) -> &mut Uniforms{
+        &mut self.uniforms
+    }
+
+    #[inline]
+    pub(crate) fn parts(&self) -> (&Program, &Uniforms){
+        (&self.shader.program, &self.uniforms)
+    }
+}
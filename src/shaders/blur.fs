@@ -0,0 +1,21 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D tex;
+uniform vec2 resolution;
+uniform float radius;
+
+void main() {
+    vec2 step = radius / 2.0 / resolution;
+    vec4 sum = vec4(0.0);
+
+    for (int x = -2; x <= 2; x++) {
+        for (int y = -2; y <= 2; y++) {
+            sum += texture(tex, v_tex_coords + vec2(x, y) * step);
+        }
+    }
+
+    color = sum / 25.0;
+}
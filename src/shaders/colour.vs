@@ -5,11 +5,12 @@ in vec4 colour;
 
 out vec4 v_colour;
 
+uniform mat4 view;
 uniform mat4 matrix;
 uniform vec2 h_size;
 
 void main() {
-    vec4 pos = matrix * vec4(position, 0.0, 1.0);
+    vec4 pos = view * matrix * vec4(position, 0.0, 1.0);
 
     pos.xy /= h_size.xy;
 
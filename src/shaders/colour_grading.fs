@@ -0,0 +1,13 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D tex;
+uniform mat4 grading;
+
+void main() {
+    vec4 c = texture(tex, v_tex_coords);
+
+    color = vec4((grading * vec4(c.rgb, 1.0)).rgb, c.a);
+}
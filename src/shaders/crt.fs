@@ -0,0 +1,25 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D tex;
+uniform vec2 resolution;
+
+void main() {
+    // Bulge the picture out like the glass of a CRT screen
+    vec2 centred = v_tex_coords * 2.0 - 1.0;
+    centred *= 1.0 + 0.1 * dot(centred.yx, centred.yx);
+    vec2 uv = centred * 0.5 + 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec4 c = texture(tex, uv);
+    float scanline = 0.85 + 0.15 * sin(uv.y * resolution.y * 3.14159);
+    float vignette = 1.0 - 0.3 * dot(centred, centred);
+
+    color = vec4(c.rgb * scanline * vignette, c.a);
+}
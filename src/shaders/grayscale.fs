@@ -0,0 +1,13 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D tex;
+
+void main() {
+    vec4 c = texture(tex, v_tex_coords);
+    float luma = dot(c.rgb, vec3(0.299, 0.587, 0.114));
+
+    color = vec4(vec3(luma), c.a);
+}
@@ -1,11 +1,11 @@
 #version 140
 
 in vec2 v_tex_coords;
+in vec4 v_colour;
 out vec4 color;
 
 uniform sampler2D tex;
-uniform vec4 colour;
 
 void main() {
-    color = colour * texture(tex, v_tex_coords);
+    color = v_colour * texture(tex, v_tex_coords);
 }
@@ -2,17 +2,20 @@
 
 in vec2 position;
 in vec2 tex_coords;
+in vec4 colour;
 
 out vec2 v_tex_coords;
+out vec4 v_colour;
 
-uniform mat4 matrix;
+uniform mat4 view;
 uniform vec2 h_size;
 
 void main() {
-    vec4 pos = matrix * vec4(position, 0.0, 1.0);
+    vec4 pos = view * vec4(position, 0.0, 1.0);
 
     pos.xy /= h_size.xy;
 
     gl_Position = pos;
     v_tex_coords = tex_coords;
+    v_colour = colour;
 }
@@ -0,0 +1,319 @@
+use std::borrow::Cow;
+use std::f32::consts::PI;
+
+use super::{Drawer, DrawTarget, Shader, Uniforms};
+use ::vertex::ColourVertex;
+
+#[derive(Debug, Clone)]
+enum Kind<'a>{
+    // An arc from the first to the second angle, or the whole ellipse
+    Ellipse{radii: (f32, f32), angles: Option<(f32, f32)>},
+    Path{points: Cow<'a, [[f32; 2]]>, closed: bool},
+    Polygon(Cow<'a, [[f32; 2]]>),
+    RoundedRect{h_size: (f32, f32), radius: f32}
+}
+
+/// Object for drawing circles, ellipses, arcs, lines, polygons and rounded rectangles using the builder pattern
+///
+/// Shapes are filled unless `outline` is set, except for lines, which are always outlines.
+/// Points of lines and polygons are relative to the position of the shape.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// # use korome::*;
+/// # fn main(){
+/// # let graphics = Graphics::new("Shapes", 800, 600).unwrap();
+/// # let mut drawer = Drawer::new(&graphics);
+/// ShapeDrawer::circle(50.)
+///     .pos((100., 0.))
+///     .colour([1., 0., 0., 1.])
+///     .draw(&mut drawer);
+/// ShapeDrawer::polygon(vec![[0., 0.], [40., 0.], [20., 10.], [40., 40.], [0., 40.]])
+///     .outline(2.)
+///     .draw(&mut drawer);
+/// # }
+/// ```
+#[must_use = "drawers are lazy and do nothing until consumed"]
+#[derive(Debug, Clone)]
+pub struct ShapeDrawer<'a>{
+    kind: Kind<'a>,
+    pos: (f32, f32),
+    sin_cos: (f32, f32),
+    colour: [f32; 4],
+    outline: Option<f32>,
+    segments: Option<u32>,
+    shader: Option<&'a Shader>,
+    uniforms: Option<&'a Uniforms>
+}
+
+impl<'a> ShapeDrawer<'a>{
+    fn new(kind: Kind<'a>) -> Self{
+        ShapeDrawer{
+            kind,
+            pos: (0., 0.),
+            sin_cos: (0., 1.),
+            colour: [1., 1., 1., 1.],
+            outline: None,
+            segments: None,
+            shader: None,
+            uniforms: None
+        }
+    }
+    #[inline]
+    /// A circle with the radius
+    pub fn circle(radius: f32) -> Self{
+        ShapeDrawer::ellipse(radius, radius)
+    }
+    #[inline]
+    /// An ellipse with the horizontal and vertical radii
+    pub fn ellipse(radius_x: f32, radius_y: f32) -> Self{
+        ShapeDrawer::new(Kind::Ellipse{radii: (radius_x, radius_y), angles: None})
+    }
+    #[inline]
+    /// The part of a circle from the start to the end angle, in radians counter-clockwise from the right.
+    ///
+    /// It's filled like a slice of pie, or only the curved line when it's an outline.
+    pub fn arc(radius: f32, start: f32, end: f32) -> Self{
+        ShapeDrawer::new(Kind::Ellipse{radii: (radius, radius), angles: Some((start, end))})
+    }
+    #[inline]
+    /// A straight line between two points, which is one unit thick unless `outline` is set
+    pub fn line(from: (f32, f32), to: (f32, f32)) -> Self{
+        ShapeDrawer::polyline(vec![[from.0, from.1], [to.0, to.1]])
+    }
+    #[inline]
+    /// Lines going through all the points, which are one unit thick unless `outline` is set
+    pub fn polyline<P: Into<Cow<'a, [[f32; 2]]>>>(points: P) -> Self{
+        ShapeDrawer::new(Kind::Path{points: points.into(), closed: false})
+    }
+    #[inline]
+    /// A polygon with the points as its corners, which can be concave but mustn't cross itself
+    pub fn polygon<P: Into<Cow<'a, [[f32; 2]]>>>(points: P) -> Self{
+        ShapeDrawer::new(Kind::Polygon(points.into()))
+    }
+    #[inline]
+    /// A rectangle of the given size centred on its position with corners rounded by the radius
+    pub fn rounded_rect(width: f32, height: f32, radius: f32) -> Self{
+        let radius = radius.min(width / 2.).min(height / 2.).max(0.);
+        ShapeDrawer::new(Kind::RoundedRect{h_size: (width / 2., height / 2.), radius})
+    }
+
+    set!{/// Sets the position of the shape
+        fn ShapeDrawer; pos: (f32, f32)}
+    set!{/// Sets the rotation of the shape around its position
+        fn ShapeDrawer; rotation: f32 => sin_cos: rotation.sin_cos()}
+    set!{/// Sets the colour of the shape
+        fn ShapeDrawer; colour: [f32; 4]}
+    set!{/// Draws only the outline of the shape with the thickness, or sets the thickness of a line
+        fn ShapeDrawer; outline: f32 => outline: Some(outline)}
+    set!{/// Sets how many straight segments curves are made of, which is otherwise worked out from their size
+        fn ShapeDrawer; segments: u32 => segments: Some(segments)}
+    set!{/// Sets a custom shader to draw the shape with instead of korome's own
+        fn ShapeDrawer; shader: &'a Shader => shader: Some(shader)}
+    set!{/// Sets the values of the custom shader's uniforms
+        fn ShapeDrawer; uniforms: &'a Uniforms => uniforms: Some(uniforms)}
+
+    /// Consumes self and draws the shape with the given options
+    pub fn draw<S: DrawTarget>(self, drawer: &mut Drawer<S>){
+        let triangles = self.triangles();
+        let (x, y) = self.pos;
+        let (sin, cos) = self.sin_cos;
+        let colour = self.colour;
+
+        let vertices: Vec<_> = triangles.into_iter().map(|p| ColourVertex::new(p, colour)).collect();
+        drawer.draw_triangles(&vertices, [
+            [ cos, sin, 0., 0.],
+            [-sin, cos, 0., 0.],
+            [  0.,  0., 1., 0.],
+            [  x ,  y , 0., 1.],
+        ], self.shader, self.uniforms);
+    }
+
+    /// Returns the corners of the triangles making up the shape, three for each,
+    /// around its position and before it's rotated
+    pub fn triangles(&self) -> Vec<[f32; 2]>{
+        match self.kind {
+            Kind::Ellipse{radii: (rx, ry), angles} => {
+                let segments = self.segments.unwrap_or_else(|| default_segments(rx.max(ry)));
+                let (start, end, whole) = match angles {
+                    Some((start, end)) => (start, end, false),
+                    None => (0., 2. * PI, true)
+                };
+                let segments = if whole {
+                    segments
+                } else {
+                    // Fewer segments for a smaller part of the circle
+                    ((segments as f32 * (end - start).abs() / (2. * PI)).ceil() as u32).max(1)
+                };
+                // Arcs need both ends
+                let count = if whole {segments} else {segments + 1};
+                let points: Vec<_> = (0..count)
+                    .map(|i| start + (end - start) * i as f32 / segments as f32)
+                    .map(|a| [rx * a.cos(), ry * a.sin()])
+                    .collect();
+
+                match self.outline {
+                    Some(thickness) => stroke(&points, whole, thickness),
+                    None => fan([0., 0.], &points, whole)
+                }
+            }
+            Kind::Path{ref points, closed} => stroke(points, closed, self.outline.unwrap_or(1.)),
+            Kind::Polygon(ref points) => match self.outline {
+                Some(thickness) => stroke(points, true, thickness),
+                None => triangulate(points).into_iter()
+                    .flat_map(|[a, b, c]| [points[a], points[b], points[c]])
+                    .collect()
+            },
+            Kind::RoundedRect{h_size: (w, h), radius} => {
+                let segments = self.segments.unwrap_or_else(|| default_segments(radius) / 4).max(1);
+                let corners = [(w - radius, h - radius), (radius - w, h - radius), (radius - w, radius - h), (w - radius, radius - h)];
+
+                let mut points = Vec::with_capacity(4 * (segments as usize + 1));
+                for (i, &(cx, cy)) in corners.iter().enumerate() {
+                    let start = i as f32 * PI / 2.;
+                    points.extend((0..=segments).map(|j| {
+                        let a = start + PI / 2. * j as f32 / segments as f32;
+                        [cx + radius * a.cos(), cy + radius * a.sin()]
+                    }));
+                }
+
+                match self.outline {
+                    Some(thickness) => stroke(&points, true, thickness),
+                    None => fan([0., 0.], &points, true)
+                }
+            }
+        }
+    }
+}
+
+// Enough segments for curves to look round
+fn default_segments(radius: f32) -> u32{
+    ((radius.sqrt() * 6.) as u32).clamp(12, 128)
+}
+
+// Triangles from the centre to each pair of neighbouring points
+fn fan(centre: [f32; 2], points: &[[f32; 2]], closed: bool) -> Vec<[f32; 2]>{
+    let mut triangles = Vec::with_capacity(points.len() * 3);
+    for pair in points.windows(2) {
+        triangles.extend_from_slice(&[centre, pair[0], pair[1]]);
+    }
+    if let (true, Some(&first), Some(&last)) = (closed, points.first(), points.last()) {
+        triangles.extend_from_slice(&[centre, last, first]);
+    }
+    triangles
+}
+
+fn normal(a: [f32; 2], b: [f32; 2]) -> [f32; 2]{
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len == 0. {
+        [0., 0.]
+    } else {
+        [-dy / len, dx / len]
+    }
+}
+
+// Triangles making up a line of the thickness through the points, with mitered corners
+fn stroke(points: &[[f32; 2]], closed: bool, thickness: f32) -> Vec<[f32; 2]>{
+    let n = points.len();
+    if n < 2 {
+        return Vec::new()
+    }
+    let half = thickness / 2.;
+
+    // How far the edges of the line are from each point
+    let offsets: Vec<_> = (0..n).map(|i| {
+        let before = if i > 0 {
+            Some(normal(points[i - 1], points[i]))
+        } else if closed {
+            Some(normal(points[n - 1], points[0]))
+        } else {
+            None
+        };
+        let after = if i + 1 < n {
+            Some(normal(points[i], points[i + 1]))
+        } else if closed {
+            Some(normal(points[n - 1], points[0]))
+        } else {
+            None
+        };
+
+        let (a, b) = match (before, after) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(a), None) | (None, Some(a)) => (a, a),
+            (None, None) => unreachable!()
+        };
+        let miter = [a[0] + b[0], a[1] + b[1]];
+        let len = (miter[0] * miter[0] + miter[1] * miter[1]).sqrt();
+        if len < 1e-6 {
+            return [a[0] * half, a[1] * half]
+        }
+        let miter = [miter[0] / len, miter[1] / len];
+        // Keep sharp corners from making very long spikes
+        let scale = half / (miter[0] * a[0] + miter[1] * a[1]).max(0.25);
+        [miter[0] * scale, miter[1] * scale]
+    }).collect();
+
+    let segments = if closed {n} else {n - 1};
+    let mut triangles = Vec::with_capacity(segments * 6);
+    for i in 0..segments {
+        let j = (i + 1) % n;
+        let (p, q, o, r) = (points[i], points[j], offsets[i], offsets[j]);
+        let (p_out, p_in) = ([p[0] + o[0], p[1] + o[1]], [p[0] - o[0], p[1] - o[1]]);
+        let (q_out, q_in) = ([q[0] + r[0], q[1] + r[1]], [q[0] - r[0], q[1] - r[1]]);
+        triangles.extend_from_slice(&[p_out, p_in, q_out, q_out, p_in, q_in]);
+    }
+    triangles
+}
+
+fn cross(o: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32{
+    (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+}
+
+/// Splits a polygon into triangles by ear clipping, returning the indices of the corners of each triangle
+///
+/// The polygon can be convex or concave and go either way round, but mustn't cross itself.
+/// The triangles all go counter-clockwise.
+pub fn triangulate(polygon: &[[f32; 2]]) -> Vec<[usize; 3]>{
+    let n = polygon.len();
+    if n < 3 {
+        return Vec::new()
+    }
+
+    let area: f32 = (0..n).map(|i| cross([0., 0.], polygon[i], polygon[(i + 1) % n])).sum();
+    // Go through the corners counter-clockwise
+    let mut corners: Vec<usize> = if area >= 0. {
+        (0..n).collect()
+    } else {
+        (0..n).rev().collect()
+    };
+
+    let mut triangles = Vec::with_capacity(n - 2);
+    while corners.len() > 3 {
+        let m = corners.len();
+        let neighbours = |i: usize| (corners[(i + m - 1) % m], corners[i], corners[(i + 1) % m]);
+
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = neighbours(i);
+            let (pa, pb, pc) = (polygon[a], polygon[b], polygon[c]);
+
+            // An ear is a convex corner with no other corner inside its triangle
+            cross(pa, pb, pc) > 0. && !corners.iter().any(|&j| {
+                j != a && j != b && j != c &&
+                cross(pa, pb, polygon[j]) >= 0. && cross(pb, pc, polygon[j]) >= 0. && cross(pc, pa, polygon[j]) >= 0.
+            })
+        });
+        // Only happens if the polygon crosses itself or has corners on top of each other,
+        // so just clip anything to finish
+        let i = ear.unwrap_or(0);
+
+        let (a, b, c) = neighbours(i);
+        triangles.push([a, b, c]);
+        corners.remove(i);
+    }
+    triangles.push([corners[0], corners[1], corners[2]]);
+
+    triangles
+}
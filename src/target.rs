@@ -0,0 +1,48 @@
+use glium::{Display, Surface};
+use glium::texture::Texture2d;
+use glium::framebuffer::SimpleFrameBuffer;
+
+use std::rc::Rc;
+
+use super::{Graphics, Texture, Drawer, TextureError};
+
+/// An off-screen texture that can be drawn to like the screen
+///
+/// Useful for minimaps, caching static layers and post-processing.
+#[derive(Debug)]
+pub struct RenderTarget{
+    texture: Texture
+}
+
+impl RenderTarget{
+    /// Creates a transparent render target with the given size in pixels
+    pub fn new(display: &Display, width: u32, height: u32) -> Result<Self, TextureError>{
+        let tex = Texture2d::empty(display, width, height)?;
+        tex.as_surface().clear_color(0., 0., 0., 0.);
+
+        Ok(RenderTarget{
+            texture: Texture::from_texture2d(Rc::new(tex))
+        })
+    }
+
+    /// Returns a `Drawer` that draws to this render target
+    ///
+    /// It works like drawing to the screen, with the origin in the centre of the target.
+    /// Everything is drawn once the `Drawer` is dropped.
+    pub fn drawer<'a>(&'a self, graphics: &'a Graphics) -> Drawer<'a, SimpleFrameBuffer<'a>>{
+        let (w, h) = self.texture.get_size();
+        // Can only fail for textures of formats that can't be rendered to
+        let target = SimpleFrameBuffer::new(&**graphics, self.texture.texture2d())
+            .expect("render target texture can't be rendered to");
+
+        Drawer::with_target(graphics, target, (w / 2., h / 2.))
+    }
+
+    #[inline]
+    /// Returns the texture that has been drawn to, which can be drawn like any other
+    ///
+    /// The texture keeps showing what is drawn to the render target later on.
+    pub fn texture(&self) -> &Texture{
+        &self.texture
+    }
+}
@@ -0,0 +1,566 @@
+use rusttype;
+use rusttype::{Scale, point};
+
+use image::{RgbaImage, Rgba};
+
+use glium::{Display, Rect};
+use glium::texture::{Texture2d, RawImage2d};
+
+use std::io;
+use std::io::Read;
+use std::fs::File;
+use std::path::Path;
+use std::mem;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{Texture, TextureError, Drawer, DrawTarget};
+use atlas::ShelfPacker;
+
+quick_error! {
+    /// Wraps together errors that can occur loading a `Font`
+    #[derive(Debug)]
+    pub enum FontError{
+        /// The data isn't a TrueType or OpenType font
+        InvalidFont{
+            description("invalid font data")
+        }
+        /// A line in a BMFont description couldn't be understood
+        BitmapFontError(line: usize, reason: &'static str){
+            description(reason)
+            display("line {} of BMFont description: {}", line, reason)
+        }
+        /// A `korome::TextureError` from loading a page of a bitmap font
+        TextureError(err: TextureError){
+            from()
+            cause(err)
+            description(err.description())
+        }
+        /// An `std::io::Error`
+        IoError(err: io::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+    }
+}
+
+// Width and height of the texture TrueType glyphs are rasterised into
+const GLYPH_CACHE_SIZE: u32 = 1024;
+
+/// How the lines of a text are aligned to its position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Align{
+    /// The position is the left end of the lines
+    Left,
+    /// The position is the middle of the lines
+    Centre,
+    /// The position is the right end of the lines
+    Right
+}
+
+/// A font for drawing text with, either TrueType/OpenType or a BMFont bitmap font
+#[derive(Debug)]
+pub struct Font{
+    kind: FontKind
+}
+
+#[derive(Debug)]
+enum FontKind{
+    TrueType{
+        font: rusttype::Font<'static>,
+        cache: RefCell<GlyphCache>
+    },
+    Bitmap(BitmapFont)
+}
+
+// A glyph ready to be drawn
+#[derive(Debug, Clone)]
+struct Glyph{
+    texture: Texture,
+    // Offset of the top left corner from the pen position on the baseline, y going up
+    left: f32,
+    top: f32,
+    // How much bigger it's drawn than its texture, for glyphs too big for the cache
+    scale: f32
+}
+
+#[derive(Debug)]
+struct GlyphCache{
+    // Made when the first glyph is drawn, so fonts can be loaded and measured without a window
+    texture: Option<Texture>,
+    packer: ShelfPacker,
+    // Keyed by the character and its size in pixels
+    glyphs: HashMap<(char, u32), Option<Glyph>>
+}
+
+impl GlyphCache{
+    fn new() -> Self{
+        GlyphCache{
+            texture: None,
+            packer: ShelfPacker::new(GLYPH_CACHE_SIZE, GLYPH_CACHE_SIZE),
+            glyphs: HashMap::new()
+        }
+    }
+    // Returns `Err(())` if the cache is full
+    fn glyph(&mut self, display: &Display, font: &rusttype::Font, c: char, size: u32) -> Result<Option<Glyph>, ()>{
+        if let Some(glyph) = self.glyphs.get(&(c, size)) {
+            return Ok(glyph.clone())
+        }
+
+        let (glyph, bb, raster) = match rasterise(font, c, size) {
+            Some(rasterised) => rasterised,
+            None => {
+                self.glyphs.insert((c, size), None);
+                return Ok(None)
+            }
+        };
+        let texture = match self.texture {
+            Some(ref texture) => texture.clone(),
+            ref mut texture => {
+                let blank = vec![0u8; (GLYPH_CACHE_SIZE * GLYPH_CACHE_SIZE * 4) as usize];
+                // Without a texture nothing can be drawn, but it's tried again for the next glyph
+                match Texture2d::new(display, RawImage2d::from_raw_rgba(blank, (GLYPH_CACHE_SIZE, GLYPH_CACHE_SIZE))) {
+                    Ok(tex) => texture.insert(Texture::from_texture2d(Rc::new(tex))).clone(),
+                    Err(_) => return Ok(None)
+                }
+            }
+        };
+
+        let (width, height) = (bb.width() as u32, bb.height() as u32);
+        // One pixel of padding so glyphs don't bleed into each other
+        let (x, y) = self.packer.pack(width + 1, height + 1).ok_or(())?;
+
+        let mut image = RgbaImage::from_pixel(width, height, Rgba{data: [255, 255, 255, 0]});
+        glyph.draw(|gx, gy, coverage| image.put_pixel(gx, gy, Rgba{data: [255, 255, 255, (coverage * 255.) as u8]}));
+
+        texture.texture2d().write(Rect{
+            left: x,
+            bottom: GLYPH_CACHE_SIZE - y - height,
+            width,
+            height
+        }, RawImage2d::from_raw_rgba_reversed(image.into_raw(), (width, height)));
+
+        let cached = Glyph{
+            texture: texture.sub_texture(x, y, width, height),
+            left: bb.min.x as f32,
+            top: -bb.min.y as f32,
+            scale: size as f32 / raster as f32
+        };
+        self.glyphs.insert((c, size), Some(cached.clone()));
+        Ok(Some(cached))
+    }
+    fn clear(&mut self){
+        self.packer.clear();
+        self.glyphs.clear();
+    }
+}
+
+// Positions a glyph to be rasterised at `size` pixels, returning its pixel bounds and the size it's rasterised at.
+// Glyphs that wouldn't fit in the cache even when it's empty are rasterised at half the size until they do, to be scaled up.
+// Returns `None` for characters without a shape.
+fn rasterise<'a>(font: &rusttype::Font<'a>, c: char, size: u32) -> Option<(rusttype::PositionedGlyph<'a>, rusttype::Rect<i32>, u32)>{
+    let mut raster = size;
+    loop {
+        let glyph = font.glyph(c).scaled(Scale::uniform(raster as f32)).positioned(point(0., 0.));
+        match glyph.pixel_bounding_box() {
+            Some(bb) if bb.width() as u32 >= GLYPH_CACHE_SIZE || bb.height() as u32 >= GLYPH_CACHE_SIZE => raster /= 2,
+            Some(bb) => return Some((glyph, bb, raster)),
+            None => return None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BitmapFont{
+    size: f32,
+    line_height: f32,
+    base: f32,
+    glyphs: HashMap<char, BitmapGlyph>,
+    kernings: HashMap<(char, char), f32>
+}
+
+#[derive(Debug)]
+struct BitmapGlyph{
+    glyph: Option<Glyph>,
+    advance: f32
+}
+
+impl Font{
+    /// Creates a `Font` from the bytes of a TrueType or OpenType font
+    ///
+    /// Glyphs are rasterised into a texture as they're first drawn.
+    pub fn from_ttf_bytes(bytes: Vec<u8>) -> Result<Self, FontError>{
+        let font = rusttype::Font::try_from_vec(bytes).ok_or(FontError::InvalidFont)?;
+
+        Ok(Font{
+            kind: FontKind::TrueType{
+                font,
+                cache: RefCell::new(GlyphCache::new())
+            }
+        })
+    }
+    /// Creates a `Font` from a TrueType or OpenType font file
+    pub fn from_ttf_file<P: AsRef<Path>>(path: P) -> Result<Self, FontError>{
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Font::from_ttf_bytes(bytes)
+    }
+    /// Creates a `Font` from a BMFont description in the text format and the textures of its pages
+    ///
+    /// The pages have to be in the same order as their ids.
+    pub fn from_bmfont(description: &str, pages: &[Texture]) -> Result<Self, FontError>{
+        let mut font = BitmapFont{
+            size: 0.,
+            line_height: 0.,
+            base: 0.,
+            glyphs: HashMap::new(),
+            kernings: HashMap::new()
+        };
+
+        for (i, line) in description.lines().enumerate() {
+            let (tag, attributes) = parse_bmfont_line(line);
+            let line = i + 1;
+            let attr = |name| attributes.iter()
+                .find(|&&(key, _)| key == name)
+                .and_then(|(_, value)| value.parse::<i32>().ok())
+                .ok_or(FontError::BitmapFontError(line, "missing or invalid attribute"));
+            let character = |name| attr(name).and_then(|id|
+                ::std::char::from_u32(id as u32).ok_or(FontError::BitmapFontError(line, "invalid character id")));
+
+            match tag {
+                "info" => font.size = (attr("size")? as f32).abs(),
+                "common" => {
+                    font.line_height = attr("lineHeight")? as f32;
+                    font.base = attr("base")? as f32;
+                }
+                "char" => {
+                    let (width, height) = (attr("width")?, attr("height")?);
+
+                    // Empty glyphs like spaces are only an advance, so their page isn't needed
+                    let glyph = if width > 0 && height > 0 {
+                        let page = pages.get(attr("page")? as usize)
+                            .ok_or(FontError::BitmapFontError(line, "page doesn't exist"))?;
+                        Some(Glyph{
+                            texture: page.sub_texture(attr("x")? as u32, attr("y")? as u32, width as u32, height as u32),
+                            left: attr("xoffset")? as f32,
+                            top: font.base - attr("yoffset")? as f32,
+                            scale: 1.
+                        })
+                    } else {
+                        None
+                    };
+
+                    font.glyphs.insert(character("id")?, BitmapGlyph{
+                        glyph,
+                        advance: attr("xadvance")? as f32
+                    });
+                }
+                "kerning" => {
+                    font.kernings.insert((character("first")?, character("second")?), attr("amount")? as f32);
+                }
+                _ => ()
+            }
+        }
+        if font.size == 0. {
+            font.size = font.line_height;
+        }
+
+        Ok(Font{
+            kind: FontKind::Bitmap(font)
+        })
+    }
+    /// Creates a `Font` from a BMFont description file in the text format.
+    ///
+    /// The pages are loaded from the files it names, relative to the description file.
+    pub fn from_bmfont_file<P: AsRef<Path>>(display: &Display, path: P) -> Result<Self, FontError>{
+        let path = path.as_ref();
+        let mut description = String::new();
+        File::open(path)?.read_to_string(&mut description)?;
+
+        let mut pages = Vec::new();
+        for line in description.lines() {
+            if let ("page", attributes) = parse_bmfont_line(line) {
+                if let Some((_, file)) = attributes.iter().find(|&&(key, _)| key == "file") {
+                    let file = path.parent().unwrap_or_else(|| Path::new("")).join(file);
+                    pages.push(Texture::from_file(display, file)?);
+                }
+            }
+        }
+
+        Font::from_bmfont(&description, &pages)
+    }
+
+    #[inline]
+    /// Returns an object used for drawing the text onto the screen with a `Drawer`
+    pub fn text<'a>(&'a self, text: &'a str) -> TextDrawer<'a>{
+        TextDrawer{
+            font: self,
+            text,
+            pos: (0., 0.),
+            sin_cos: (0., 1.),
+            colour: [1., 1., 1., 1.],
+            size: match self.kind {
+                FontKind::TrueType{..} => 16.,
+                FontKind::Bitmap(ref font) => font.size
+            },
+            align: Align::Left,
+            wrap_width: None
+        }
+    }
+
+    // Returns the size to rasterise at and how much to scale bitmap glyphs
+    fn pixel_size(&self, size: f32) -> (u32, f32){
+        match self.kind {
+            FontKind::TrueType{..} => (size.round().max(1.) as u32, 1.),
+            FontKind::Bitmap(ref font) => (0, size / font.size)
+        }
+    }
+    // Returns the distance from the top of a line to the baseline and the line height
+    fn v_metrics(&self, size: f32) -> (f32, f32){
+        match self.kind {
+            FontKind::TrueType{ref font, ..} => {
+                let v = font.v_metrics(Scale::uniform(self.pixel_size(size).0 as f32));
+                (v.ascent, v.ascent - v.descent + v.line_gap)
+            }
+            FontKind::Bitmap(ref font) => {
+                let scale = size / font.size;
+                (font.base * scale, font.line_height * scale)
+            }
+        }
+    }
+    fn advance(&self, c: char, size: f32) -> f32{
+        match self.kind {
+            FontKind::TrueType{ref font, ..} => {
+                let scale = Scale::uniform(self.pixel_size(size).0 as f32);
+                font.glyph(c).scaled(scale).h_metrics().advance_width
+            }
+            FontKind::Bitmap(ref font) => font.glyphs.get(&c).map(|g| g.advance * size / font.size).unwrap_or(0.)
+        }
+    }
+    fn kerning(&self, first: char, second: char, size: f32) -> f32{
+        match self.kind {
+            FontKind::TrueType{ref font, ..} => {
+                font.pair_kerning(Scale::uniform(self.pixel_size(size).0 as f32), first, second)
+            }
+            FontKind::Bitmap(ref font) => font.kernings.get(&(first, second)).map(|k| k * size / font.size).unwrap_or(0.)
+        }
+    }
+    // Flushes the drawer before glyphs can get overwritten in the cache.
+    // Every glyph fits in the emptied cache, so the second try only fails for characters without a shape.
+    fn glyph<S: DrawTarget>(&self, c: char, size: f32, drawer: &mut Drawer<S>) -> Option<Glyph>{
+        match self.kind {
+            FontKind::TrueType{ref font, ref cache} => {
+                let mut cache = cache.borrow_mut();
+                let size = self.pixel_size(size).0;
+
+                let graphics = drawer.graphics;
+                let display: &Display = graphics;
+                cache.glyph(display, font, c, size).or_else(|()| {
+                    drawer.flush();
+                    cache.clear();
+                    cache.glyph(display, font, c, size)
+                }).unwrap_or(None)
+            }
+            FontKind::Bitmap(ref font) => font.glyphs.get(&c).and_then(|g| g.glyph.clone())
+        }
+    }
+
+    // Splits the text into lines, wrapping them between words if they're too wide
+    fn layout(&self, text: &str, size: f32, wrap_width: Option<f32>) -> Vec<Line>{
+        let mut lines = Vec::new();
+
+        for paragraph in text.lines() {
+            let mut line = Line::default();
+            let mut x = 0.;
+
+            for (i, word) in paragraph.split(' ').enumerate() {
+                if i > 0 {
+                    let space = self.advance(' ', size);
+                    let word_width = self.word_width(word, size);
+
+                    if wrap_width.is_some_and(|w| !line.chars.is_empty() && x + space + word_width > w) {
+                        lines.push(mem::take(&mut line));
+                        x = 0.;
+                    } else {
+                        x += space;
+                    }
+                }
+
+                let mut last = None;
+                for c in word.chars() {
+                    if let Some(last) = last {
+                        x += self.kerning(last, c, size);
+                    }
+                    line.chars.push((c, x));
+                    x += self.advance(c, size);
+                    last = Some(c);
+                }
+                line.width = x;
+            }
+            lines.push(line);
+        }
+
+        lines
+    }
+    fn word_width(&self, word: &str, size: f32) -> f32{
+        let mut last = None;
+        word.chars().map(|c| {
+            let kerning = last.map(|last| self.kerning(last, c, size)).unwrap_or(0.);
+            last = Some(c);
+            kerning + self.advance(c, size)
+        }).sum()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Line{
+    // Characters and their pen positions from the start of the line
+    chars: Vec<(char, f32)>,
+    width: f32
+}
+
+// Splits a line like `char id=65 x="1"` into its tag and attributes
+fn parse_bmfont_line(line: &str) -> (&str, Vec<(&str, String)>){
+    let line = line.trim();
+    let (tag, rest) = line.split_at(line.find(' ').unwrap_or(line.len()));
+
+    let mut attributes = Vec::new();
+    let mut rest = rest.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let value_start = &rest[eq+1..];
+
+        let (value, next) = if let Some(quoted) = value_start.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (quoted[..end].to_owned(), quoted.get(end+1..).unwrap_or(""))
+        } else {
+            let end = value_start.find(' ').unwrap_or(value_start.len());
+            (value_start[..end].to_owned(), &value_start[end..])
+        };
+
+        attributes.push((key, value));
+        rest = next.trim_start();
+    }
+
+    (tag, attributes)
+}
+
+/// Object for drawing text to the screen using the builder pattern
+#[must_use = "drawers are lazy and do nothing until consumed"]
+#[derive(Debug, Clone)]
+pub struct TextDrawer<'a>{
+    font: &'a Font,
+    text: &'a str,
+    /// The position on the screen of the top of the text, see `Align`
+    pub pos: (f32, f32),
+    sin_cos: (f32, f32),
+    /// The colour the text will be drawn with
+    pub colour: [f32; 4],
+    /// The height of the text in pixels
+    pub size: f32,
+    /// How the lines are aligned to the position
+    pub align: Align,
+    /// The width at which lines are wrapped, if any
+    pub wrap_width: Option<f32>
+}
+
+impl<'a> TextDrawer<'a>{
+    set!{/// Sets the position the text will be drawn at
+        fn TextDrawer; pos: (f32, f32)}
+    set!{/// Sets the colour the text will be drawn with
+        fn TextDrawer; colour: [f32; 4]}
+    set!{/// Sets the rotation of the text around its position
+        fn TextDrawer; rotation: f32 => sin_cos: rotation.sin_cos()}
+    set!{/// Sets the height of the text in pixels
+        fn TextDrawer; size: f32}
+    set!{/// Sets how the lines are aligned to the position
+        fn TextDrawer; align: Align}
+    set!{/// Makes lines wrap between words so they're not wider than `width`
+        fn TextDrawer; wrap: f32 => wrap_width: Some(wrap)}
+
+    /// Returns the width and height the text will take up on the screen
+    pub fn measure(&self) -> (f32, f32){
+        let lines = self.font.layout(self.text, self.size, self.wrap_width);
+        let (_, line_height) = self.font.v_metrics(self.size);
+
+        let width = lines.iter().map(|line| line.width).fold(0., f32::max);
+        (width, lines.len() as f32 * line_height)
+    }
+
+    /// Consumes self and draws the text to the screen with the given options
+    pub fn draw<S: DrawTarget>(self, drawer: &mut Drawer<S>){
+        let TextDrawer{font, text, pos: (x, y), sin_cos: (sin, cos), colour, size, align, wrap_width} = self;
+        let (ascent, line_height) = font.v_metrics(size);
+        let (_, scale) = font.pixel_size(size);
+        let rotation = sin.atan2(cos);
+
+        for (i, line) in font.layout(text, size, wrap_width).into_iter().enumerate() {
+            let baseline = -ascent - i as f32 * line_height;
+            let start = match align {
+                Align::Left => 0.,
+                Align::Centre => -line.width / 2.,
+                Align::Right => -line.width
+            };
+
+            for (c, pen) in line.chars {
+                let glyph = match font.glyph(c, size, drawer){
+                    Some(glyph) => glyph,
+                    None => continue
+                };
+                let scale = scale * glyph.scale;
+                let (w, h) = glyph.texture.get_size();
+                let cx = start + pen + (glyph.left + w / 2.) * scale;
+                let cy = baseline + (glyph.top - h / 2.) * scale;
+
+                glyph.texture.drawer()
+                    .pos((x + cos * cx - sin * cy, y + sin * cx + cos * cy))
+                    .rotation(rotation)
+                    .scale(scale)
+                    .colour(colour)
+                    .draw(drawer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn boxes() -> rusttype::Font<'static>{
+        rusttype::Font::try_from_bytes(include_bytes!("../tests/assets/boxes.ttf")).unwrap()
+    }
+
+    #[test]
+    fn glyphs_too_big_for_the_cache_are_rasterised_smaller(){
+        let font = boxes();
+        // 'B' is a bar as tall as the size
+        let (_, bb, raster) = rasterise(&font, 'B', 1000).unwrap();
+        assert_eq!((bb.height(), raster), (1000, 1000));
+        let (_, bb, raster) = rasterise(&font, 'B', 4000).unwrap();
+        assert_eq!((bb.height(), raster), (1000, 1000));
+        // Just too big, so it's halved even though it's then rounded out to 513 pixels
+        let (_, bb, raster) = rasterise(&font, 'B', 1024).unwrap();
+        assert_eq!((bb.height(), raster), (513, 512));
+
+        assert!(rasterise(&font, ' ', 1000).is_none());
+    }
+
+    #[test]
+    fn the_cache_fits_glyphs_until_cleared(){
+        let font = boxes();
+        let mut cache = GlyphCache::new();
+        // 'A' at 1000 pixels is a 500 pixel square, so four fit next to and over each other
+        let (_, bb, _) = rasterise(&font, 'A', 1000).unwrap();
+        let (width, height) = (bb.width() as u32 + 1, bb.height() as u32 + 1);
+        for _ in 0..4 {
+            assert!(cache.packer.pack(width, height).is_some());
+        }
+        assert!(cache.packer.pack(width, height).is_none());
+
+        cache.clear();
+        assert!(cache.packer.pack(width, height).is_some());
+    }
+}
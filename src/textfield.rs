@@ -0,0 +1,280 @@
+use super::{FrameInfo, VirtualKeyCode};
+
+// The most times `KeyRepeat` repeats a key in one update
+const MAX_REPEATS: usize = 4;
+
+/// Turns holding a key down into repeated presses, like when typing
+///
+/// The first repeat comes after `delay` seconds and the rest every `interval` seconds.
+/// Only the last key pressed repeats, as is usual for keyboards.
+#[derive(Debug, Clone)]
+pub struct KeyRepeat{
+    delay: f32,
+    interval: f32,
+    held: Option<(VirtualKeyCode, f32)>
+}
+
+impl Default for KeyRepeat{
+    /// Repeats after half a second, 30 times a second
+    fn default() -> Self{
+        KeyRepeat::new(0.5, 1. / 30.)
+    }
+}
+
+impl KeyRepeat{
+    #[inline]
+    /// Creates a key repeat with the given delay before the first repeat and interval between repeats in seconds
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval isn't above zero.
+    pub fn new(delay: f32, interval: f32) -> Self{
+        assert!(interval > 0., "key repeat interval must be above zero, not {}", interval);
+        KeyRepeat{
+            delay,
+            interval,
+            held: None
+        }
+    }
+    #[inline]
+    /// Returns the keys that were pressed or repeated this frame in order
+    pub fn update(&mut self, info: &FrameInfo) -> Vec<VirtualKeyCode>{
+        self.update_with_events(info.get_key_events(), info.delta)
+    }
+    /// Returns the keys that were pressed or repeated from the key events and the time passed
+    ///
+    /// A key repeats at most 4 times in one update, so a long frame doesn't type a burst.
+    pub fn update_with_events(&mut self, key_events: &[(bool, VirtualKeyCode)], delta: f32) -> Vec<VirtualKeyCode>{
+        let mut keys = Vec::new();
+
+        if let Some((key, ref mut timer)) = self.held {
+            *timer -= delta;
+            while *timer <= 0. {
+                if keys.len() == MAX_REPEATS {
+                    // Drop the time the rest would have taken
+                    *timer = self.interval;
+                    break
+                }
+                keys.push(key);
+                *timer += self.interval;
+            }
+        }
+
+        for &(pressed, key) in key_events {
+            match self.held {
+                // Ignore the presses some systems send for their own key repeat
+                Some((held, _)) if pressed && held == key => (),
+                _ if pressed => {
+                    keys.push(key);
+                    self.held = Some((key, self.delay));
+                }
+                Some((held, _)) if held == key => self.held = None,
+                _ => ()
+            }
+        }
+
+        keys
+    }
+}
+
+/// Where `TextField` copies to and pastes from
+pub trait Clipboard{
+    /// Returns the text on the clipboard, if there is any
+    fn get(&mut self) -> Option<String>;
+    /// Puts the text on the clipboard
+    fn set(&mut self, text: &str);
+}
+
+/// A clipboard that only exists within the game
+#[derive(Debug, Clone, Default)]
+pub struct LocalClipboard(pub Option<String>);
+
+impl Clipboard for LocalClipboard{
+    fn get(&mut self) -> Option<String>{
+        self.0.clone()
+    }
+    fn set(&mut self, text: &str){
+        self.0 = Some(text.to_owned());
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::system::SystemClipboard;
+
+#[cfg(target_os = "linux")]
+mod system{
+    use super::Clipboard;
+
+    use std::process::{Command, Stdio};
+    use std::io::Write;
+
+    /// The clipboard of the system on Linux, accessed through `wl-copy`/`wl-paste` or `xclip`
+    ///
+    /// If neither are installed the clipboard acts as empty and copying does nothing.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct SystemClipboard;
+
+    // The programs tried in order for pasting and copying
+    const PASTE_COMMANDS: &[&[&str]] = &[&["wl-paste", "--no-newline"], &["xclip", "-o", "-selection", "clipboard"]];
+    const COPY_COMMANDS: &[&[&str]] = &[&["wl-copy"], &["xclip", "-i", "-selection", "clipboard"]];
+
+    impl Clipboard for SystemClipboard{
+        fn get(&mut self) -> Option<String>{
+            PASTE_COMMANDS.iter()
+                .filter_map(|cmd| Command::new(cmd[0]).args(&cmd[1..]).stderr(Stdio::null()).output().ok())
+                .find(|output| output.status.success())
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+        }
+        fn set(&mut self, text: &str){
+            for cmd in COPY_COMMANDS {
+                let child = Command::new(cmd[0]).args(&cmd[1..])
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn();
+
+                if let Ok(mut child) = child {
+                    let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+                    if child.wait().is_ok_and(|status| status.success()) && written {
+                        return
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An editable line of text for name entry, chat boxes and the like
+///
+/// It only handles the editing, drawing it is up to the game, e.g. with `Font::text`.
+/// The part before the cursor is `&field.text()[..field.cursor()]`, which can be measured to place the cursor.
+///
+/// Typed characters are inserted at the cursor, Backspace and Delete remove characters,
+/// Left, Right, Home and End move the cursor, Ctrl+V pastes and Ctrl+C copies all the text.
+#[derive(Debug, Clone, Default)]
+pub struct TextField{
+    text: String,
+    cursor: usize,
+    max_chars: Option<usize>,
+    repeat: KeyRepeat
+}
+
+impl TextField{
+    #[inline]
+    /// Creates an empty text field
+    pub fn new() -> Self{
+        TextField::default()
+    }
+    /// Creates a text field with some text and the cursor at the end
+    pub fn with_text<S: Into<String>>(text: S) -> Self{
+        let mut field = TextField::new();
+        field.set_text(text);
+        field
+    }
+    set!{/// Sets the most characters the text can have
+        fn TextField; max_chars: usize => max_chars: Some(max_chars)}
+    set!{/// Sets how held keys like Backspace repeat
+        fn TextField; repeat: KeyRepeat}
+
+    #[inline]
+    /// Returns the text
+    pub fn text(&self) -> &str{
+        &self.text
+    }
+    #[inline]
+    /// Returns the position of the cursor as a byte index into the text
+    pub fn cursor(&self) -> usize{
+        self.cursor
+    }
+    /// Replaces the text and moves the cursor to the end
+    pub fn set_text<S: Into<String>>(&mut self, text: S){
+        self.text.clear();
+        self.cursor = 0;
+        self.insert_str(&text.into());
+    }
+    /// Removes the text and returns it, e.g. when a chat message is sent
+    pub fn take(&mut self) -> String{
+        self.cursor = 0;
+        ::std::mem::take(&mut self.text)
+    }
+
+    /// Inserts text at the cursor, leaving out control characters and anything beyond the character limit
+    pub fn insert_str(&mut self, text: &str){
+        let mut room = self.max_chars.map_or(usize::MAX, |max| max.saturating_sub(self.text.chars().count()));
+
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if room == 0 {
+                break
+            }
+            room -= 1;
+            self.text.insert(self.cursor, c);
+            self.cursor += c.len_utf8();
+        }
+    }
+    /// Removes the character before the cursor
+    pub fn backspace(&mut self){
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+    /// Removes the character after the cursor
+    pub fn delete(&mut self){
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+    /// Moves the cursor one character to the left
+    pub fn move_left(&mut self){
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+    /// Moves the cursor one character to the right
+    pub fn move_right(&mut self){
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+    #[inline]
+    /// Moves the cursor to the start
+    pub fn home(&mut self){
+        self.cursor = 0;
+    }
+    #[inline]
+    /// Moves the cursor to the end
+    pub fn end(&mut self){
+        self.cursor = self.text.len();
+    }
+
+    /// Edits the text by what was typed this frame.
+    ///
+    /// Returns `true` if Return or Enter was pressed, e.g. to submit the text.
+    pub fn update(&mut self, info: &FrameInfo, clipboard: &mut dyn Clipboard) -> bool{
+        let ctrl = info.is_down(&VirtualKeyCode::LControl) || info.is_down(&VirtualKeyCode::RControl);
+        let mut submitted = false;
+
+        for key in self.repeat.update(info) {
+            match key {
+                VirtualKeyCode::Back => self.backspace(),
+                VirtualKeyCode::Delete => self.delete(),
+                VirtualKeyCode::Left => self.move_left(),
+                VirtualKeyCode::Right => self.move_right(),
+                VirtualKeyCode::Home => self.home(),
+                VirtualKeyCode::End => self.end(),
+                VirtualKeyCode::V if ctrl => if let Some(text) = clipboard.get() {
+                    self.insert_str(&text);
+                },
+                VirtualKeyCode::C if ctrl => clipboard.set(&self.text),
+                VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => submitted = true,
+                _ => ()
+            }
+        }
+        // Characters typed while holding control are shortcuts, not text
+        if !ctrl {
+            self.insert_str(info.get_text_input());
+        }
+
+        submitted
+    }
+}
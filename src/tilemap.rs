@@ -0,0 +1,1210 @@
+use xml::reader::{EventReader, XmlEvent};
+use serde_json::{self, Value};
+use inflate::InflateStream;
+
+use glium::Display;
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, io, mem};
+
+use super::{Texture, TextureError, Camera, Drawer, DrawTarget};
+
+quick_error! {
+    /// Wraps together errors that can occur loading a `TileMap`
+    #[derive(Debug)]
+    pub enum TileMapError{
+        /// The map is well-formed but isn't a map korome understands
+        InvalidMap(reason: &'static str){
+            description(reason)
+            display("invalid map: {}", reason)
+        }
+        /// An `xml::reader::Error` from a .tmx or .tsx file
+        XmlError(err: ::xml::reader::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+        /// A `serde_json::Error` from a .json file
+        JsonError(err: serde_json::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+        /// A `TextureError` from loading a tileset's image
+        TextureError(err: TextureError){
+            from()
+            cause(err)
+            description(err.description())
+        }
+        /// An `std::io::Error`
+        IoError(err: io::Error){
+            from()
+            cause(err)
+            description(err.description())
+        }
+    }
+}
+
+type Result<T> = ::std::result::Result<T, TileMapError>;
+
+#[inline]
+fn invalid<T>(reason: &'static str) -> Result<T>{
+    Err(TileMapError::InvalidMap(reason))
+}
+
+// The flags Tiled stores in the highest bits of a global tile id
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+// Only used by hexagonal maps, but masked out all the same
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+
+/// A tile placed on a tile layer or as a tile object
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Tile{
+    /// The global id of the tile, telling which tileset it is from and which tile in it
+    pub gid: u32,
+    /// Whether the tile is mirrored left to right
+    pub flipped_horizontally: bool,
+    /// Whether the tile is mirrored upside down
+    pub flipped_vertically: bool,
+    /// Whether the tile is mirrored along its top left to bottom right diagonal,
+    /// which is done before the other flips
+    pub flipped_diagonally: bool
+}
+
+impl Tile{
+    /// Splits a global tile id as it is stored by Tiled into the id and its flags.
+    ///
+    /// Returns `None` for `0`, which means there is no tile.
+    pub fn from_raw(raw: u32) -> Option<Self>{
+        let gid = raw & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL);
+        if gid == 0 {
+            return None
+        }
+        Some(Tile{
+            gid,
+            flipped_horizontally: raw & FLIPPED_HORIZONTALLY != 0,
+            flipped_vertically: raw & FLIPPED_VERTICALLY != 0,
+            flipped_diagonally: raw & FLIPPED_DIAGONALLY != 0
+        })
+    }
+
+    /// Returns the point of the tile's texture shown at a corner, from `(0., 0.)` to `(1., 1.)`.
+    ///
+    /// The corner is given from the top left like in Tiled, and the point is returned from the bottom left like in OpenGL.
+    pub fn tex_coords(&self, (mut u, mut v): (f32, f32)) -> [f32; 2]{
+        // Undoing the diagonal flip, then the horizontal and then the vertical one, but backwards
+        if self.flipped_vertically {
+            v = 1. - v;
+        }
+        if self.flipped_horizontally {
+            u = 1. - u;
+        }
+        if self.flipped_diagonally {
+            mem::swap(&mut u, &mut v);
+        }
+        [u, 1. - v]
+    }
+}
+
+/// A custom property set in Tiled on a map, layer, tileset, tile or object
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property{
+    /// A `bool` property
+    Bool(bool),
+    /// An `int` property
+    Int(i64),
+    /// A `float` property
+    Float(f64),
+    /// A `string` property
+    String(String),
+    /// A `color` property, as a colour korome draws with
+    Colour([f32; 4]),
+    /// A `file` property, as it was written in the map
+    File(String),
+    /// An `object` property, the id of the object it refers to
+    Object(u32)
+}
+
+impl Property{
+    #[inline]
+    /// Returns the value if it's a `Bool`
+    pub fn as_bool(&self) -> Option<bool>{
+        match *self {
+            Property::Bool(b) => Some(b),
+            _ => None
+        }
+    }
+    #[inline]
+    /// Returns the value if it's an `Int`
+    pub fn as_int(&self) -> Option<i64>{
+        match *self {
+            Property::Int(i) => Some(i),
+            _ => None
+        }
+    }
+    #[inline]
+    /// Returns the value if it's a `Float` or an `Int`
+    pub fn as_float(&self) -> Option<f64>{
+        match *self {
+            Property::Float(f) => Some(f),
+            Property::Int(i) => Some(i as f64),
+            _ => None
+        }
+    }
+    #[inline]
+    /// Returns the value if it's a `String` or a `File`
+    pub fn as_str(&self) -> Option<&str>{
+        match *self {
+            Property::String(ref s) | Property::File(ref s) => Some(s),
+            _ => None
+        }
+    }
+}
+
+/// The custom properties of something in a map by their names
+pub type Properties = BTreeMap<String, Property>;
+
+/// An image used by a tileset or one of its tiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilesetImage{
+    /// The path of the image, relative to the working directory if the map was loaded from a file
+    pub source: PathBuf,
+    /// The width of the image in pixels
+    pub width: u32,
+    /// The height of the image in pixels
+    pub height: u32
+}
+
+/// Extra information about one tile in a tileset
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TileInfo{
+    /// The tile's own image, if the tileset is a collection of images
+    pub image: Option<TilesetImage>,
+    /// The custom properties of the tile
+    pub properties: Properties
+}
+
+/// A set of tiles cut out of one image, or a collection of single images
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tileset{
+    /// The global id of the first tile in the set
+    pub first_gid: u32,
+    /// The name of the tileset
+    pub name: String,
+    /// The width of each tile in pixels
+    pub tile_width: u32,
+    /// The height of each tile in pixels
+    pub tile_height: u32,
+    /// The pixels between two tiles in the image
+    pub spacing: u32,
+    /// The pixels around the tiles at the edges of the image
+    pub margin: u32,
+    /// The amount of tiles in the set
+    pub tile_count: u32,
+    /// The amount of tiles in one row of the image
+    pub columns: u32,
+    /// The image the tiles are cut out of, `None` for a collection of images
+    pub image: Option<TilesetImage>,
+    /// Information about the tiles that have any, by their id in the tileset
+    pub tiles: BTreeMap<u32, TileInfo>,
+    /// The custom properties of the tileset
+    pub properties: Properties
+}
+
+impl Tileset{
+    #[inline]
+    /// Checks whether the global tile id is one of this tileset's tiles
+    pub fn contains(&self, gid: u32) -> bool{
+        gid >= self.first_gid && gid - self.first_gid < self.tile_count
+    }
+}
+
+/// The shape of an object in an object layer
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape{
+    /// A rectangle going right and down from the object's position by its size
+    Rectangle,
+    /// An ellipse inside the rectangle of the object
+    Ellipse,
+    /// Just the object's position
+    Point,
+    /// A closed shape with points relative to the object's position
+    Polygon(Vec<(f32, f32)>),
+    /// An open line with points relative to the object's position
+    Polyline(Vec<(f32, f32)>)
+}
+
+/// An object in an object layer, e.g. a spawn point, a trigger area or a collision shape
+///
+/// Positions and sizes are in pixels like in Tiled, from the top left corner of the map with y going down.
+/// `TileMap::map_to_world` turns them into world positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object{
+    /// The unique id of the object
+    pub id: u32,
+    /// The name of the object
+    pub name: String,
+    /// The type (or class) of the object
+    pub kind: String,
+    /// The position of the object
+    pub pos: (f32, f32),
+    /// The width and height of the object
+    pub size: (f32, f32),
+    /// The rotation of the object in degrees clockwise
+    pub rotation: f32,
+    /// The tile the object is shown as, if it's a tile object
+    pub tile: Option<Tile>,
+    /// Whether the object is shown
+    pub visible: bool,
+    /// The shape of the object
+    pub shape: ObjectShape,
+    /// The custom properties of the object
+    pub properties: Properties
+}
+
+/// What a layer contains
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerKind{
+    /// A raw global tile id for each tile of the map, row by row from the top left.
+    ///
+    /// `TileMap::tile` picks one out and decodes it.
+    Tiles(Vec<u32>),
+    /// Objects placed freely on the map
+    Objects(Vec<Object>)
+}
+
+/// A layer of a map
+///
+/// Layers inside groups are flattened out with the groups' visibility, opacity and offset applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer{
+    /// The name of the layer
+    pub name: String,
+    /// Whether the layer is shown
+    pub visible: bool,
+    /// The opacity of the layer between `0.` and `1.`
+    pub opacity: f32,
+    /// How far the layer is moved in pixels, with y going down
+    pub offset: (f32, f32),
+    /// The custom properties of the layer
+    pub properties: Properties,
+    /// The tiles or objects of the layer
+    pub kind: LayerKind
+}
+
+/// A map made in the Tiled map editor, loaded from a .tmx or .json file
+///
+/// Only orthogonal, finite maps are supported, and image layers are skipped.
+/// The map is drawn with its top left corner at a position in the world and goes right and down from there.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// use korome::*;
+///
+/// # fn main(){
+/// let graphics = Graphics::new("Tiles", 800, 600).unwrap();
+/// let map = TileMap::load_with_textures(&graphics, "level.tmx").unwrap();
+/// let spawn = map.layer_by_name("objects").and_then(|layer| match layer.kind {
+///     LayerKind::Objects(ref objects) => objects.iter().find(|o| o.name == "spawn"),
+///     _ => None
+/// });
+///
+/// run_until_closed(graphics, |_: &FrameInfo, drawer: &mut Drawer| {
+///     drawer.clear(0., 0., 0.);
+///     map.drawer().pos((-400., 300.)).draw(drawer);
+/// });
+/// # let _ = spawn;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TileMap{
+    /// The width of the map in tiles
+    pub width: u32,
+    /// The height of the map in tiles
+    pub height: u32,
+    /// The width of a tile in pixels
+    pub tile_width: u32,
+    /// The height of a tile in pixels
+    pub tile_height: u32,
+    /// The background colour of the map, if it has one
+    pub background: Option<[f32; 4]>,
+    /// The custom properties of the map
+    pub properties: Properties,
+    /// The tilesets of the map, ordered by their first global tile id
+    pub tilesets: Vec<Tileset>,
+    /// The layers of the map from the bottom up
+    pub layers: Vec<Layer>,
+    // The texture of each tile by its global id, once they are loaded
+    textures: Vec<Option<Texture>>,
+    // How many more columns and rows than the ones in view can have tiles reaching into the view
+    overhang: (u32, u32)
+}
+
+impl TileMap{
+    /// Loads a map from a .tmx or .json file, without loading the textures of its tilesets
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self>{
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        if is_json(path) {
+            TileMap::parse_json(&text, dir)
+        } else {
+            TileMap::parse_tmx(&text, dir)
+        }
+    }
+    /// Loads a map from a .tmx or .json file along with the textures of its tilesets
+    pub fn load_with_textures<P: AsRef<Path>>(display: &Display, path: P) -> Result<Self>{
+        let mut map = TileMap::load(path)?;
+        map.load_textures(display)?;
+        Ok(map)
+    }
+    /// Parses a map in Tiled's XML format.
+    ///
+    /// Paths of images and external tilesets are relative to `dir`.
+    pub fn parse_tmx(text: &str, dir: &Path) -> Result<Self>{
+        let root = Element::parse(text)?;
+        if root.name != "map" {
+            return invalid("the root element isn't a map")
+        }
+        check_orientation(root.attr_str("orientation"), root.attr::<u8>("infinite")? == Some(1))?;
+
+        let mut map = TileMap::new(
+            required(root.attr("width")?, "map is missing its width")?,
+            required(root.attr("height")?, "map is missing its height")?,
+            required(root.attr("tilewidth")?, "map is missing its tile width")?,
+            required(root.attr("tileheight")?, "map is missing its tile height")?
+        );
+        map.background = match root.attr_str("backgroundcolor") {
+            Some(colour) => Some(required(parse_colour(colour), "invalid background colour")?),
+            None => None
+        };
+        map.properties = xml_properties(&root)?;
+        for tileset in root.children("tileset") {
+            map.tilesets.push(xml_tileset(tileset, dir)?);
+        }
+        xml_layers(&root, &Group::default(), map.width * map.height, &mut map.layers)?;
+
+        map.sort_tilesets();
+        Ok(map)
+    }
+    /// Parses a map in Tiled's JSON format.
+    ///
+    /// Paths of images and external tilesets are relative to `dir`.
+    pub fn parse_json(text: &str, dir: &Path) -> Result<Self>{
+        let root: Value = serde_json::from_str(text)?;
+        if root.get("type").and_then(Value::as_str).is_some_and(|t| t != "map") {
+            return invalid("the file isn't a map")
+        }
+        check_orientation(root.get("orientation").and_then(Value::as_str),
+            root.get("infinite").and_then(Value::as_bool) == Some(true))?;
+
+        let mut map = TileMap::new(
+            required(json_u32(&root, "width"), "map is missing its width")?,
+            required(json_u32(&root, "height"), "map is missing its height")?,
+            required(json_u32(&root, "tilewidth"), "map is missing its tile width")?,
+            required(json_u32(&root, "tileheight"), "map is missing its tile height")?
+        );
+        map.background = match root.get("backgroundcolor").and_then(Value::as_str) {
+            Some(colour) => Some(required(parse_colour(colour), "invalid background colour")?),
+            None => None
+        };
+        map.properties = json_properties(&root)?;
+        for tileset in json_array(&root, "tilesets") {
+            map.tilesets.push(json_tileset(tileset, dir)?);
+        }
+        json_layers(&root, &Group::default(), map.width * map.height, &mut map.layers)?;
+
+        map.sort_tilesets();
+        Ok(map)
+    }
+
+    fn new(width: u32, height: u32, tile_width: u32, tile_height: u32) -> Self{
+        TileMap{
+            width,
+            height,
+            tile_width,
+            tile_height,
+            background: None,
+            properties: Properties::new(),
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            textures: Vec::new(),
+            overhang: (0, 0)
+        }
+    }
+
+    fn sort_tilesets(&mut self){
+        self.tilesets.sort_by_key(|tileset| tileset.first_gid);
+    }
+
+    /// Loads the images of all the tilesets into textures so the map can be drawn
+    pub fn load_textures(&mut self, display: &Display) -> Result<()>{
+        let mut textures = Vec::new();
+
+        for tileset in &self.tilesets {
+            let first = tileset.first_gid as usize;
+            let (tw, th) = (tileset.tile_width, tileset.tile_height);
+
+            if let Some(ref image) = tileset.image {
+                let texture = Texture::from_file(display, &image.source)?;
+                let (width, height) = texture.get_size();
+                let fits = |size: f32, tile: u32| ((size as u32).saturating_sub(2 * tileset.margin) + tileset.spacing) / (tile + tileset.spacing).max(1);
+
+                let columns = if tileset.columns > 0 { tileset.columns } else { fits(width, tw) };
+                let count = if tileset.tile_count > 0 { tileset.tile_count } else { columns * fits(height, th) };
+                for id in 0..count {
+                    let (col, row) = (id % columns.max(1), id / columns.max(1));
+                    let x = tileset.margin + col * (tw + tileset.spacing);
+                    let y = tileset.margin + row * (th + tileset.spacing);
+                    put(&mut textures, first + id as usize, texture.sub_texture(x, y, tw, th));
+                }
+            }
+            for (&id, tile) in &tileset.tiles {
+                if let Some(ref image) = tile.image {
+                    put(&mut textures, first + id as usize, Texture::from_file(display, &image.source)?);
+                }
+            }
+        }
+
+        // Tiles larger than the map's grid stick out upwards and to the right of their cell
+        let (tw, th) = (self.tile_width.max(1) as f32, self.tile_height.max(1) as f32);
+        self.overhang = textures.iter().flatten().fold((0, 0), |(x, y), texture| {
+            let (w, h) = texture.get_size();
+            // Diagonally flipped tiles swap their width and height
+            let largest = w.max(h);
+            (x.max(((largest / tw).ceil() as u32).saturating_sub(1)), y.max(((largest / th).ceil() as u32).saturating_sub(1)))
+        });
+        self.textures = textures;
+        Ok(())
+    }
+
+    /// Returns the tileset that the global tile id belongs to
+    pub fn tileset(&self, gid: u32) -> Option<&Tileset>{
+        self.tilesets.iter().rev().find(|tileset| tileset.first_gid <= gid).filter(|tileset| tileset.contains(gid))
+    }
+    /// Returns the custom properties of a tile by its global id, if it has any
+    pub fn tile_properties(&self, gid: u32) -> Option<&Properties>{
+        self.tileset(gid)
+            .and_then(|tileset| tileset.tiles.get(&(gid - tileset.first_gid)))
+            .map(|tile| &tile.properties)
+    }
+    /// Returns the first layer with the given name
+    pub fn layer_by_name(&self, name: &str) -> Option<&Layer>{
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+    /// Returns the tile in the given column and row of a tile layer.
+    ///
+    /// Returns `None` if there is no tile there, or the layer isn't a tile layer.
+    pub fn tile(&self, layer: usize, column: u32, row: u32) -> Option<Tile>{
+        if column >= self.width || row >= self.height {
+            return None
+        }
+        match self.layers.get(layer).map(|layer| &layer.kind) {
+            Some(LayerKind::Tiles(tiles)) => tiles.get((row * self.width + column) as usize).and_then(|&raw| Tile::from_raw(raw)),
+            _ => None
+        }
+    }
+
+    /// Converts a position in pixels in the map, like that of an `Object`,
+    /// into a position in the world, when the map is drawn at `pos`
+    pub fn map_to_world(&self, pos: (f32, f32), (x, y): (f32, f32)) -> (f32, f32){
+        (pos.0 + x, pos.1 - y)
+    }
+    /// Returns the column and row of the tile at a position in the world,
+    /// when the map is drawn at `pos`, or `None` if the position is outside the map
+    pub fn tile_at(&self, pos: (f32, f32), (x, y): (f32, f32)) -> Option<(u32, u32)>{
+        let (x, y) = (x - pos.0, pos.1 - y);
+        if x < 0. || y < 0. {
+            return None
+        }
+        let (column, row) = ((x / self.tile_width as f32) as u32, (y / self.tile_height as f32) as u32);
+        if column < self.width && row < self.height {
+            Some((column, row))
+        } else {
+            None
+        }
+    }
+    /// Returns the centre in the world of the tile in the given column and row, when the map is drawn at `pos`
+    pub fn tile_centre(&self, pos: (f32, f32), (column, row): (u32, u32)) -> (f32, f32){
+        let (tw, th) = (self.tile_width as f32, self.tile_height as f32);
+        (pos.0 + (column as f32 + 0.5) * tw, pos.1 - (row as f32 + 0.5) * th)
+    }
+    /// Returns the columns and rows of the tiles that can be seen through the camera,
+    /// when the map is drawn at `pos` on a surface with half size `h_size`
+    pub fn visible_tiles(&self, pos: (f32, f32), camera: &Camera, (hw, hh): (f32, f32)) -> (Range<u32>, Range<u32>){
+        let mut min = (f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &corner in &[(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)] {
+            let (x, y) = camera.screen_to_world(corner);
+            // Relative to the top left of the map, going down
+            let (x, y) = (x - pos.0, pos.1 - y);
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+
+        let range = |min: f32, max: f32, size: u32, count: u32| {
+            let size = size.max(1) as f32;
+            let end = ((max / size).ceil().max(0.) as u32).min(count);
+            ((min / size).floor().max(0.) as u32).min(end)..end
+        };
+        (range(min.0, max.0, self.tile_width, self.width), range(min.1, max.1, self.tile_height, self.height))
+    }
+
+    #[inline]
+    /// Returns an object used for drawing the map with a `Drawer`.
+    ///
+    /// Nothing is drawn until `load_textures` has been called.
+    pub fn drawer(&self) -> TileMapDrawer<'_>{
+        TileMapDrawer{
+            pos: (0., 0.),
+            colour: [1., 1., 1., 1.],
+            layer: None,
+            map: self
+        }
+    }
+}
+
+// Sets the texture of a global tile id, growing the list if needed
+fn put(textures: &mut Vec<Option<Texture>>, gid: usize, texture: Texture){
+    if textures.len() <= gid {
+        textures.resize(gid + 1, None);
+    }
+    textures[gid] = Some(texture);
+}
+
+/// Object for drawing the tile layers of a `TileMap` using the builder pattern
+///
+/// Only the tiles that can be seen with the drawer's camera are drawn,
+/// and they are batched like any other textures.
+#[must_use = "drawers are lazy and do nothing until consumed"]
+#[derive(Debug, Clone)]
+pub struct TileMapDrawer<'a>{
+    /// The position in the world of the map's top left corner
+    pub pos: (f32, f32),
+    /// The colour the tiles will be drawn with
+    pub colour: [f32; 4],
+    layer: Option<usize>,
+    map: &'a TileMap
+}
+
+impl<'a> TileMapDrawer<'a>{
+    set!{/// Sets the position in the world of the map's top left corner
+        fn TileMapDrawer; pos: (f32, f32)}
+    set!{/// Sets the colour the tiles will be drawn with, on top of each layer's opacity
+        fn TileMapDrawer; colour: [f32; 4]}
+    set!{/// Sets the one layer to be drawn, which is drawn even if it's hidden,
+        /// instead of all the visible tile layers
+        fn TileMapDrawer; layer: usize => layer: Some(layer)}
+
+    /// Consumes self and adds the visible tiles to the `Drawer`'s batch
+    pub fn draw<S: DrawTarget>(self, drawer: &mut Drawer<S>){
+        let TileMapDrawer{pos, colour, layer: only, map} = self;
+        let (camera, h_size) = (drawer.camera(), drawer.get_h_size());
+        let (tw, th) = (map.tile_width as f32, map.tile_height as f32);
+
+        for (i, layer) in map.layers.iter().enumerate() {
+            let tiles = match layer.kind {
+                LayerKind::Tiles(ref tiles) if only.map_or(layer.visible, |only| only == i) => tiles,
+                _ => continue
+            };
+            let pos = (pos.0 + layer.offset.0, pos.1 - layer.offset.1);
+            let colour = [colour[0], colour[1], colour[2], colour[3] * layer.opacity];
+
+            let (columns, rows) = map.visible_tiles(pos, &camera, h_size);
+            let columns = columns.start.saturating_sub(map.overhang.0)..columns.end;
+            let rows = rows.start..(rows.end + map.overhang.1).min(map.height);
+
+            for row in rows {
+                for column in columns.clone() {
+                    let tile = match tiles.get((row * map.width + column) as usize).and_then(|&raw| Tile::from_raw(raw)) {
+                        Some(tile) => tile,
+                        None => continue
+                    };
+                    let texture = match map.textures.get(tile.gid as usize) {
+                        Some(Some(texture)) => texture,
+                        _ => continue
+                    };
+                    let (mut w, mut h) = texture.get_size();
+                    if tile.flipped_diagonally {
+                        mem::swap(&mut w, &mut h);
+                    }
+
+                    // Tiles sit on the bottom left corner of their cell
+                    let left = pos.0 + column as f32 * tw;
+                    let bottom = pos.1 - (row + 1) as f32 * th;
+                    drawer.push_texture(texture, [
+                        ([left    , bottom    ], tile.tex_coords((0., 1.))),
+                        ([left + w, bottom    ], tile.tex_coords((1., 1.))),
+                        ([left + w, bottom + h], tile.tex_coords((1., 0.))),
+                        ([left    , bottom + h], tile.tex_coords((0., 0.)))
+                    ], colour);
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn required<T>(value: Option<T>, reason: &'static str) -> Result<T>{
+    value.ok_or(TileMapError::InvalidMap(reason))
+}
+
+// Tiles without a size can't be cut out of their image
+#[inline]
+fn tile_size(value: Option<u32>, reason: &'static str) -> Result<u32>{
+    match required(value, reason)? {
+        0 => invalid("tileset has a zero tile size"),
+        size => Ok(size)
+    }
+}
+
+fn is_json(path: &Path) -> bool{
+    path.extension().is_some_and(|ext| ext == "json" || ext == "tsj" || ext == "tmj")
+}
+
+fn check_orientation(orientation: Option<&str>, infinite: bool) -> Result<()>{
+    if orientation.is_some_and(|o| o != "orthogonal") {
+        invalid("only orthogonal maps are supported")
+    } else if infinite {
+        invalid("infinite maps are not supported")
+    } else {
+        Ok(())
+    }
+}
+
+// Parses "#RRGGBB" or "#AARRGGBB" like Tiled writes them
+fn parse_colour(colour: &str) -> Option<[f32; 4]>{
+    let hex = colour.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let channel = |shift: u32| ((value >> shift) & 0xff) as f32 / 255.;
+
+    match hex.len() {
+        6 => Some([channel(16), channel(8), channel(0), 1.]),
+        8 => Some([channel(16), channel(8), channel(0), channel(24)]),
+        _ => None
+    }
+}
+
+fn parse_property(kind: &str, value: &str) -> Result<Property>{
+    let number = |reason| TileMapError::InvalidMap(reason);
+    Ok(match kind {
+        "bool" => Property::Bool(value == "true"),
+        "int" => Property::Int(value.parse().map_err(|_| number("int property isn't an integer"))?),
+        "float" => Property::Float(value.parse().map_err(|_| number("float property isn't a number"))?),
+        "object" => Property::Object(value.parse().map_err(|_| number("object property isn't an object id"))?),
+        // Tiled leaves colours that haven't been picked empty
+        "color" if value.is_empty() => Property::Colour([0., 0., 0., 0.]),
+        "color" => Property::Colour(parse_colour(value).ok_or(TileMapError::InvalidMap("color property isn't a colour"))?),
+        "file" => Property::File(value.to_owned()),
+        _ => Property::String(value.to_owned())
+    })
+}
+
+// The visibility, opacity and offset that a group of layers gives the layers in it
+#[derive(Debug, Clone, Copy)]
+struct Group{
+    visible: bool,
+    opacity: f32,
+    offset: (f32, f32)
+}
+
+impl Default for Group{
+    fn default() -> Self{
+        Group{
+            visible: true,
+            opacity: 1.,
+            offset: (0., 0.)
+        }
+    }
+}
+
+impl Group{
+    fn inner(&self, visible: bool, opacity: f32, (x, y): (f32, f32)) -> Self{
+        Group{
+            visible: self.visible && visible,
+            opacity: self.opacity * opacity,
+            offset: (self.offset.0 + x, self.offset.1 + y)
+        }
+    }
+}
+
+// Turns tile data that may be compressed into global tile ids
+fn decode_tiles(data: &[u8], compression: Option<&str>) -> Result<Vec<u32>>{
+    let data = match compression {
+        None | Some("") => data.to_vec(),
+        Some("zlib") => inflate(InflateStream::from_zlib(), data)?,
+        Some("gzip") => inflate(InflateStream::new(), gzip_body(data)?)?,
+        Some(_) => return invalid("unsupported tile data compression")
+    };
+    if data.len() % 4 != 0 {
+        return invalid("tile data isn't a whole number of tiles")
+    }
+    Ok(data.chunks(4).map(|b| u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16 | u32::from(b[3]) << 24).collect())
+}
+
+fn inflate(mut stream: InflateStream, mut data: &[u8]) -> Result<Vec<u8>>{
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let (read, bytes) = match stream.update(data) {
+            Ok(update) => update,
+            Err(_) => return invalid("corrupt compressed tile data")
+        };
+        if read == 0 && bytes.is_empty() {
+            break
+        }
+        out.extend_from_slice(bytes);
+        data = &data[read..];
+    }
+    Ok(out)
+}
+
+// Skips the header and trailer of gzip data, leaving the deflate stream inside
+fn gzip_body(data: &[u8]) -> Result<&[u8]>{
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+
+    if data.len() < 18 || data[..3] != [0x1f, 0x8b, 8] {
+        return invalid("corrupt gzip tile data")
+    }
+    let flags = data[3];
+    let end = data.len() - 8;
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        pos += 2 + (usize::from(data[10]) | usize::from(data[11]) << 8);
+    }
+    for &flag in &[FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            pos += data[pos.min(end)..end].iter().position(|&b| b == 0).unwrap_or(end) + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+    if pos > end {
+        return invalid("corrupt gzip tile data")
+    }
+    Ok(&data[pos..end])
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>>{
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut len) = (0u32, 0);
+
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return invalid("tile data isn't valid base64")
+        };
+        bits = (bits << 6 | u32::from(value)) & 0xffff;
+        len += 6;
+        if len >= 8 {
+            len -= 8;
+            out.push((bits >> len) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn decode_csv(text: &str) -> Result<Vec<u32>>{
+    text.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| TileMapError::InvalidMap("csv tile data isn't a list of tile ids")))
+        .collect()
+}
+
+fn check_tiles(tiles: Vec<u32>, count: u32) -> Result<Vec<u32>>{
+    if tiles.len() == count as usize {
+        Ok(tiles)
+    } else {
+        invalid("tile layer doesn't have a tile for each place on the map")
+    }
+}
+
+// An element of an XML document with everything inside it
+#[derive(Debug)]
+struct Element{
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String
+}
+
+impl Element{
+    fn parse(text: &str) -> Result<Self>{
+        let mut stack: Vec<Element> = Vec::new();
+
+        for event in EventReader::from_str(text) {
+            match event? {
+                XmlEvent::StartElement{name, attributes, ..} => stack.push(Element{
+                    name: name.local_name,
+                    attributes: attributes.into_iter().map(|a| (a.name.local_name, a.value)).collect(),
+                    children: Vec::new(),
+                    text: String::new()
+                }),
+                XmlEvent::EndElement{..} => {
+                    let element = stack.pop().expect("xml-rs checks that elements are closed");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element)
+                    }
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                },
+                _ => ()
+            }
+        }
+        invalid("the document is empty")
+    }
+
+    fn attr_str(&self, name: &str) -> Option<&str>{
+        self.attributes.iter().find(|a| a.0 == name).map(|a| &*a.1)
+    }
+    fn attr<T: FromStr>(&self, name: &str) -> Result<Option<T>>{
+        match self.attr_str(name) {
+            Some(value) => value.parse().map(Some).map_err(|_| TileMapError::InvalidMap("attribute has an invalid number")),
+            None => Ok(None)
+        }
+    }
+    fn child(&self, name: &str) -> Option<&Element>{
+        self.children.iter().find(|c| c.name == name)
+    }
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a Element> + 'a{
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+fn xml_properties(element: &Element) -> Result<Properties>{
+    let mut properties = Properties::new();
+    for property in element.child("properties").into_iter().flat_map(|p| p.children("property")) {
+        let name = required(property.attr_str("name"), "property is missing its name")?;
+        let kind = property.attr_str("type").unwrap_or("string");
+        // Class properties have their members inside, which aren't supported
+        if kind == "class" {
+            continue
+        }
+        // Strings with several lines are stored as the text of the element
+        let value = property.attr_str("value").unwrap_or(&property.text);
+        properties.insert(name.to_owned(), parse_property(kind, value)?);
+    }
+    Ok(properties)
+}
+
+fn xml_image(element: &Element, dir: &Path) -> Result<Option<TilesetImage>>{
+    let image = match element.child("image") {
+        Some(image) => image,
+        None => return Ok(None)
+    };
+    Ok(Some(TilesetImage{
+        source: dir.join(required(image.attr_str("source"), "image is missing its source")?),
+        width: image.attr("width")?.unwrap_or(0),
+        height: image.attr("height")?.unwrap_or(0)
+    }))
+}
+
+fn xml_tileset(element: &Element, dir: &Path) -> Result<Tileset>{
+    let first_gid = required(element.attr("firstgid")?, "tileset is missing its first gid")?;
+
+    if let Some(source) = element.attr_str("source") {
+        let path = dir.join(source);
+        let text = fs::read_to_string(&path)?;
+        let dir = path.parent().unwrap_or(dir);
+        let mut tileset = if is_json(&path) {
+            json_tileset(&serde_json::from_str(&text)?, dir)?
+        } else {
+            xml_tileset_contents(&Element::parse(&text)?, dir)?
+        };
+        tileset.first_gid = first_gid;
+        return Ok(tileset)
+    }
+    let mut tileset = xml_tileset_contents(element, dir)?;
+    tileset.first_gid = first_gid;
+    Ok(tileset)
+}
+
+// Parses a tileset without its first gid, as external tilesets don't have one
+fn xml_tileset_contents(element: &Element, dir: &Path) -> Result<Tileset>{
+    let mut tiles = BTreeMap::new();
+    for tile in element.children("tile") {
+        tiles.insert(required(tile.attr("id")?, "tile is missing its id")?, TileInfo{
+            image: xml_image(tile, dir)?,
+            properties: xml_properties(tile)?
+        });
+    }
+
+    Ok(Tileset{
+        first_gid: 0,
+        name: element.attr_str("name").unwrap_or("").to_owned(),
+        tile_width: tile_size(element.attr("tilewidth")?, "tileset is missing its tile width")?,
+        tile_height: tile_size(element.attr("tileheight")?, "tileset is missing its tile height")?,
+        spacing: element.attr("spacing")?.unwrap_or(0),
+        margin: element.attr("margin")?.unwrap_or(0),
+        tile_count: element.attr("tilecount")?.unwrap_or(0),
+        columns: element.attr("columns")?.unwrap_or(0),
+        image: xml_image(element, dir)?,
+        tiles,
+        properties: xml_properties(element)?
+    })
+}
+
+fn xml_layers(parent: &Element, group: &Group, count: u32, layers: &mut Vec<Layer>) -> Result<()>{
+    for element in &parent.children {
+        let inner = group.inner(
+            element.attr::<u8>("visible")? != Some(0),
+            element.attr("opacity")?.unwrap_or(1.),
+            (element.attr("offsetx")?.unwrap_or(0.), element.attr("offsety")?.unwrap_or(0.))
+        );
+        let kind = match &*element.name {
+            "layer" => LayerKind::Tiles(check_tiles(xml_tile_data(element)?, count)?),
+            "objectgroup" => LayerKind::Objects(element.children("object").map(xml_object).collect::<Result<_>>()?),
+            "group" => {
+                xml_layers(element, &inner, count, layers)?;
+                continue
+            }
+            _ => continue
+        };
+        layers.push(Layer{
+            name: element.attr_str("name").unwrap_or("").to_owned(),
+            visible: inner.visible,
+            opacity: inner.opacity,
+            offset: inner.offset,
+            properties: xml_properties(element)?,
+            kind
+        });
+    }
+    Ok(())
+}
+
+fn xml_tile_data(layer: &Element) -> Result<Vec<u32>>{
+    let data = required(layer.child("data"), "tile layer is missing its data")?;
+    if data.child("chunk").is_some() {
+        return invalid("infinite maps are not supported")
+    }
+
+    match data.attr_str("encoding") {
+        None => data.children("tile").map(|tile| tile.attr("gid").map(|gid| gid.unwrap_or(0))).collect(),
+        Some("csv") => decode_csv(&data.text),
+        Some("base64") => decode_tiles(&decode_base64(&data.text)?, data.attr_str("compression")),
+        Some(_) => invalid("unsupported tile data encoding")
+    }
+}
+
+fn xml_object(element: &Element) -> Result<Object>{
+    let points = |name| -> Result<Option<Vec<(f32, f32)>>>{
+        let points = match element.child(name).and_then(|e| e.attr_str("points")) {
+            Some(points) => points,
+            None => return Ok(None)
+        };
+        points.split_whitespace()
+            .map(|point| {
+                let mut xy = point.split(',').map(str::parse);
+                match (xy.next(), xy.next()) {
+                    (Some(Ok(x)), Some(Ok(y))) => Ok((x, y)),
+                    _ => invalid("object has invalid points")
+                }
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    };
+    let shape = if let Some(points) = points("polygon")? {
+        ObjectShape::Polygon(points)
+    } else if let Some(points) = points("polyline")? {
+        ObjectShape::Polyline(points)
+    } else if element.child("ellipse").is_some() {
+        ObjectShape::Ellipse
+    } else if element.child("point").is_some() {
+        ObjectShape::Point
+    } else {
+        ObjectShape::Rectangle
+    };
+
+    Ok(Object{
+        id: element.attr("id")?.unwrap_or(0),
+        name: element.attr_str("name").unwrap_or("").to_owned(),
+        kind: element.attr_str("type").or_else(|| element.attr_str("class")).unwrap_or("").to_owned(),
+        pos: (element.attr("x")?.unwrap_or(0.), element.attr("y")?.unwrap_or(0.)),
+        size: (element.attr("width")?.unwrap_or(0.), element.attr("height")?.unwrap_or(0.)),
+        rotation: element.attr("rotation")?.unwrap_or(0.),
+        tile: element.attr("gid")?.and_then(Tile::from_raw),
+        visible: element.attr::<u8>("visible")? != Some(0),
+        shape,
+        properties: xml_properties(element)?
+    })
+}
+
+fn json_u32(value: &Value, key: &str) -> Option<u32>{
+    value.get(key).and_then(Value::as_u64).map(|n| n as u32)
+}
+
+fn json_f32(value: &Value, key: &str) -> Option<f32>{
+    value.get(key).and_then(Value::as_f64).map(|n| n as f32)
+}
+
+fn json_str<'a>(value: &'a Value, key: &str) -> &'a str{
+    value.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+fn json_array<'a>(value: &'a Value, key: &str) -> ::std::slice::Iter<'a, Value>{
+    value.get(key).and_then(Value::as_array).map(|a| a.iter()).unwrap_or_else(|| [].iter())
+}
+
+fn json_properties(value: &Value) -> Result<Properties>{
+    let mut properties = Properties::new();
+    for property in json_array(value, "properties") {
+        let name = required(property.get("name").and_then(Value::as_str), "property is missing its name")?;
+        let value = property.get("value").unwrap_or(&Value::Null);
+        let property = match (json_str(property, "type"), value) {
+            ("class", _) => continue,
+            (kind, Value::String(s)) => parse_property(kind, s)?,
+            (_, &Value::Bool(b)) => Property::Bool(b),
+            ("object", v) => Property::Object(required(v.as_u64(), "object property isn't an object id")? as u32),
+            ("int", v) => Property::Int(required(v.as_i64(), "int property isn't an integer")?),
+            (_, v) => Property::Float(required(v.as_f64(), "float property isn't a number")?)
+        };
+        properties.insert(name.to_owned(), property);
+    }
+    Ok(properties)
+}
+
+fn json_image(value: &Value, dir: &Path) -> Option<TilesetImage>{
+    value.get("image").and_then(Value::as_str).map(|source| TilesetImage{
+        source: dir.join(source),
+        width: json_u32(value, "imagewidth").unwrap_or(0),
+        height: json_u32(value, "imageheight").unwrap_or(0)
+    })
+}
+
+fn json_tileset(value: &Value, dir: &Path) -> Result<Tileset>{
+    if let Some(source) = value.get("source").and_then(Value::as_str) {
+        let path = dir.join(source);
+        let text = fs::read_to_string(&path)?;
+        let dir = path.parent().unwrap_or(dir);
+        let mut tileset = if is_json(&path) {
+            json_tileset(&serde_json::from_str(&text)?, dir)?
+        } else {
+            xml_tileset_contents(&Element::parse(&text)?, dir)?
+        };
+        tileset.first_gid = required(json_u32(value, "firstgid"), "tileset is missing its first gid")?;
+        return Ok(tileset)
+    }
+
+    let mut tiles = BTreeMap::new();
+    for tile in json_array(value, "tiles") {
+        tiles.insert(required(json_u32(tile, "id"), "tile is missing its id")?, TileInfo{
+            image: json_image(tile, dir),
+            properties: json_properties(tile)?
+        });
+    }
+
+    Ok(Tileset{
+        // Missing in external tilesets, where it's set by the map instead
+        first_gid: json_u32(value, "firstgid").unwrap_or(0),
+        name: json_str(value, "name").to_owned(),
+        tile_width: tile_size(json_u32(value, "tilewidth"), "tileset is missing its tile width")?,
+        tile_height: tile_size(json_u32(value, "tileheight"), "tileset is missing its tile height")?,
+        spacing: json_u32(value, "spacing").unwrap_or(0),
+        margin: json_u32(value, "margin").unwrap_or(0),
+        tile_count: json_u32(value, "tilecount").unwrap_or(0),
+        columns: json_u32(value, "columns").unwrap_or(0),
+        image: json_image(value, dir),
+        tiles,
+        properties: json_properties(value)?
+    })
+}
+
+fn json_layers(parent: &Value, group: &Group, count: u32, layers: &mut Vec<Layer>) -> Result<()>{
+    for value in json_array(parent, "layers") {
+        let inner = group.inner(
+            value.get("visible").and_then(Value::as_bool).unwrap_or(true),
+            json_f32(value, "opacity").unwrap_or(1.),
+            (json_f32(value, "offsetx").unwrap_or(0.), json_f32(value, "offsety").unwrap_or(0.))
+        );
+        let kind = match json_str(value, "type") {
+            "tilelayer" => LayerKind::Tiles(check_tiles(json_tile_data(value)?, count)?),
+            "objectgroup" => LayerKind::Objects(json_array(value, "objects").map(json_object).collect::<Result<_>>()?),
+            "group" => {
+                json_layers(value, &inner, count, layers)?;
+                continue
+            }
+            _ => continue
+        };
+        layers.push(Layer{
+            name: json_str(value, "name").to_owned(),
+            visible: inner.visible,
+            opacity: inner.opacity,
+            offset: inner.offset,
+            properties: json_properties(value)?,
+            kind
+        });
+    }
+    Ok(())
+}
+
+fn json_tile_data(layer: &Value) -> Result<Vec<u32>>{
+    if layer.get("chunks").is_some() {
+        return invalid("infinite maps are not supported")
+    }
+    match layer.get("data") {
+        Some(Value::Array(data)) => data.iter()
+            .map(|gid| gid.as_u64().map(|gid| gid as u32).ok_or(TileMapError::InvalidMap("tile data isn't a list of tile ids")))
+            .collect(),
+        Some(Value::String(data)) if json_str(layer, "encoding") == "base64" => {
+            let compression = layer.get("compression").and_then(Value::as_str);
+            decode_tiles(&decode_base64(data)?, compression)
+        }
+        Some(_) => invalid("unsupported tile data encoding"),
+        None => invalid("tile layer is missing its data")
+    }
+}
+
+fn json_object(value: &Value) -> Result<Object>{
+    let points = |name| value.get(name).and_then(Value::as_array).map(|points| {
+        points.iter().map(|p| (json_f32(p, "x").unwrap_or(0.), json_f32(p, "y").unwrap_or(0.))).collect()
+    });
+    let is = |name| value.get(name).and_then(Value::as_bool) == Some(true);
+    let shape = if let Some(points) = points("polygon") {
+        ObjectShape::Polygon(points)
+    } else if let Some(points) = points("polyline") {
+        ObjectShape::Polyline(points)
+    } else if is("ellipse") {
+        ObjectShape::Ellipse
+    } else if is("point") {
+        ObjectShape::Point
+    } else {
+        ObjectShape::Rectangle
+    };
+    let kind = match json_str(value, "type") {
+        "" => json_str(value, "class"),
+        kind => kind
+    };
+
+    Ok(Object{
+        id: json_u32(value, "id").unwrap_or(0),
+        name: json_str(value, "name").to_owned(),
+        kind: kind.to_owned(),
+        pos: (json_f32(value, "x").unwrap_or(0.), json_f32(value, "y").unwrap_or(0.)),
+        size: (json_f32(value, "width").unwrap_or(0.), json_f32(value, "height").unwrap_or(0.)),
+        rotation: json_f32(value, "rotation").unwrap_or(0.),
+        tile: json_u32(value, "gid").and_then(Tile::from_raw),
+        visible: value.get("visible").and_then(Value::as_bool).unwrap_or(true),
+        shape,
+        properties: json_properties(value)?
+    })
+}
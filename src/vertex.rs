@@ -1,18 +1,20 @@
 #[derive(Debug, Copy, Clone)]
 pub struct TextureVertex {
     position  : [f32; 2],
-    tex_coords: [f32; 2]
+    tex_coords: [f32; 2],
+    colour    : [f32; 4]
 }
 
 // Implement the glium::vertex::Vertex trait for `TextureVertex`.
-implement_vertex!(TextureVertex, position, tex_coords);
+implement_vertex!(TextureVertex, position, tex_coords, colour);
 
 impl TextureVertex{
     #[inline]
-    pub fn new(position: [f32; 2], tex_coords: [f32; 2]) -> Self{
+    pub fn new(position: [f32; 2], tex_coords: [f32; 2], colour: [f32; 4]) -> Self{
         TextureVertex{
-            position  : position,
-            tex_coords: tex_coords,
+            position,
+            tex_coords,
+            colour
         }
     }
 }
@@ -30,8 +32,8 @@ impl ColourVertex{
     #[inline]
     pub fn new(position: [f32; 2], colour: [f32; 4]) -> Self{
         ColourVertex{
-            position: position,
-            colour: colour
+            position,
+            colour
         }
     }
 }
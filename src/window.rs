@@ -0,0 +1,125 @@
+use glium::DisplayBuild;
+use glium::glutin::{self, WindowBuilder};
+
+use super::{Graphics, GraphicsCreationError};
+
+type Size = (u32, u32);
+
+/// Creates a `Graphics` with a window configured in more detail than `Graphics::new` allows
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate korome;
+/// # fn main(){
+/// let graphics = korome::GraphicsBuilder::new("Game", 1280, 720)
+///     .min_size((640, 360))
+///     .multisampling(4)
+///     .build()
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphicsBuilder{
+    pub(crate) title: String,
+    pub(crate) size: (u32, u32),
+    pub(crate) fullscreen: bool,
+    resizable: bool,
+    min_size: Option<(u32, u32)>,
+    multisampling: u16,
+    decorations: bool,
+    vsync: bool
+}
+
+impl GraphicsBuilder{
+    /// Creates a builder for a resizable, decorated window with vsync and the given title and size
+    pub fn new<S: Into<String>>(title: S, width: u32, height: u32) -> Self{
+        GraphicsBuilder{
+            title: title.into(),
+            size: (width, height),
+            fullscreen: false,
+            resizable: true,
+            min_size: None,
+            multisampling: 0,
+            decorations: true,
+            vsync: true
+        }
+    }
+    set!{/// Sets whether the window covers the whole primary monitor
+        fn GraphicsBuilder; fullscreen: bool}
+    set!{/// Sets whether the window can be resized by the user
+        fn GraphicsBuilder; resizable: bool}
+    set!{/// Sets the smallest size the window can be resized to
+        fn GraphicsBuilder; min_size: (u32, u32) => min_size: Some(min_size)}
+    set!{/// Sets the amount of samples used for multisample anti-aliasing, which has to be a power of two
+        fn GraphicsBuilder; multisampling: u16}
+    set!{/// Sets whether the window has a title bar and borders
+        fn GraphicsBuilder; decorations: bool}
+    set!{/// Sets whether frames are synchronised with the monitor's refresh rate
+        fn GraphicsBuilder; vsync: bool}
+
+    /// Opens the window
+    pub fn build<'a>(self) -> Result<Graphics<'a>, GraphicsCreationError>{
+        let display = self.window_builder().build_glium()?;
+        Graphics::from_display(display).map(|graphics| graphics.with_window_config(self))
+    }
+
+    // Makes a glutin builder for the window as it is configured now
+    pub(crate) fn window_builder(&self) -> WindowBuilder<'static>{
+        let (width, height) = self.size;
+        let mut builder = WindowBuilder::new()
+            .with_title(self.title.clone())
+            .with_dimensions(width, height)
+            .with_decorations(self.decorations);
+
+        if self.fullscreen {
+            builder = builder.with_fullscreen(glutin::get_primary_monitor());
+        }
+        let (min_size, max_size) = self.size_limits();
+        if let Some((w, h)) = min_size {
+            builder = builder.with_min_dimensions(w, h);
+        }
+        if let Some((w, h)) = max_size {
+            builder = builder.with_max_dimensions(w, h);
+        }
+        if self.multisampling > 0 {
+            builder = builder.with_multisampling(self.multisampling);
+        }
+        if self.vsync {
+            builder = builder.with_vsync();
+        }
+        builder
+    }
+
+    // The smallest and largest size the window can be resized to
+    fn size_limits(&self) -> (Option<Size>, Option<Size>){
+        if self.resizable {
+            (self.min_size, None)
+        } else {
+            // glutin can't make windows that can't be resized, so it is kept at one size instead
+            (Some(self.size), Some(self.size))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn fixed_size_windows_keep_their_size(){
+        let builder = GraphicsBuilder::new("test", 640, 480);
+        assert_eq!(builder.size_limits(), (None, None));
+        let builder = builder.min_size((320, 240));
+        assert_eq!(builder.size_limits(), (Some((320, 240)), None));
+        // The smallest size is ignored when the window keeps one size anyway
+        assert_eq!(builder.resizable(false).size_limits(), (Some((640, 480)), Some((640, 480))));
+    }
+
+    #[test]
+    fn vsync_can_be_turned_off(){
+        let builder = GraphicsBuilder::new("test", 640, 480);
+        assert!(builder.window_builder().opengl.vsync);
+        assert!(!builder.vsync(false).window_builder().opengl.vsync);
+    }
+}
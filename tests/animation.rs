@@ -0,0 +1,62 @@
+extern crate korome;
+
+use korome::{Animation, AnimationPlayer, PlayMode, SpriteSheet};
+
+fn frames_over(mode: PlayMode, steps: usize) -> Vec<usize> {
+    let mut player = AnimationPlayer::new(Animation::uniform(0..3, 0.1, mode));
+
+    (0..steps).map(|_| {
+        let frame = player.current_frame();
+        player.update(0.1);
+        frame
+    }).collect()
+}
+
+#[test]
+fn looping_starts_over() {
+    assert_eq!(frames_over(PlayMode::Loop, 7), [0, 1, 2, 0, 1, 2, 0]);
+}
+
+#[test]
+fn ping_pong_turns_around() {
+    assert_eq!(frames_over(PlayMode::PingPong, 8), [0, 1, 2, 1, 0, 1, 2, 1]);
+}
+
+#[test]
+fn once_stops_at_last_frame() {
+    let mut player = AnimationPlayer::new(Animation::uniform(0..3, 0.1, PlayMode::Once));
+    player.update(1.);
+
+    assert_eq!(player.current_frame(), 2);
+    assert!(player.is_finished());
+}
+
+#[test]
+fn frames_have_own_durations() {
+    let animation = Animation::new(PlayMode::Loop).frame(4, 0.5).frame(7, 0.25);
+    let mut player = AnimationPlayer::new(animation);
+
+    player.update(0.4);
+    assert_eq!(player.current_frame(), 4);
+    player.update(0.2);
+    assert_eq!(player.current_frame(), 7);
+    player.update(0.2);
+    assert_eq!(player.current_frame(), 4);
+}
+
+#[test]
+fn grids_are_sliced_row_by_row() {
+    // The 4 pixels left over at the right and bottom are ignored
+    assert_eq!(SpriteSheet::grid_frames((20, 12), 8, 4), [
+        (0, 0, 8, 4), (8, 0, 8, 4),
+        (0, 4, 8, 4), (8, 4, 8, 4),
+        (0, 8, 8, 4), (8, 8, 8, 4)
+    ]);
+    assert!(SpriteSheet::grid_frames((4, 4), 8, 8).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "sprite sheet frames can't be 8x0")]
+fn sprite_sheet_frames_need_a_size() {
+    SpriteSheet::grid_frames((16, 16), 8, 0);
+}
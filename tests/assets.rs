@@ -0,0 +1,92 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+use korome::{Assets, Handle};
+use image::RgbaImage;
+use std::env;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn handles_share_their_asset() {
+    let handle = Handle::new(vec![1, 2]);
+    let clone = handle.clone();
+    assert!(handle.ptr_eq(&clone));
+    assert!(!handle.ptr_eq(&Handle::new(vec![1, 2])));
+
+    // Replacing it through one handle changes it for every clone
+    assert_eq!(handle.replace(vec![3]), [1, 2]);
+    assert_eq!(*clone.get(), [3]);
+
+    let assets = Assets::new();
+    assert!(assets.is_empty() && !assets.contains("missing.png"));
+    assets.set_hot_reload(false);
+    assert!(!assets.hot_reload());
+    assert!(!assets.remove("missing.png"));
+}
+
+#[test]
+#[ignore]
+fn assets_are_cached_and_reloaded() {
+    let graphics = graphics();
+    let path = env::temp_dir().join("korome_asset.png");
+    RgbaImage::new(4, 2).save(&path).unwrap();
+
+    let texture = graphics.texture(&path).unwrap();
+    let again = graphics.texture(env::temp_dir().join(".").join("korome_asset.png")).unwrap();
+    assert!(texture.ptr_eq(&again));
+    assert_eq!(graphics.assets().len(), 1);
+    assert!(graphics.assets().reload_changed(&graphics).is_empty());
+
+    // Keep saving until the change is seen, as file times can be coarse
+    let modified = || fs::metadata(&path).unwrap().modified().unwrap();
+    let before = modified();
+    while modified() == before {
+        thread::sleep(Duration::from_millis(50));
+        RgbaImage::new(8, 6).save(&path).unwrap();
+    }
+
+    let reloaded = graphics.assets().reload_changed(&graphics);
+    assert_eq!(reloaded.len(), 1);
+    assert!(reloaded[0].1.is_ok());
+    assert_eq!(again.get().get_size(), (8., 6.));
+
+    assert!(graphics.assets().remove(&path));
+    assert!(!graphics.assets().contains(&path));
+    assert!(!graphics.texture(&path).unwrap().ptr_eq(&texture));
+}
+
+#[test]
+#[ignore]
+fn assets_load_in_the_background() {
+    let graphics = graphics();
+    let dir = env::temp_dir();
+    for (i, size) in [4, 8].iter().enumerate() {
+        RgbaImage::new(*size, *size).save(dir.join(format!("korome_async{}.png", i))).unwrap();
+    }
+    let assets = graphics.assets();
+
+    let small = graphics.texture_async(dir.join("korome_async0.png")).unwrap();
+    let big = graphics.texture_async(dir.join("korome_async1.png")).unwrap();
+    graphics.texture_async(dir.join("korome_missing.png")).unwrap();
+    // Nothing is uploaded until the assets are updated
+    assert_eq!(small.get().get_size(), (1., 1.));
+    assert!(assets.is_loading() && assets.progress() == 0.);
+
+    let start = Instant::now();
+    while assets.is_loading() && start.elapsed() < Duration::from_secs(10) {
+        assets.update(&graphics);
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(assets.progress(), 1.);
+    assert!(assets.is_loaded(dir.join("korome_async1.png")));
+    assert_eq!((small.get().get_size(), big.get().get_size()), ((4., 4.), (8., 8.)));
+    let errors = assets.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].0.ends_with("korome_missing.png"));
+}
@@ -0,0 +1,22 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+use korome::{AtlasBuilder, TextureError};
+use image::RgbaImage;
+
+#[test]
+#[ignore]
+fn atlases_too_big_for_a_texture_are_refused() {
+    let graphics = graphics();
+    // Taller than any graphics card takes
+    let atlas = AtlasBuilder::new().add("pole", RgbaImage::new(1, 100_000)).build(&graphics);
+    match atlas {
+        Err(TextureError::TooBig(_, height)) => assert_eq!(height, 100_001),
+        other => panic!("expected the atlas to be too big, got {:?}", other.map(|_| ()))
+    }
+
+    assert!(AtlasBuilder::new().add("dot", RgbaImage::new(1, 1)).build(&graphics).is_ok());
+}
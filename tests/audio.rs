@@ -0,0 +1,219 @@
+extern crate korome;
+
+use korome::{Sound, Music, Mixer, Bus, Audio, AudioBackend, NullBackend, WavBackend, AudioError};
+use std::io::{self, Cursor};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// A 16-bit stereo WAV file of the given samples, made by the WAV backend
+fn wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let mut backend = WavBackend::new(Cursor::new(Vec::new()), sample_rate).unwrap();
+    backend.write(samples).unwrap();
+    backend.finish().unwrap().into_inner()
+}
+
+fn render(mixer: &mut Mixer, frames: usize) -> Vec<(f32, f32)> {
+    let mut out = vec![0.; frames * 2];
+    mixer.render(&mut out);
+    out.chunks(2).map(|f| (f[0], f[1])).collect()
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 0.01
+}
+
+#[test]
+fn wav_files_are_decoded() {
+    let sound = Sound::from_bytes(&wav(&[0.5, -0.5, 0.25, 0.], 22050)).unwrap();
+    assert_eq!((sound.channels(), sound.sample_rate(), sound.frames()), (2, 22050, 2));
+    let expected = [0.5, -0.5, 0.25, 0.];
+    assert!(sound.samples().iter().zip(&expected).all(|(&a, &b)| close(a, b)));
+
+    // 8-bit mono with a chunk before the data that should be skipped
+    let mut bytes = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0\x01\0\x01\0\x40\x1f\0\0\x40\x1f\0\0\x01\0\x08\0".to_vec();
+    bytes.extend_from_slice(b"LIST\x03\0\0\0abc\0data\x03\0\0\0\x80\xff\x00");
+    let sound = Sound::from_bytes(&bytes).unwrap();
+    assert_eq!((sound.channels(), sound.sample_rate()), (1, 8000));
+    assert_eq!(sound.samples(), [0., 127. / 128., -1.]);
+    assert_eq!(sound.duration(), 3. / 8000.);
+
+    match Sound::from_bytes(b"MThd, not audio at all") {
+        Err(AudioError::InvalidAudio(_)) => (),
+        other => panic!("midi gave {:?}", other)
+    }
+    assert!(Sound::from_bytes(b"OggS but not really").is_err());
+}
+
+#[test]
+fn wav_chunk_sizes_are_not_trusted() {
+    // A fmt chunk claiming to be 4GB is rejected before anything is allocated for it
+    let bytes = b"RIFF\0\0\0\0WAVEfmt \xff\xff\xff\xff\x01\0\x01\0";
+    match Sound::from_bytes(bytes) {
+        Err(AudioError::InvalidAudio("fmt chunk is too long")) => (),
+        other => panic!("a huge fmt chunk gave {:?}", other)
+    }
+
+    // An odd chunk as big as it can be is skipped past the end, rather than overflowing its padding
+    let mut bytes = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0\x01\0\x01\0\x40\x1f\0\0\x40\x1f\0\0\x01\0\x08\0".to_vec();
+    bytes.extend_from_slice(b"LIST\xff\xff\xff\xffdata\x01\0\0\0\x80");
+    match Sound::from_bytes(&bytes) {
+        Err(AudioError::InvalidAudio("no data chunk")) => (),
+        other => panic!("a huge chunk gave {:?}", other)
+    }
+}
+
+// A hand-made OGG Vorbis file of 39 blocks of a stereo tone at 8000 Hz, with a different pitch in each ear
+const TONE: &[u8] = include_bytes!("assets/tone.ogg");
+
+#[test]
+fn ogg_vorbis_is_decoded() {
+    let sound = Sound::from_bytes(TONE).unwrap();
+    assert_eq!((sound.channels(), sound.sample_rate(), sound.frames()), (2, 8000, 39 * 128));
+
+    let peak = |channel: usize| sound.samples().iter().skip(channel).step_by(2).fold(0f32, |m, s| m.max(s.abs()));
+    assert!(peak(0) > 0.4 && peak(0) < 0.6);
+    assert!(peak(1) > 0.4 && peak(1) < 0.6);
+    // The ears have tones of their own
+    assert!(sound.samples().chunks(2).any(|f| !close(f[0], f[1])));
+}
+
+#[test]
+fn ogg_music_loops() {
+    let frames = 39 * 128;
+    let sound = Sound::from_bytes(TONE).unwrap();
+    let mut mixer = Mixer::new(8000);
+    mixer.play_music(Music::from_bytes(TONE.to_vec()).unwrap(), 0.);
+
+    let out = render(&mut mixer, frames * 2 + 100);
+    assert!(mixer.is_music_playing());
+    // Streamed the same as when decoded all at once, and starting over from the beginning at the end
+    for i in (0..frames).step_by(97) {
+        let expected = (sound.samples()[i * 2], sound.samples()[i * 2 + 1]);
+        assert!(close(out[i].0, expected.0) && close(out[i].1, expected.1), "frame {}", i);
+        assert!(close(out[frames + i].0, expected.0) && close(out[frames + i].1, expected.1), "looped frame {}", i);
+    }
+}
+
+#[test]
+fn voices_have_volume_pan_and_pitch() {
+    let mut mixer = Mixer::new(4);
+    let sound = Sound::from_samples(vec![0.5; 4], 1, 4);
+
+    let left = sound.player().pan(-1.).play(&mut mixer);
+    let quiet = sound.player().volume(0.5).pan(1.).pitch(2.).play(&mut mixer);
+    assert_eq!(mixer.voices(), 2);
+
+    let out = render(&mut mixer, 2);
+    assert_eq!(out, [(0.5, 0.25), (0.5, 0.25)]);
+    // Played twice as fast, the second sound is already over
+    assert!(mixer.is_playing(left) && !mixer.is_playing(quiet));
+    assert!(!mixer.set_voice_volume(quiet, 1.));
+
+    assert!(mixer.set_voice_pan(left, 0.));
+    assert_eq!(render(&mut mixer, 3), [(0.5, 0.5), (0.5, 0.5), (0., 0.)]);
+    assert_eq!(mixer.voices(), 0);
+
+    // Sounds at half the mixer's rate are stretched and blended between samples
+    let ramp = Sound::from_samples(vec![0., 1.], 1, 2);
+    let looping = ramp.player().looping(true).play(&mut mixer);
+    let out: Vec<f32> = render(&mut mixer, 6).into_iter().map(|f| f.0).collect();
+    assert_eq!(out, [0., 0.5, 1., 0.5, 0., 0.5]);
+    mixer.stop(looping);
+    assert_eq!(render(&mut mixer, 1), [(0., 0.)]);
+}
+
+#[test]
+fn buses_scale_what_goes_through_them() {
+    let mut mixer = Mixer::new(10);
+    let sound = Sound::from_samples(vec![0.8; 10], 1, 10);
+
+    mixer.set_volume(Bus::Sfx, 0.5);
+    mixer.set_volume(Bus::Master, 0.5);
+    assert_eq!(mixer.volume(Bus::Music), 1.);
+    sound.player().play(&mut mixer);
+    assert_eq!(render(&mut mixer, 1), [(0.2, 0.2)]);
+
+    mixer.stop_all();
+    sound.player().bus(Bus::Music).play(&mut mixer);
+    assert_eq!(render(&mut mixer, 1), [(0.4, 0.4)]);
+
+    // Loud mixes are clipped
+    mixer.set_volume(Bus::Master, 4.);
+    assert_eq!(render(&mut mixer, 1), [(1., 1.)]);
+
+    let mut backend = NullBackend::default();
+    mixer.render_to(&mut backend, 25).unwrap();
+    assert_eq!(backend.frames, 25);
+}
+
+#[test]
+fn music_loops_and_crossfades() {
+    let mut mixer = Mixer::new(10);
+    let once = Music::from_bytes(wav(&[0.5, 0.5, 0.5, 0.5], 10)).unwrap().looping(false);
+    mixer.play_music(once, 0.);
+    assert!(render(&mut mixer, 2).iter().all(|f| close(f.0, 0.5)));
+    render(&mut mixer, 1);
+    assert!(!mixer.is_music_playing());
+
+    // Looping music keeps going past its end
+    let first = Music::from_bytes(wav(&[0.5; 6], 10)).unwrap();
+    mixer.play_music(first, 0.);
+    assert!(render(&mut mixer, 20).iter().all(|f| close(f.0, 0.5)));
+
+    // Over a second the first fades out while the second fades in
+    let second = Music::from_bytes(wav(&[-0.5; 6], 10)).unwrap().volume(0.5);
+    mixer.play_music(second, 1.);
+    let out = render(&mut mixer, 10);
+    assert!(close(out[0].0, 0.5) && close(out[4].0, 0.6 * 0.5 - 0.4 * 0.25));
+    assert!(close(out[9].0, 0.1 * 0.5 - 0.9 * 0.25));
+    assert!(close(render(&mut mixer, 5)[4].0, -0.25));
+
+    mixer.stop_music(0.5);
+    let out = render(&mut mixer, 6);
+    assert!(close(out[2].0, -0.25 * 0.6) && close(out[5].0, 0.));
+    assert!(!mixer.is_music_playing());
+}
+
+// Counts the stereo samples written to it, failing after `limit` of them
+struct Counter {
+    frames: Arc<Mutex<usize>>,
+    limit: usize
+}
+
+impl AudioBackend for Counter {
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        let mut frames = self.frames.lock().unwrap();
+        if *frames >= self.limit {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "unplugged"))
+        }
+        *frames += samples.len() / 2;
+        Ok(())
+    }
+}
+
+#[test]
+fn audio_keeps_to_the_clock() {
+    let frames = Arc::new(Mutex::new(0));
+    let audio = Audio::new(10000, Counter{frames: frames.clone(), limit: usize::MAX});
+    thread::sleep(Duration::from_millis(200));
+
+    // A backend that never blocks still only gets about 0.2 seconds' worth, not as fast as it can be mixed
+    let written = *frames.lock().unwrap();
+    assert!((1000..6000).contains(&written), "{} frames", written);
+    assert!(audio.is_running() && audio.error().is_none());
+}
+
+#[test]
+fn audio_stops_when_the_backend_fails() {
+    let audio = Audio::new(44100, Counter{frames: Arc::new(Mutex::new(0)), limit: 0});
+    for _ in 0..100 {
+        if !audio.is_running() {
+            break
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(!audio.is_running());
+    assert_eq!(audio.error().unwrap().kind(), io::ErrorKind::BrokenPipe);
+    assert!(audio.error().is_none());
+}
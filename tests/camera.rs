@@ -0,0 +1,42 @@
+extern crate korome;
+
+use korome::Camera;
+
+fn assert_close((ax, ay): (f32, f32), (bx, by): (f32, f32)) {
+    assert!((ax - bx).abs() < 1e-4 && (ay - by).abs() < 1e-4, "{:?} != {:?}", (ax, ay), (bx, by));
+}
+
+#[test]
+fn default_camera_changes_nothing() {
+    let camera = Camera::new();
+
+    assert_close(camera.screen_to_world((12., -34.)), (12., -34.));
+    assert_close(camera.world_to_screen((12., -34.)), (12., -34.));
+}
+
+#[test]
+fn pan_zoom_and_rotate() {
+    let camera = Camera{
+        pos: (100., 50.),
+        zoom: 2.,
+        rotation: ::std::f32::consts::FRAC_PI_2
+    };
+
+    // The camera's position is always in the centre of the screen
+    assert_close(camera.world_to_screen((100., 50.)), (0., 0.));
+    // With the camera turned a quarter to the left, what is above it appears to the right
+    assert_close(camera.world_to_screen((100., 60.)), (20., 0.));
+    assert_close(camera.screen_to_world((20., 0.)), (100., 60.));
+}
+
+#[test]
+fn conversions_are_inverses() {
+    let camera = Camera{
+        pos: (-3., 7.5),
+        zoom: 0.75,
+        rotation: 1.2
+    };
+    let world = (40., -25.);
+
+    assert_close(camera.screen_to_world(camera.world_to_screen(world)), world);
+}
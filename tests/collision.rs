@@ -0,0 +1,144 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+use korome::{Collider, SpatialHash, Texture, Quad};
+use image::RgbaImage;
+use std::f32::consts::FRAC_PI_4;
+
+fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4
+}
+
+#[test]
+fn overlaps_and_translation_vectors() {
+    let a = Collider::Aabb{centre: (0., 0.), h_size: (10., 10.)};
+    let b = Collider::Aabb{centre: (15., 2.), h_size: (10., 10.)};
+    let contact = a.collide(&b).unwrap();
+    assert!(close(contact.mtv(), (-5., 0.)));
+    assert!(close(b.collide(&a).unwrap().mtv(), (5., 0.)));
+    assert!(!a.overlaps(&Collider::Aabb{centre: (25., 0.), h_size: (4., 4.)}));
+
+    let circle = Collider::Circle{centre: (0., 17.), radius: 8.};
+    assert!(close(circle.collide(&a).unwrap().mtv(), (0., 1.)));
+    let other = Collider::Circle{centre: (0., 30.), radius: 6.};
+    assert!(close(circle.collide(&other).unwrap().mtv(), (0., -1.)));
+    // Just past the corner of the box, which only the axis towards the corner notices
+    assert!(!a.overlaps(&Collider::Circle{centre: (16., 16.), radius: 8.}));
+
+    // A diamond reaching 10 * sqrt(2) out to the right
+    let diamond = Collider::Obb{centre: (0., 0.), h_size: (10., 10.), rotation: FRAC_PI_4};
+    let right = Collider::Aabb{centre: (22., 0.), h_size: (10., 10.)};
+    let depth = 10. * 2f32.sqrt() - 12.;
+    assert!(close(diamond.collide(&right).unwrap().mtv(), (-depth, 0.)));
+    assert!(!diamond.overlaps(&Collider::Aabb{centre: (20., 20.), h_size: (5., 5.)}));
+
+    let triangle = Collider::Polygon(vec![(0., 0.), (0., 10.), (10., 0.)]);
+    assert!(triangle.overlaps(&Collider::Circle{centre: (4., 4.), radius: 1.}));
+    assert!(!triangle.overlaps(&Collider::Circle{centre: (8., 8.), radius: 2.}));
+}
+
+#[test]
+fn points_and_bounds() {
+    let diamond = Collider::Obb{centre: (5., 5.), h_size: (10., 10.), rotation: FRAC_PI_4};
+    assert!(diamond.contains_point((5., 18.)));
+    assert!(!diamond.contains_point((14., 14.)));
+    let (min, max) = diamond.bounds();
+    let reach = 10. * 2f32.sqrt();
+    assert!(close(min, (5. - reach, 5. - reach)) && close(max, (5. + reach, 5. + reach)));
+
+    let mut triangle = Collider::Polygon(vec![(0., 0.), (10., 0.), (0., 10.)]);
+    triangle.translate((1., 1.));
+    assert!(triangle.contains_point((3., 3.)));
+    assert!(!triangle.contains_point((9., 9.)));
+    assert_eq!(triangle.bounds(), ((1., 1.), (11., 11.)));
+    assert!(close(triangle.centre(), (13. / 3., 13. / 3.)));
+}
+
+#[test]
+fn raycasts() {
+    let aabb = Collider::Aabb{centre: (10., 0.), h_size: (2., 4.)};
+    let hit = aabb.raycast((0., 0.), (3., 0.), f32::INFINITY).unwrap();
+    assert!(close((hit.distance, 0.), (8., 0.)));
+    assert!(close(hit.point, (8., 0.)) && close(hit.normal, (-1., 0.)));
+    assert!(aabb.raycast((0., 0.), (1., 0.), 7.).is_none());
+    assert!(aabb.raycast((0., 0.), (-1., 0.), f32::INFINITY).is_none());
+    assert!(aabb.raycast((0., 5.), (1., 0.), f32::INFINITY).is_none());
+    assert_eq!(aabb.raycast((10., 1.), (0., 1.), 1.).unwrap().distance, 0.);
+
+    let circle = Collider::Circle{centre: (0., 10.), radius: 2.};
+    let hit = circle.raycast((0., 0.), (0., 1.), 100.).unwrap();
+    assert!(close(hit.point, (0., 8.)) && close(hit.normal, (0., -1.)));
+    assert!(circle.raycast((3., 0.), (0., 1.), 100.).is_none());
+}
+
+#[test]
+fn spatial_hash_finds_neighbours() {
+    let colliders = [
+        Collider::Circle{centre: (0., 0.), radius: 5.},
+        Collider::Circle{centre: (8., 0.), radius: 5.},
+        Collider::Aabb{centre: (100., 100.), h_size: (5., 5.)},
+        // Covers many cells and overlaps the first two
+        Collider::Aabb{centre: (0., -20.), h_size: (40., 16.)},
+    ];
+    let mut grid = SpatialHash::new(16.);
+    for (i, collider) in colliders.iter().enumerate() {
+        grid.insert(i, collider);
+    }
+    assert_eq!(grid.len(), 4);
+
+    let pairs: Vec<_> = grid.pairs().into_iter().map(|(&a, &b)| (a, b)).collect();
+    assert_eq!(pairs, [(0, 1), (0, 3), (1, 3)]);
+    assert_eq!(grid.query(&Collider::Circle{centre: (98., 98.), radius: 1.}), [&2]);
+    assert_eq!(grid.query_point((-30., -30.)), [&3]);
+    assert!(grid.query_point((60., 60.)).is_empty());
+
+    grid.clear();
+    assert!(grid.is_empty() && grid.pairs().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "spatial hash cells can't be 0 wide")]
+fn spatial_hash_cells_need_a_size() {
+    SpatialHash::<usize>::new(0.);
+}
+
+#[test]
+fn huge_colliders_are_checked_against_everything() {
+    let colliders = [
+        Collider::Circle{centre: (0., 0.), radius: 5.},
+        Collider::Aabb{centre: (0., 0.), h_size: (1e9, 1e9)},
+        Collider::Circle{centre: (1e6, 1e6), radius: 5.},
+        Collider::Aabb{centre: (0., 0.), h_size: (f32::INFINITY, 1.)},
+    ];
+    let mut grid = SpatialHash::new(1.);
+    for (i, collider) in colliders.iter().enumerate() {
+        grid.insert(i, collider);
+    }
+
+    let pairs: Vec<_> = grid.pairs().into_iter().map(|(&a, &b)| (a, b)).collect();
+    assert_eq!(pairs, [(0, 1), (0, 3), (1, 2), (1, 3)]);
+    assert_eq!(grid.query_point((1e6, 1e6)), [&1, &2]);
+    assert_eq!(grid.query(&Collider::Aabb{centre: (0., 0.), h_size: (f32::INFINITY, f32::INFINITY)}), [&0, &1, &2, &3]);
+}
+
+#[test]
+fn rects_are_only_oriented_when_rotated() {
+    assert_eq!(Collider::rect((1., 2.), (8., 4.), 0.), Collider::Aabb{centre: (1., 2.), h_size: (8., 4.)});
+    assert_eq!(Collider::rect((1., 2.), (8., 4.), 1.), Collider::Obb{centre: (1., 2.), h_size: (8., 4.), rotation: 1.});
+}
+
+#[test]
+#[ignore]
+fn colliders_from_drawers() {
+    let graphics = graphics();
+    let texture = Texture::new(&graphics, RgbaImage::new(8, 4)).unwrap();
+    let quad = Quad::new_rect(&graphics, [1., 1., 1., 1.], 6., 2.).unwrap();
+
+    assert_eq!(texture.drawer().pos((1., 2.)).scale(2.).collider(), Collider::Aabb{centre: (1., 2.), h_size: (8., 4.)});
+    let collider = quad.drawer().pos((10., 0.)).rotation(::std::f32::consts::FRAC_PI_2).collider();
+    let (min, max) = collider.bounds();
+    assert!((min.0 - 9.).abs() < 1e-4 && (max.1 - 3.).abs() < 1e-4);
+}
@@ -0,0 +1,30 @@
+// Helpers for the tests that render, shared by the test files with `mod common;`
+//
+// Rendering needs an OpenGL implementation (e.g. Mesa's llvmpipe through OSMesa),
+// so those tests are ignored by default and run with `cargo test -- --ignored` where there is one.
+
+// Not every test file uses every helper
+#![allow(dead_code)]
+
+use korome::{Graphics, Drawer};
+use image::RgbaImage;
+
+pub fn graphics() -> Graphics<'static> {
+    sized_graphics(64, 64)
+}
+
+pub fn sized_graphics(width: u32, height: u32) -> Graphics<'static> {
+    Graphics::headless(width, height).expect("no headless OpenGL context")
+}
+
+// Draws a frame cleared to black and returns what it looks like
+pub fn capture<F: FnOnce(&mut Drawer)>(graphics: &Graphics, draw: F) -> RgbaImage {
+    let mut drawer = Drawer::new(graphics);
+    drawer.clear(0., 0., 0.);
+    draw(&mut drawer);
+    drawer.capture()
+}
+
+pub fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+    image.get_pixel(x, y).data
+}
@@ -0,0 +1,72 @@
+extern crate korome;
+
+use korome::{FixedTimestep, GameUpdate};
+
+use std::collections::VecDeque;
+
+#[test]
+fn steps_accumulate_across_frames() {
+    let mut timestep = FixedTimestep::new(0.25);
+
+    assert_eq!(timestep.advance(0.1), 0);
+    assert_eq!(timestep.advance(0.1), 0);
+    assert_eq!(timestep.advance(0.1), 1);
+    assert!((timestep.alpha() - 0.2).abs() < 1e-4);
+}
+
+#[test]
+fn several_steps_in_a_slow_frame() {
+    let mut timestep = FixedTimestep::new(0.25);
+
+    assert_eq!(timestep.advance(0.8), 3);
+    assert!((timestep.alpha() - 0.2).abs() < 1e-4);
+}
+
+#[test]
+fn catching_up_is_capped() {
+    let mut timestep = FixedTimestep::new(0.1).max_steps(4);
+
+    assert_eq!(timestep.advance(10.), 4);
+    // The time that couldn't be caught up on is dropped
+    assert_eq!(timestep.advance(0.05), 0);
+    assert_eq!(timestep.advance(0.05), 1);
+}
+
+#[test]
+fn every_step_is_taken_and_its_update_queued() {
+    let mut timestep = FixedTimestep::new(0.1);
+    let mut updates = VecDeque::new();
+    let mut steps = 0;
+    let mut step = || {
+        steps += 1;
+        GameUpdate::SetTitle(format!("step {}", steps))
+    };
+
+    // Only one update is handed on a frame, the rest wait in order for the frames after
+    let handed: Vec<_> = [0.35, 0., 0., 0.].iter().map(|&delta| timestep.take_steps(delta, &mut updates, &mut step)).collect();
+    assert_eq!(handed, [
+        GameUpdate::SetTitle("step 1".to_owned()),
+        GameUpdate::SetTitle("step 2".to_owned()),
+        GameUpdate::SetTitle("step 3".to_owned()),
+        GameUpdate::Nothing
+    ]);
+}
+
+#[test]
+fn closing_stops_the_steps() {
+    let mut timestep = FixedTimestep::new(0.1);
+    let mut updates = VecDeque::new();
+    let mut steps = 0;
+
+    let update = timestep.take_steps(0.45, &mut updates, || {
+        steps += 1;
+        match steps {
+            1 => GameUpdate::Nothing,
+            2 => GameUpdate::SetTitle("left for later".to_owned()),
+            _ => GameUpdate::Close
+        }
+    });
+    assert_eq!(update, GameUpdate::Close);
+    assert_eq!(steps, 3);
+    assert_eq!(updates, [GameUpdate::SetTitle("left for later".to_owned())]);
+}
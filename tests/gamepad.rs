@@ -0,0 +1,77 @@
+extern crate korome;
+
+use korome::{Gamepads, GamepadEvent, GamepadButton, GamepadAxis, MockBackend};
+use GamepadEvent::*;
+
+fn gamepads() -> (Gamepads, MockBackend) {
+    let backend = MockBackend::new();
+    (Gamepads::new(Box::new(backend.clone())), backend)
+}
+
+#[test]
+fn connect_and_disconnect() {
+    let (mut pads, backend) = gamepads();
+    backend.push(Connected(0, "First".to_owned()));
+    backend.push(Connected(2, "Second".to_owned()));
+
+    assert_eq!(pads.update().len(), 2);
+    assert_eq!(pads.connected().collect::<Vec<_>>(), [0, 2]);
+    assert_eq!(pads.name(2), Some("Second"));
+
+    backend.push(Button(2, GamepadButton::A, true));
+    backend.push(Disconnected(0));
+    pads.update();
+
+    assert!(!pads.is_connected(0));
+    assert!(pads.is_down(2, GamepadButton::A));
+    assert!(!pads.is_down(0, GamepadButton::A));
+}
+
+#[test]
+fn buttons_only_report_changes() {
+    let (mut pads, backend) = gamepads();
+    backend.push(Connected(0, "Pad".to_owned()));
+    backend.push(Button(0, GamepadButton::Start, true));
+    backend.push(Button(0, GamepadButton::Start, true));
+    // Events for gamepads that aren't connected are ignored
+    backend.push(Button(1, GamepadButton::Start, true));
+
+    assert_eq!(pads.update(), [Connected(0, "Pad".to_owned()), Button(0, GamepadButton::Start, true)]);
+
+    backend.push(Button(0, GamepadButton::Start, false));
+    assert_eq!(pads.update(), [Button(0, GamepadButton::Start, false)]);
+    assert!(!pads.is_down(0, GamepadButton::Start));
+}
+
+#[test]
+fn dead_zone() {
+    let (mut pads, backend) = gamepads();
+    pads.set_dead_zone(0.2);
+    backend.push(Connected(0, "Pad".to_owned()));
+    backend.push(Axis(0, GamepadAxis::LeftStickX, 0.1));
+    backend.push(Axis(0, GamepadAxis::LeftStickY, -0.6));
+    backend.push(Axis(0, GamepadAxis::RightTrigger, 1.));
+
+    // The small movement is inside the dead zone and doesn't change anything
+    assert_eq!(pads.update().len(), 3);
+    let (x, y) = pads.left_stick(0);
+    assert_eq!(x, 0.);
+    assert!((y + 0.5).abs() < 1e-5);
+    assert_eq!(pads.axis(0, GamepadAxis::RightTrigger), 1.);
+    assert_eq!(pads.axis(1, GamepadAxis::RightTrigger), 0.);
+}
+
+#[test]
+fn reconnecting_resets_state() {
+    let (mut pads, backend) = gamepads();
+    backend.push(Connected(0, "Pad".to_owned()));
+    backend.push(Button(0, GamepadButton::X, true));
+    backend.push(Axis(0, GamepadAxis::DPadX, 1.));
+    backend.push(Disconnected(0));
+    backend.push(Connected(0, "Pad".to_owned()));
+    pads.update();
+
+    assert!(pads.is_connected(0));
+    assert!(!pads.is_down(0, GamepadButton::X));
+    assert_eq!(pads.axis(0, GamepadAxis::DPadX), 0.);
+}
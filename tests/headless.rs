@@ -0,0 +1,54 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::{graphics, capture, pixel};
+use korome::{Drawer, Quad, Texture};
+use image::{RgbaImage, Rgba};
+
+#[test]
+#[ignore]
+fn clear_fills_frame() {
+    let graphics = graphics();
+    let mut drawer = Drawer::new(&graphics);
+
+    drawer.clear(0., 0., 1.);
+    let frame = drawer.capture();
+
+    assert_eq!(frame.dimensions(), (64, 64));
+    assert!(frame.pixels().all(|p| p.data == [0, 0, 255, 255]));
+}
+
+#[test]
+#[ignore]
+fn quad_is_drawn_at_its_position() {
+    let graphics = graphics();
+    let quad = Quad::new_rect(&graphics, [1., 0., 0., 1.], 16., 16.).unwrap();
+
+    // The top right quarter of the frame, as y goes upwards
+    let frame = capture(&graphics, |drawer| quad.drawer().pos((16., 16.)).draw(drawer));
+
+    assert_eq!(pixel(&frame, 48, 16), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 16, 48), [0, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+#[ignore]
+fn texture_is_drawn_upright_and_tinted() {
+    let graphics = graphics();
+    // White on top, green at the bottom
+    let image = RgbaImage::from_fn(32, 32, |_, y| if y < 16 {
+        Rgba{data: [255, 255, 255, 255]}
+    } else {
+        Rgba{data: [0, 255, 0, 255]}
+    });
+    let texture = Texture::new(&graphics, image).unwrap();
+
+    let frame = capture(&graphics, |drawer| texture.drawer().colour([1., 1., 0., 1.]).draw(drawer));
+
+    assert_eq!(pixel(&frame, 32, 20), [255, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 44), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 4, 4), [0, 0, 0, 255]);
+}
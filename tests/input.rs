@@ -0,0 +1,45 @@
+extern crate korome;
+
+use korome::{InputMap, Binding, InputMapError, VirtualKeyCode, MouseButton};
+
+#[test]
+fn config_round_trip() {
+    let map = InputMap::new()
+        .with_action("jump", VirtualKeyCode::Space)
+        .with_action("jump", VirtualKeyCode::W)
+        .with_action("fire", MouseButton::Left)
+        .with_action("fire", MouseButton::Other(4))
+        .with_axis("move_x", VirtualKeyCode::A, VirtualKeyCode::D)
+        .with_axis("move_x", VirtualKeyCode::Left, VirtualKeyCode::Right);
+
+    let config = map.to_config();
+    assert_eq!(InputMap::from_config(&config).unwrap(), map);
+}
+
+#[test]
+fn parse_config() {
+    let map = InputMap::from_config("
+        # Controls
+        action jump = Space, W
+        action fire = MouseLeft
+        axis move_x = A/D, Left / Right
+    ").unwrap();
+
+    assert_eq!(map.action_bindings("jump"), [Binding::Key(VirtualKeyCode::Space), Binding::Key(VirtualKeyCode::W)]);
+    assert_eq!(map.action_bindings("fire"), [Binding::Mouse(MouseButton::Left)]);
+    assert_eq!(map.axis_bindings("move_x"), [
+        (Binding::Key(VirtualKeyCode::A), Binding::Key(VirtualKeyCode::D)),
+        (Binding::Key(VirtualKeyCode::Left), Binding::Key(VirtualKeyCode::Right))
+    ]);
+    assert!(map.action_bindings("crouch").is_empty());
+}
+
+#[test]
+fn invalid_config() {
+    match InputMap::from_config("action jump = Space\naction fire = Trigger") {
+        Err(InputMapError::ParseError(2, _)) => (),
+        other => panic!("expected a parse error on line 2, got {:?}", other)
+    }
+    assert!(InputMap::from_config("axis move_x = A").is_err());
+    assert!(InputMap::from_config("button jump = Space").is_err());
+}
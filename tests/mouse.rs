@@ -0,0 +1,20 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+use korome::CursorState;
+use image::RgbaImage;
+
+#[test]
+#[ignore]
+fn cursor_state_without_window() {
+    let graphics = graphics();
+
+    assert_eq!(graphics.cursor_state(), CursorState::Normal);
+    graphics.set_cursor_state(CursorState::Grab).unwrap();
+    assert_eq!(graphics.cursor_state(), CursorState::Grab);
+    graphics.set_cursor_image(RgbaImage::new(8, 8), (0, 0)).unwrap();
+    graphics.reset_cursor_image();
+}
@@ -0,0 +1,149 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::{graphics, capture, pixel};
+use korome::{ParticleEmitter, Texture};
+use image::{RgbaImage, Rgba};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn spawns_at_its_rate_and_dies_of_age() {
+    let mut emitter = ParticleEmitter::new(10.).lifetime((1., 1.)).seed(7);
+
+    emitter.step(0.25);
+    // Two and a half particles, so the half waits for the next step
+    assert_eq!(emitter.len(), 2);
+    emitter.step(0.25);
+    assert_eq!(emitter.len(), 5);
+    emitter.step(0.6);
+    assert_eq!(emitter.len(), 11);
+
+    emitter.emitting = false;
+    // The first five have lived longer than their lifetime now
+    emitter.step(0.45);
+    assert_eq!(emitter.len(), 6);
+    emitter.step(1.);
+    assert!(emitter.is_empty());
+}
+
+#[test]
+fn particles_move_with_gravity() {
+    let mut emitter = ParticleEmitter::new(0.)
+        .pos((10., 20.))
+        .speed((100., 100.))
+        .angle((0., 0.))
+        .spin((2., 2.))
+        .gravity((0., -50.))
+        .lifetime((5., 5.));
+
+    emitter.burst(3);
+    emitter.step(0.5);
+    for p in emitter.particles() {
+        assert_eq!(p.velocity, (100., -25.));
+        assert!(close(p.pos.0, 60.) && close(p.pos.1, 7.5));
+        assert!(close(p.rotation, 1.) && close(p.life(), 0.1));
+    }
+}
+
+#[test]
+fn ranges_and_max_particles() {
+    let mut emitter = ParticleEmitter::new(1000.)
+        .area((5., 2.))
+        .lifetime((1., 3.))
+        .speed((10., 20.))
+        .angle((0.5, 1.))
+        .max_particles(100)
+        .seed(42);
+
+    emitter.step(1.);
+    assert_eq!(emitter.len(), 100);
+    emitter.burst(10);
+    assert_eq!(emitter.len(), 100);
+
+    for p in emitter.particles() {
+        let speed = (p.velocity.0.hypot(p.velocity.1) - 10.) / 10.;
+        let angle = (p.velocity.1.atan2(p.velocity.0) - 0.5) * 2.;
+        let spawned = (p.pos.0 - p.velocity.0 * p.age, p.pos.1 - p.velocity.1 * p.age);
+        assert!(p.lifetime >= 1. && p.lifetime < 3.);
+        assert!(speed > -1e-4 && speed < 1. + 1e-4);
+        assert!(angle > -1e-4 && angle < 1. + 1e-4);
+        assert!(spawned.0.abs() <= 5. + 1e-3 && spawned.1.abs() <= 2. + 1e-3);
+    }
+
+    // The same seed gives the same particles
+    let a = ParticleEmitter::new(0.).seed(3);
+    let mut b = a.clone();
+    let mut a = a;
+    a.burst(5);
+    b.burst(5);
+    assert_eq!(a.particles(), b.particles());
+}
+
+#[test]
+fn colour_and_size_over_life() {
+    let emitter = ParticleEmitter::new(0.);
+    assert_eq!(emitter.colour_at(0.5), [1., 1., 1., 1.]);
+    assert_eq!(emitter.size_at(0.5), 1.);
+
+    let emitter = emitter
+        .colour_over_life(vec![(0., [1., 0., 0., 1.]), (0.5, [0., 1., 0., 1.]), (1., [0., 0., 1., 0.])])
+        .size_over_life(vec![(0.2, 1.), (1., 3.)]);
+
+    assert_eq!(emitter.colour_at(0.), [1., 0., 0., 1.]);
+    assert_eq!(emitter.colour_at(0.25), [0.5, 0.5, 0., 1.]);
+    assert_eq!(emitter.colour_at(0.75), [0., 0.5, 0.5, 0.5]);
+    assert_eq!(emitter.colour_at(1.), [0., 0., 1., 0.]);
+    assert_eq!(emitter.size_at(0.), 1.);
+    assert_eq!(emitter.size_at(0.6), 2.);
+    assert_eq!(emitter.size_at(2.), 3.);
+}
+
+#[test]
+fn particles_look_as_they_are_through_their_life() {
+    let mut emitter = ParticleEmitter::new(0.)
+        .lifetime((2., 2.))
+        .colour_over_life(vec![(0., [0., 1., 0., 1.]), (1., [0., 0., 1., 0.])])
+        .size_over_life(vec![(0., 2.), (1., 4.)]);
+    emitter.burst(3);
+    assert!(emitter.particles().iter().all(|p| emitter.appearance(p) == (2., [0., 1., 0., 1.])));
+
+    // Halfway through its two seconds
+    let mut particle = emitter.particles()[0];
+    particle.age = 1.;
+    assert_eq!(emitter.appearance(&particle), (3., [0., 0.5, 0.5, 0.5]));
+}
+
+#[test]
+fn particles_without_a_lifetime_are_at_their_end() {
+    let mut emitter = ParticleEmitter::new(0.)
+        .lifetime((0., 0.))
+        .colour_over_life(vec![(0., [0., 1., 0., 1.]), (1., [0., 0., 1., 0.])]);
+    emitter.burst(2);
+
+    let particle = emitter.particles()[0];
+    assert_eq!(particle.life(), 1.);
+    assert_eq!(emitter.appearance(&particle), (1., [0., 0., 1., 0.]));
+}
+
+#[test]
+#[ignore]
+fn particles_are_drawn_in_their_colour() {
+    let graphics = graphics();
+    let texture = Texture::new(&graphics, RgbaImage::from_pixel(4, 4, Rgba{data: [255, 255, 255, 255]})).unwrap();
+    let mut emitter = ParticleEmitter::new(0.)
+        .pos((-16., 16.))
+        .speed((0., 0.))
+        .colour_over_life(vec![(0., [0., 1., 0., 1.])])
+        .size_over_life(vec![(0., 2.)]);
+    emitter.burst(20);
+
+    let frame = capture(&graphics, |drawer| emitter.draw(&texture, drawer));
+
+    assert_eq!(pixel(&frame, 16, 16), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 48, 48), [0, 0, 0, 255]);
+}
@@ -0,0 +1,78 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+use korome::{Replay, ReplayError, FrameInfo, Drawer, VirtualKeyCode, MouseButton, GamepadButton, replay_until_closed};
+
+const RECORDING: &str = "korome recording 1
+frame 0.016
+mouse 10 -20 0 0 inside
+key press W
+down W
+pad connected 0 Some Gamepad
+frame 0.016
+mouse 12.5 -20 2.5 0 inside
+button press MouseLeft
+text 104 233
+down MouseLeft W
+pad button 0 press A
+frame 0.5
+mouse 12.5 -20 0 0 outside
+scroll 0 -1 0 0
+key release W
+down MouseLeft
+pad axis 0 LeftStickX -0.25
+";
+
+#[test]
+fn parse_recording() {
+    let replay = Replay::parse(RECORDING).unwrap();
+
+    assert_eq!(replay.len(), 3);
+    assert!((replay.duration() - 0.532).abs() < 1e-5);
+}
+
+#[test]
+fn recording_round_trip() {
+    let replay = Replay::parse(RECORDING).unwrap();
+    let written = replay.to_string();
+
+    assert_eq!(written, RECORDING);
+    assert_eq!(Replay::parse(&written).unwrap().to_string(), written);
+}
+
+#[test]
+fn invalid_recordings() {
+    assert!(Replay::parse("frame 0.1").is_err());
+    assert!(Replay::parse("korome recording 1\nkey press W").is_err());
+    match Replay::parse("korome recording 1\nframe 0.1\nkey press NotAKey") {
+        Err(ReplayError::ParseError(3, _)) => (),
+        other => panic!("expected a parse error on line 3, got {:?}", other)
+    }
+    assert!(Replay::parse("korome recording 1\nframe 0.1\nbutton press W").is_err());
+    assert!(Replay::parse("korome recording 1\nframe 0.1\npad button 0 press Trigger").is_err());
+}
+
+#[test]
+#[ignore]
+fn replay_headless() {
+    let mut frames = Vec::new();
+
+    replay_until_closed(graphics(), |info: &FrameInfo, _: &mut Drawer| {
+        frames.push((
+            info.delta,
+            info.is_down(&VirtualKeyCode::W),
+            info.is_mouse_down(&MouseButton::Left),
+            info.get_text_input().to_owned(),
+            info.gamepads().is_down(0, GamepadButton::A)
+        ));
+    }, Replay::parse(RECORDING).unwrap());
+
+    assert_eq!(frames, [
+        (0.016, true, false, String::new(), false),
+        (0.016, true, true, "hé".to_owned(), true),
+        (0.5, false, true, String::new(), true)
+    ]);
+}
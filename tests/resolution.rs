@@ -0,0 +1,94 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::{graphics, capture, pixel};
+use korome::{VirtualResolution, ScaleMode, Quad};
+use korome::glium::Rect;
+
+fn rect(left: u32, bottom: u32, width: u32, height: u32) -> Rect {
+    Rect{left, bottom, width, height}
+}
+
+#[test]
+fn fit_letterboxes() {
+    let res = VirtualResolution::new(800., 600., ScaleMode::Fit);
+
+    assert_eq!(res.viewport(800, 600), rect(0, 0, 800, 600));
+    // Bars on the sides of a wider window
+    assert_eq!(res.viewport(1920, 1080), rect(240, 0, 1440, 1080));
+    // Bars at the top and bottom of a taller window
+    assert_eq!(res.viewport(400, 600), rect(0, 150, 400, 300));
+}
+
+#[test]
+fn stretch_fills_window() {
+    let res = VirtualResolution::new(800., 600., ScaleMode::Stretch);
+
+    assert_eq!(res.viewport(1920, 1080), rect(0, 0, 1920, 1080));
+    assert_eq!(res.scale((1600, 300)), (2., 0.5));
+}
+
+#[test]
+fn pixel_perfect_uses_whole_scales() {
+    let res = VirtualResolution::new(320., 180., ScaleMode::PixelPerfect);
+
+    assert_eq!(res.viewport(1920, 1080), rect(0, 0, 1920, 1080));
+    assert_eq!(res.viewport(1000, 700), rect(20, 80, 960, 540));
+    assert_eq!(res.scale((1000, 700)), (3., 3.));
+    // Smaller than the virtual resolution, so it's scaled down like `Fit`
+    assert_eq!(res.viewport(160, 180), rect(0, 45, 160, 90));
+}
+
+#[test]
+fn mouse_to_logical() {
+    let res = VirtualResolution::new(800., 600., ScaleMode::Fit);
+    let window = (1920, 1080);
+
+    assert_eq!(res.to_logical(window, (960., 540.)), (0., 0.));
+    // Top-left corner of the viewport
+    assert_eq!(res.to_logical(window, (240., 0.)), (-400., 300.));
+    assert_eq!(res.to_logical(window, (1680., 1080.)), (400., -300.));
+    // In the bars
+    assert!(res.to_logical(window, (0., 540.)).0 < -400.);
+}
+
+#[test]
+fn nothing_is_drawn_without_a_size() {
+    let res = VirtualResolution::new(800., 600., ScaleMode::Fit);
+    // A minimised window
+    assert_eq!(res.viewport(0, 0), rect(0, 0, 0, 0));
+    assert_eq!(res.scale((0, 0)), (1., 1.));
+    assert_eq!(res.to_logical((0, 0), (10., 10.)), (0., 0.));
+    assert_eq!(res.viewport(400, 0), rect(200, 0, 0, 0));
+
+    let empty = VirtualResolution::new(0., 600., ScaleMode::PixelPerfect);
+    assert_eq!(empty.viewport(800, 600), rect(400, 300, 0, 0));
+    assert_eq!(empty.scale((800, 600)), (1., 1.));
+    assert_eq!(empty.to_logical((800, 600), (10., 10.)), (0., 0.));
+
+    // Stretching a zero-sized resolution still has finite scales
+    let stretched = VirtualResolution::new(0., 0., ScaleMode::Stretch);
+    assert_eq!(stretched.scale((800, 600)), (1., 1.));
+    assert_eq!(stretched.to_logical((800, 600), (10., 10.)), (0., 0.));
+}
+
+#[test]
+#[ignore]
+fn virtual_resolution_letterboxes() {
+    let mut graphics = graphics();
+    // Half as high as the frame, so there are bars at the top and bottom
+    graphics.set_virtual_resolution(Some(VirtualResolution::new(32., 16., ScaleMode::Fit)));
+    assert_eq!(graphics.get_h_size(), (16., 8.));
+
+    let quad = Quad::new_rect(&graphics, [1., 0., 0., 1.], 8., 8.).unwrap();
+    let frame = capture(&graphics, |drawer| {
+        drawer.clear(0., 0., 1.);
+        quad.drawer().pos((12., 4.)).draw(drawer);
+    });
+
+    assert_eq!(pixel(&frame, 32, 4), [0, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 8, 40), [0, 0, 255, 255]);
+    assert_eq!(pixel(&frame, 56, 24), [255, 0, 0, 255]);
+}
@@ -0,0 +1,60 @@
+extern crate korome;
+
+use korome::*;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+struct Logged(&'static str, Log);
+
+impl Logged{
+    fn boxed(name: &'static str, log: &Log) -> Box<dyn Scene>{
+        Box::new(Logged(name, log.clone()))
+    }
+    fn log(&self, what: &str){
+        self.1.borrow_mut().push(format!("{} {}", what, self.0));
+    }
+}
+
+impl Scene for Logged{
+    fn frame(&mut self, _: &FrameInfo, _: &mut Drawer) -> SceneUpdate{
+        SceneUpdate::Nothing
+    }
+    fn enter(&mut self){ self.log("enter") }
+    fn exit(&mut self){ self.log("exit") }
+    fn pause(&mut self){ self.log("pause") }
+    fn resume(&mut self){ self.log("resume") }
+}
+
+#[test]
+fn hooks_are_called_in_order() {
+    let log = Log::default();
+
+    let mut stack = SceneStack::new(Logged::boxed("menu", &log));
+    stack.replace(Logged::boxed("level", &log));
+    stack.push(Logged::boxed("pause", &log));
+    assert_eq!(stack.len(), 2);
+    stack.pop();
+    stack.pop();
+    assert!(stack.is_empty());
+
+    assert_eq!(*log.borrow(), [
+        "enter menu",
+        "exit menu",
+        "enter level",
+        "pause level",
+        "enter pause",
+        "exit pause",
+        "resume level",
+        "exit level",
+    ]);
+}
+
+#[test]
+fn popping_empty_stack() {
+    let mut stack = SceneStack::default();
+    assert!(stack.pop().is_none());
+    assert!(stack.is_empty());
+}
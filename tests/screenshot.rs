@@ -0,0 +1,47 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+use korome::{GameUpdate, FrameInfo, Drawer, Replay, replay_until_closed};
+use std::env;
+use std::fs;
+
+#[test]
+#[ignore]
+fn screenshots_and_recordings_report_failures() {
+    let dir = env::temp_dir().join("korome_recording");
+    let shot = env::temp_dir().join("korome_shot.png");
+    let _ = fs::remove_dir_all(&dir);
+    // A directory can't be made inside a file, nor a file saved in a directory that doesn't exist
+    let bad_dir = shot.join("recording");
+    let bad_shot = dir.join("missing").join("shot.png");
+
+    let updates = [
+        GameUpdate::StartRecording(dir.clone()),
+        GameUpdate::StopRecording,
+        GameUpdate::Screenshot(shot.clone()),
+        GameUpdate::StartRecording(bad_dir.clone()),
+        GameUpdate::Screenshot(bad_shot.clone()),
+        GameUpdate::Nothing
+    ];
+    let mut errors = Vec::new();
+    let mut frame = 0;
+    replay_until_closed(graphics(), |info: &FrameInfo, _: &mut Drawer| {
+        errors.push(info.get_update_errors().iter().map(|e| e.update.clone()).collect::<Vec<_>>());
+        frame += 1;
+        updates[frame - 1].clone()
+    }, Replay::parse(&format!("korome recording 1\n{}", "frame 0.016\n".repeat(6))).unwrap());
+
+    // Only the frame after starting was recorded
+    assert!(dir.join("frame00000.png").exists());
+    assert!(!dir.join("frame00001.png").exists());
+    assert!(shot.exists());
+    // Each failure shows up on the next frame
+    assert_eq!(errors, [
+        vec![], vec![], vec![], vec![],
+        vec![GameUpdate::StartRecording(bad_dir)],
+        vec![GameUpdate::Screenshot(bad_shot)]
+    ]);
+}
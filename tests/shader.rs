@@ -0,0 +1,55 @@
+extern crate korome;
+
+use korome::{Uniform, Uniforms};
+
+#[test]
+fn uniforms_keep_their_types() {
+    let uniforms = Uniforms::new()
+        .with("time", 1.5)
+        .with("offset", [1., 2.])
+        .with("tint", [1., 0.5, 0.25])
+        .with("colour", [0., 0., 0., 1.])
+        .with("frame", 3)
+        .with("grading", [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]]);
+
+    assert_eq!(uniforms.len(), 6);
+    match uniforms.get("time") {
+        Some(&Uniform::Float(t)) => assert_eq!(t, 1.5),
+        other => panic!("time is {:?}", other)
+    }
+    match uniforms.get("offset") {
+        Some(&Uniform::Vec2(v)) => assert_eq!(v, [1., 2.]),
+        other => panic!("offset is {:?}", other)
+    }
+    assert!(matches!(uniforms.get("tint"), Some(&Uniform::Vec3(_))));
+    assert!(matches!(uniforms.get("colour"), Some(&Uniform::Vec4(_))));
+    // Integers stay integers rather than becoming floats
+    assert!(matches!(uniforms.get("frame"), Some(&Uniform::Int(3))));
+    assert!(matches!(uniforms.get("grading"), Some(&Uniform::Mat4(m)) if m[3][3] == 1.));
+    assert!(uniforms.get("missing").is_none());
+}
+
+#[test]
+fn setting_a_uniform_again_replaces_it() {
+    let mut uniforms = Uniforms::new().with("a", 1.).with("b", 2.).with("c", 3.);
+    uniforms.set("b", 4);
+
+    // It keeps its place and takes the new type
+    let names: Vec<&str> = uniforms.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert!(matches!(uniforms.get("b"), Some(&Uniform::Int(4))));
+    assert_eq!(uniforms.len(), 3);
+}
+
+#[test]
+fn clones_are_changed_separately() {
+    let original = Uniforms::new().with("a", 1.);
+    let mut copy = original.clone();
+    copy.set("a", 2.);
+    copy.set("b", 3.);
+
+    assert!(matches!(original.get("a"), Some(&Uniform::Float(f)) if f == 1.));
+    assert_eq!(original.len(), 1);
+    assert!(matches!(copy.get("a"), Some(&Uniform::Float(f)) if f == 2.));
+    assert!(Uniforms::new().is_empty());
+}
@@ -0,0 +1,95 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::{graphics, capture, pixel};
+use korome::{triangulate, ShapeDrawer};
+
+fn area(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.
+}
+
+fn total_area(polygon: &[[f32; 2]]) -> f32 {
+    triangulate(polygon).iter()
+        .map(|&[a, b, c]| {
+            let area = area(polygon[a], polygon[b], polygon[c]);
+            assert!(area >= 0., "triangle goes clockwise");
+            area
+        })
+        .sum()
+}
+
+#[test]
+fn square() {
+    let square = [[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+
+    assert_eq!(triangulate(&square).len(), 2);
+    assert_eq!(total_area(&square), 1.);
+}
+
+#[test]
+fn concave_polygon() {
+    // An arrow pointing right, with the notch at (1, 1)
+    let arrow = [[0., 0.], [3., 1.], [0., 2.], [1., 1.]];
+
+    assert_eq!(triangulate(&arrow).len(), 2);
+    assert!((total_area(&arrow) - 2.).abs() < 1e-5);
+
+    let l_shape = [[0., 0.], [2., 0.], [2., 1.], [1., 1.], [1., 3.], [0., 3.]];
+    assert_eq!(triangulate(&l_shape).len(), 4);
+    assert!((total_area(&l_shape) - 4.).abs() < 1e-5);
+}
+
+#[test]
+fn clockwise_polygon() {
+    let clockwise = [[0., 1.], [1., 1.], [1., 0.], [0., 0.]];
+
+    assert!((total_area(&clockwise) - 1.).abs() < 1e-5);
+}
+
+#[test]
+fn too_few_points() {
+    assert!(triangulate(&[[0., 0.], [1., 1.]]).is_empty());
+}
+
+// Whether any of the triangles of a shape covers the point
+fn covers(triangles: &[[f32; 2]], p: [f32; 2]) -> bool {
+    triangles.chunks(3).any(|t| {
+        let (a, b, c) = (area(t[0], t[1], p), area(t[1], t[2], p), area(t[2], t[0], p));
+        (a >= 0. && b >= 0. && c >= 0.) || (a <= 0. && b <= 0. && c <= 0.)
+    })
+}
+
+#[test]
+fn shapes_are_filled_or_outlined() {
+    let filled = ShapeDrawer::circle(10.).triangles();
+    assert!(covers(&filled, [0., 0.]) && covers(&filled, [0., 9.5]));
+    assert!(!covers(&filled, [0., 10.5]));
+
+    // The outline is centred on the edge, so its inside is left empty
+    let outlined = ShapeDrawer::circle(10.).outline(2.).triangles();
+    assert!(!covers(&outlined, [0., 0.]) && !covers(&outlined, [0., 8.5]));
+    assert!(covers(&outlined, [0., 9.5]) && covers(&outlined, [-10.5, 0.]));
+
+    let line = ShapeDrawer::line((-32., -16.), (32., -16.)).outline(4.).triangles();
+    assert!(covers(&line, [0., -16.]) && covers(&line, [20., -14.5]));
+    assert!(!covers(&line, [0., -19.]) && !covers(&line, [40., -16.]));
+}
+
+#[test]
+#[ignore]
+fn shapes_are_drawn() {
+    let graphics = graphics();
+    let frame = capture(&graphics, |drawer| {
+        ShapeDrawer::circle(10.).pos((-16., 16.)).colour([1., 0., 0., 1.]).draw(drawer);
+        ShapeDrawer::circle(10.).pos((16., 16.)).outline(2.).draw(drawer);
+        ShapeDrawer::line((-32., -16.), (32., -16.)).outline(4.).colour([0., 1., 0., 1.]).draw(drawer);
+    });
+
+    // The middle of the filled circle, but not of the outlined one
+    assert_eq!(pixel(&frame, 16, 16), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 48, 16), [0, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 58, 16), [255, 255, 255, 255]);
+    assert_eq!(pixel(&frame, 32, 48), [0, 255, 0, 255]);
+}
@@ -0,0 +1,119 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::{graphics, capture, pixel};
+use korome::{Font, FontError};
+
+// Glyphs without a size only have an advance, so no pages are needed
+const FONT: &str = r#"info face="Test Font" size=-10 bold=0
+common lineHeight=12 base=9 scaleW=64 scaleH=64 pages=1
+page id=0 file="test.png"
+chars count=3
+char id=65 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=6 page=0
+char id=66 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=5 page=0
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=3 page=0
+kernings count=1
+kerning first=65 second=66 amount=-1
+"#;
+
+fn font() -> Font {
+    Font::from_bmfont(FONT, &[]).unwrap()
+}
+
+#[test]
+fn bmfont_is_parsed() {
+    let font = font();
+    // The size from `info` is the default, even when it's negative like some tools write it
+    assert_eq!(font.text("A").size, 10.);
+    assert_eq!(font.text("A").measure(), (6., 12.));
+    // Characters the font doesn't have take up no room
+    assert_eq!(font.text("AZ").measure(), (6., 12.));
+
+    // Without `info` the line height is the size
+    let no_info = FONT.replace("info face=\"Test Font\" size=-10 bold=0", "");
+    assert_eq!(Font::from_bmfont(&no_info, &[]).unwrap().text("A").size, 12.);
+}
+
+#[test]
+fn invalid_bmfonts() {
+    let missing = FONT.replace(" xadvance=5", "");
+    match Font::from_bmfont(&missing, &[]) {
+        Err(FontError::BitmapFontError(6, _)) => (),
+        other => panic!("expected an error on line 6, got {:?}", other)
+    }
+    let visible = FONT.replace("char id=65 x=0 y=0 width=0 height=0", "char id=65 x=0 y=0 width=4 height=8");
+    match Font::from_bmfont(&visible, &[]) {
+        Err(FontError::BitmapFontError(5, "page doesn't exist")) => (),
+        other => panic!("expected a missing page on line 5, got {:?}", other)
+    }
+    let surrogate = FONT.replace("first=65", "first=55296");
+    assert!(Font::from_bmfont(&surrogate, &[]).is_err());
+}
+
+#[test]
+fn kerning_only_applies_within_words() {
+    let font = font();
+    assert_eq!(font.text("AB").measure(), (10., 12.));
+    assert_eq!(font.text("BA").measure(), (11., 12.));
+    assert_eq!(font.text("A B").measure(), (14., 12.));
+    // Everything scales with the size
+    assert_eq!(font.text("AB").size(20.).measure(), (20., 24.));
+}
+
+#[test]
+fn lines_are_laid_out_and_wrapped() {
+    let font = font();
+    assert_eq!(font.text("A\nAB").measure(), (10., 24.));
+
+    // "AB AB" is 23 wide, so a third word goes on its own line
+    assert_eq!(font.text("AB AB AB").wrap(25.).measure(), (23., 24.));
+    assert_eq!(font.text("AB AB AB").wrap(20.).measure(), (10., 36.));
+    // Words wider than the wrap width aren't broken up
+    assert_eq!(font.text("ABAB A").wrap(5.).measure(), (20., 24.));
+    assert_eq!(font.text("AB AB AB").measure(), (36., 12.));
+}
+
+// A hand-made TrueType font with 1000 units to the em, an ascent of 800 and a descent of 200.
+// 'A' is a square 500 units wide sitting on the baseline and 'B' a bar 500 units wide filling the whole em,
+// both advancing 600 units, and a space advances 250.
+const BOXES: &[u8] = include_bytes!("assets/boxes.ttf");
+
+fn boxes() -> Font {
+    Font::from_ttf_bytes(BOXES.to_vec()).unwrap()
+}
+
+#[test]
+fn truetype_fonts_are_laid_out_without_a_window() {
+    let font = boxes();
+    assert_eq!(font.text("A").size, 16.);
+    // The size is the height from the lowest descent to the highest ascent
+    assert_eq!(font.text("A").size(10.).measure(), (6., 10.));
+    assert_eq!(font.text("AB A").size(10.).measure(), (20.5, 10.));
+    assert_eq!(font.text("AB A").size(10.).wrap(15.).measure(), (12., 20.));
+    // Characters the font doesn't have are as wide as its missing glyph
+    assert_eq!(font.text("Z").size(10.).measure(), (5., 10.));
+
+    match Font::from_ttf_bytes(b"not a font".to_vec()) {
+        Err(FontError::InvalidFont) => (),
+        other => panic!("expected an invalid font, got {:?}", other)
+    }
+}
+
+#[test]
+#[ignore]
+fn truetype_glyphs_are_drawn_even_when_too_big_for_the_cache() {
+    let graphics = graphics();
+    let font = boxes();
+
+    // The top of the text is at the top of the frame, so the baseline is 32 pixels down, in the middle
+    let frame = capture(&graphics, |drawer| font.text("A").size(40.).pos((-32., 32.)).draw(drawer));
+    assert_eq!(pixel(&frame, 10, 22), [255, 255, 255, 255]);
+    assert_eq!(pixel(&frame, 30, 22), [0, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 10, 42), [0, 0, 0, 255]);
+
+    // A 4000 pixel bar is rasterised at a quarter of that and scaled up, covering the whole frame
+    let frame = capture(&graphics, |drawer| font.text("B").size(4000.).pos((-32., 32.)).draw(drawer));
+    assert!(frame.pixels().all(|p| p.data == [255, 255, 255, 255]));
+}
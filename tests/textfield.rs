@@ -0,0 +1,66 @@
+extern crate korome;
+
+use korome::{TextField, KeyRepeat, VirtualKeyCode};
+
+#[test]
+fn editing() {
+    let mut field = TextField::with_text("hé");
+    assert_eq!(field.cursor(), 3);
+
+    field.move_left();
+    field.insert_str("ø\tx");
+    assert_eq!(field.text(), "høxé");
+
+    field.backspace();
+    field.delete();
+    assert_eq!(field.text(), "hø");
+
+    field.home();
+    field.delete();
+    field.end();
+    field.backspace();
+    assert_eq!(field.text(), "");
+    field.backspace();
+    assert_eq!(field.take(), "");
+}
+
+#[test]
+fn character_limit() {
+    let mut field = TextField::new().max_chars(4);
+    field.insert_str("äbc");
+    field.insert_str("def");
+    assert_eq!(field.text(), "äbcd");
+
+    field.set_text("123456");
+    assert_eq!(field.text(), "1234");
+    assert_eq!(field.cursor(), 4);
+}
+
+#[test]
+fn key_repeat() {
+    use VirtualKeyCode::{Back, Left};
+
+    let mut repeat = KeyRepeat::new(0.5, 0.1);
+    assert_eq!(repeat.update_with_events(&[(true, Back)], 0.), [Back]);
+    assert!(repeat.update_with_events(&[], 0.4).is_empty());
+    assert_eq!(repeat.update_with_events(&[], 0.15), [Back]);
+    // The system's own repeated presses are ignored
+    assert_eq!(repeat.update_with_events(&[(true, Back)], 0.2), [Back, Back]);
+
+    assert_eq!(repeat.update_with_events(&[(true, Left)], 0.), [Left]);
+    assert!(repeat.update_with_events(&[(false, Back)], 0.3).is_empty());
+    assert!(repeat.update_with_events(&[(false, Left)], 0.).is_empty());
+    assert!(repeat.update_with_events(&[], 1.).is_empty());
+
+    // A long frame only repeats a few times, and the time after that is dropped
+    assert_eq!(repeat.update_with_events(&[(true, Back)], 0.), [Back]);
+    assert_eq!(repeat.update_with_events(&[], 10.), [Back; 4]);
+    assert!(repeat.update_with_events(&[], 0.05).is_empty());
+    assert_eq!(repeat.update_with_events(&[], 0.05), [Back]);
+}
+
+#[test]
+#[should_panic]
+fn key_repeat_needs_an_interval() {
+    KeyRepeat::new(0.5, 0.);
+}
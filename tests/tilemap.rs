@@ -0,0 +1,250 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::{sized_graphics, capture, pixel};
+use korome::{TileMap, TileMapError, Tile, LayerKind, ObjectShape, Property, Camera};
+use image::{RgbaImage, Rgba};
+use std::env;
+use std::path::Path;
+
+const TMX: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="8" backgroundcolor="#80ff0000">
+ <properties>
+  <property name="gravity" type="float" value="9.8"/>
+  <property name="title" value="First"/>
+ </properties>
+ <tileset firstgid="1" name="ground" tilewidth="16" tileheight="8" tilecount="4" columns="2" spacing="1" margin="2">
+  <image source="ground.png" width="36" height="21"/>
+  <tile id="2">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer name="floor" width="3" height="2">
+  <data encoding="csv">
+1,2,0,
+0,2147483651,4
+</data>
+ </layer>
+ <group name="details" opacity="0.5" offsetx="4">
+  <layer name="decoration" width="3" height="2" visible="0" offsety="2">
+   <data encoding="base64" compression="zlib">eJxjZGBgYGJAAGYGhgYWIA0AAwAAiw==</data>
+  </layer>
+ </group>
+ <objectgroup name="things">
+  <object id="1" name="spawn" type="player" x="8" y="4">
+   <point/>
+  </object>
+  <object id="2" x="0" y="0" width="10" height="5" rotation="45">
+   <properties>
+    <property name="colour" type="color" value="#ff00ff00"/>
+   </properties>
+  </object>
+  <object id="3" x="1" y="2">
+   <polygon points="0,0 4,0 4,3"/>
+  </object>
+ </objectgroup>
+</map>
+"##;
+
+const JSON: &str = r##"{
+ "type": "map", "orientation": "orthogonal", "infinite": false,
+ "width": 3, "height": 2, "tilewidth": 16, "tileheight": 8,
+ "properties": [{"name": "level", "type": "int", "value": 3}],
+ "tilesets": [{
+  "firstgid": 1, "name": "ground", "tilewidth": 16, "tileheight": 8, "tilecount": 4, "columns": 2,
+  "image": "ground.png", "imagewidth": 32, "imageheight": 16,
+  "tiles": [{"id": 0, "properties": [{"name": "name", "type": "string", "value": "grass"}]}]
+ }],
+ "layers": [
+  {"type": "tilelayer", "name": "gzip", "width": 3, "height": 2, "visible": true, "opacity": 1,
+   "encoding": "base64", "compression": "gzip", "data": "H4sIAAAAAAACA2NkYGBgYkAAZgaGBhYgDQCt+SFYGAAAAA=="},
+  {"type": "group", "name": "group", "visible": false, "layers": [
+   {"type": "tilelayer", "name": "plain", "width": 3, "height": 2, "visible": true,
+    "encoding": "base64", "data": "AQAAAAIAAAAAAAAAAAAAAAMAAIAEAAAA"}
+  ]},
+  {"type": "imagelayer", "name": "sky", "image": "sky.png"},
+  {"type": "objectgroup", "name": "things", "objects": [
+   {"id": 5, "name": "path", "class": "route", "x": 2, "y": 3, "width": 0, "height": 0, "rotation": 0, "visible": true,
+    "polyline": [{"x": 0, "y": 0}, {"x": 8, "y": 8}]},
+   {"id": 6, "name": "crate", "x": 0, "y": 8, "width": 16, "height": 8, "gid": 1073741826, "visible": true}
+  ]}
+ ]
+}"##;
+
+fn tiles(map: &TileMap, layer: usize) -> Vec<Option<u32>> {
+    (0..2).flat_map(|row| (0..3).map(move |column| (column, row)))
+        .map(|(column, row)| map.tile(layer, column, row).map(|t| t.gid))
+        .collect()
+}
+
+#[test]
+fn tmx_layers_objects_and_properties() {
+    let map = TileMap::parse_tmx(TMX, Path::new("maps")).unwrap();
+
+    assert_eq!((map.width, map.height, map.tile_width, map.tile_height), (3, 2, 16, 8));
+    assert_eq!(map.background, Some([1., 0., 0., 128. / 255.]));
+    assert_eq!(map.properties["gravity"], Property::Float(9.8));
+    assert_eq!(map.properties["title"].as_str(), Some("First"));
+
+    let tileset = &map.tilesets[0];
+    assert_eq!((tileset.spacing, tileset.margin, tileset.columns), (1, 2, 2));
+    assert_eq!(tileset.image.as_ref().unwrap().source, Path::new("maps/ground.png"));
+    assert_eq!(map.tile_properties(3).unwrap()["solid"], Property::Bool(true));
+    assert!(map.tile_properties(1).is_none());
+    assert!(map.tileset(5).is_none());
+
+    assert_eq!(map.layers.len(), 3);
+    assert_eq!(tiles(&map, 0), [Some(1), Some(2), None, None, Some(3), Some(4)]);
+    assert_eq!(map.tile(0, 1, 1), Some(Tile{gid: 3, flipped_horizontally: true, flipped_vertically: false, flipped_diagonally: false}));
+    assert_eq!(tiles(&map, 1), tiles(&map, 0));
+
+    // Layers in groups get the group's opacity and offset
+    let decoration = map.layer_by_name("decoration").unwrap();
+    assert!(!decoration.visible);
+    assert_eq!((decoration.opacity, decoration.offset), (0.5, (4., 2.)));
+
+    let objects = match map.layers[2].kind {
+        LayerKind::Objects(ref objects) => objects,
+        _ => panic!("not an object layer")
+    };
+    assert_eq!((&*objects[0].name, &*objects[0].kind, objects[0].pos), ("spawn", "player", (8., 4.)));
+    assert_eq!(objects[0].shape, ObjectShape::Point);
+    assert_eq!((objects[1].size, objects[1].rotation), ((10., 5.), 45.));
+    assert_eq!(objects[1].properties["colour"], Property::Colour([0., 1., 0., 1.]));
+    assert_eq!(objects[2].shape, ObjectShape::Polygon(vec![(0., 0.), (4., 0.), (4., 3.)]));
+    assert_eq!(map.map_to_world((-24., 8.), objects[0].pos), (-16., 4.));
+}
+
+#[test]
+fn json_maps_and_compression() {
+    let map = TileMap::parse_json(JSON, Path::new("")).unwrap();
+
+    assert_eq!(map.properties["level"], Property::Int(3));
+    assert_eq!(map.tile_properties(1).unwrap()["name"].as_str(), Some("grass"));
+    // The image layer is skipped
+    assert_eq!(map.layers.len(), 3);
+    assert_eq!(tiles(&map, 0), [Some(1), Some(2), None, None, Some(3), Some(4)]);
+    assert_eq!(tiles(&map, 1), tiles(&map, 0));
+    assert!(!map.layers[1].visible);
+
+    let objects = match map.layer_by_name("things").unwrap().kind {
+        LayerKind::Objects(ref objects) => objects,
+        _ => panic!("not an object layer")
+    };
+    assert_eq!(&*objects[0].kind, "route");
+    assert_eq!(objects[0].shape, ObjectShape::Polyline(vec![(0., 0.), (8., 8.)]));
+    assert_eq!(objects[1].tile, Some(Tile{gid: 2, flipped_horizontally: false, flipped_vertically: true, flipped_diagonally: false}));
+}
+
+#[test]
+fn unsupported_maps_are_rejected() {
+    let isometric = TMX.replace("orthogonal", "isometric");
+    match TileMap::parse_tmx(&isometric, Path::new("")) {
+        Err(TileMapError::InvalidMap(_)) => (),
+        other => panic!("isometric map gave {:?}", other)
+    }
+    let short = TMX.replace("0,2147483651,4", "0,2147483651");
+    assert!(TileMap::parse_tmx(&short, Path::new("")).is_err());
+    assert!(TileMap::parse_json("{\"width\": 3}", Path::new("")).is_err());
+    assert!(TileMap::parse_tmx("<map", Path::new("")).is_err());
+
+    // Tiles need a size to be cut out of the tileset's image
+    let flat = TMX.replace(r#"name="ground" tilewidth="16" tileheight="8""#, r#"name="ground" tilewidth="16" tileheight="0""#);
+    match TileMap::parse_tmx(&flat, Path::new("")) {
+        Err(TileMapError::InvalidMap(_)) => (),
+        other => panic!("zero tile height gave {:?}", other)
+    }
+    let thin = JSON.replace(r#""name": "ground", "tilewidth": 16"#, r#""name": "ground", "tilewidth": 0"#);
+    assert!(TileMap::parse_json(&thin, Path::new("")).is_err());
+
+    let colour = TMX.replace("#ff00ff00", "green");
+    match TileMap::parse_tmx(&colour, Path::new("")) {
+        Err(TileMapError::InvalidMap(_)) => (),
+        other => panic!("invalid colour gave {:?}", other)
+    }
+    // Colours that haven't been picked are transparent
+    let unset = TileMap::parse_tmx(&TMX.replace("#ff00ff00", ""), Path::new("")).unwrap();
+    let objects = match unset.layer_by_name("things").unwrap().kind {
+        LayerKind::Objects(ref objects) => objects,
+        _ => panic!("not an object layer")
+    };
+    assert_eq!(objects[1].properties["colour"], Property::Colour([0.; 4]));
+}
+
+#[test]
+fn only_visible_tiles_are_culled_in() {
+    let map = TileMap::parse_tmx(TMX, Path::new("")).unwrap();
+    let camera = Camera::new();
+
+    // The whole 48x16 map fits in the view
+    assert_eq!(map.visible_tiles((-24., 8.), &camera, (50., 50.)), (0..3, 0..2));
+    // Only the top left tile is inside a 16x8 view centred on it
+    assert_eq!(map.visible_tiles((-8., 4.), &camera, (8., 4.)), (0..1, 0..1));
+    // Moving the camera to the right shows the second column as well
+    let moved = Camera{pos: (4., 0.), .. camera};
+    assert_eq!(map.visible_tiles((-8., 4.), &moved, (8., 4.)), (0..2, 0..1));
+    // Entirely off the map
+    let (columns, rows) = map.visible_tiles((500., 500.), &camera, (8., 4.));
+    assert!(columns.start == columns.end && rows.start == rows.end);
+
+    assert_eq!(map.tile_at((-24., 8.), (-10., 5.)), Some((0, 0)));
+    assert_eq!(map.tile_at((-24., 8.), (20., -7.)), Some((2, 1)));
+    assert_eq!(map.tile_at((-24., 8.), (30., 0.)), None);
+    assert_eq!(map.tile_centre((-24., 8.), (2, 1)), (16., -4.));
+}
+
+#[test]
+fn flipped_tiles_show_their_texture_flipped() {
+    // The corners from the top left, going round clockwise
+    let corners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+    let coords = |raw: u32| {
+        let tile = Tile::from_raw(raw).unwrap();
+        corners.iter().map(|&corner| tile.tex_coords(corner)).collect::<Vec<_>>()
+    };
+
+    // Textures go up from the bottom left, unlike Tiled
+    assert_eq!(coords(1), [[0., 1.], [1., 1.], [1., 0.], [0., 0.]]);
+    assert_eq!(coords(0x8000_0001), [[1., 1.], [0., 1.], [0., 0.], [1., 0.]]);
+    assert_eq!(coords(0x4000_0001), [[0., 0.], [1., 0.], [1., 1.], [0., 1.]]);
+    // Flipped diagonally, the top right corner shows the bottom left of the texture
+    assert_eq!(coords(0x2000_0001), [[0., 1.], [0., 0.], [1., 0.], [1., 1.]]);
+    // Which together with a horizontal flip turns it a quarter clockwise
+    assert_eq!(coords(0xA000_0001), [[0., 0.], [0., 1.], [1., 1.], [1., 0.]]);
+}
+
+#[test]
+#[ignore]
+fn tilemap_is_drawn_with_flips() {
+    let graphics = sized_graphics(64, 16);
+    // A red tile and a tile that's green on its left half and blue on its right
+    let tiles = RgbaImage::from_fn(32, 16, |x, _| Rgba{data: match x {
+        0..=15 => [255, 0, 0, 255],
+        16..=23 => [0, 255, 0, 255],
+        _ => [0, 0, 255, 255]
+    }});
+    let dir = env::temp_dir();
+    tiles.save(dir.join("korome_tiles.png")).unwrap();
+
+    let tmx = r#"<map orientation="orthogonal" width="4" height="1" tilewidth="16" tileheight="16">
+        <tileset firstgid="1" tilewidth="16" tileheight="16" tilecount="2" columns="2">
+            <image source="korome_tiles.png" width="32" height="16"/>
+        </tileset>
+        <layer name="tiles" width="4" height="1"><data encoding="csv">1,2,2147483650,0</data></layer>
+    </map>"#;
+    let mut map = TileMap::parse_tmx(tmx, &dir).unwrap();
+    map.load_textures(&graphics).unwrap();
+
+    let frame = capture(&graphics, |drawer| map.drawer().pos((-32., 8.)).draw(drawer));
+
+    assert_eq!(pixel(&frame, 8, 8), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 20, 8), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 28, 8), [0, 0, 255, 255]);
+    // The third tile is flipped horizontally
+    assert_eq!(pixel(&frame, 36, 8), [0, 0, 255, 255]);
+    assert_eq!(pixel(&frame, 44, 8), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 56, 8), [0, 0, 0, 255]);
+}
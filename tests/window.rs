@@ -0,0 +1,19 @@
+extern crate korome;
+extern crate image;
+
+mod common;
+
+use common::graphics;
+
+#[test]
+#[ignore]
+fn window_changes_without_window() {
+    let mut graphics = graphics();
+
+    graphics.set_title("Nothing to see");
+    graphics.set_fullscreen(true).unwrap();
+    assert!(!graphics.is_fullscreen());
+    // There's no window to resize, so the size stays as it was made
+    graphics.set_size(128, 32);
+    assert_eq!(graphics.get_h_size(), (32., 32.));
+}